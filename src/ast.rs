@@ -1,14 +1,168 @@
-use crate::instruction::{Instruction, Simple, Tele, Level, Image, validate, conditions};
-use crate::statement::{Statement, Target};
-use crate::token::{Verb, Property, Noun};
-use crate::error_handler::{throw_error, ErrorType, throw_error_str};
+use crate::instruction::{Instruction, Simple, Complex, Tele, Level, Image, validate, conditions, is_reserved, subject};
+use crate::statement::{Statement, Target, Combinator, target_spelling};
+use crate::token::{Token, Verb, Property, Noun, spelling};
+use crate::error_handler::{BabaError, ErrorType, error, error_str, warn, set_span};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Parses a stream of statements into instructions.
-pub fn parse<'a>(statements: &'a [Statement], identifiers: &HashMap<usize, String>) -> Vec<Instruction> {
-    let (inner, _inner_last) = parse_inner(statements, None, identifiers);
-    inner
+pub fn parse(statements: &[Statement], identifiers: &HashMap<usize, String>) -> Result<Vec<Instruction>, BabaError> {
+    let (inner, _inner_last) = parse_inner(statements, None, identifiers)?;
+    set_span(None);
+    for (level, local) in stray_locals(&inner, &HashSet::new()) {
+        warn(
+            ErrorType::InstructionParserError, 
+            format!(
+                "Instruction following the end of LEVEL {0} refers to {1}, which is local to {0}. \
+                Should it be placed before {0} IS DONE?", 
+                level, local
+            ),
+            Some((&[level, local], identifiers))
+        );
+    }
+    Ok(inner)
+}
+
+/// Finds instructions directly following a LEVEL (or IMAGE constructor) which refer 
+/// to objects local to it. These were usually meant to be placed inside the LEVEL.
+/// 
+/// Returns the LEVEL and the local object of each such instruction. `known` holds
+/// the objects defined by the enclosing scopes.
+fn stray_locals(instructions: &[Instruction], known: &HashSet<usize>) -> Vec<(usize, usize)> {
+    let mut out = Vec::new();
+    let mut known = known.clone();
+    for (i, instruction) in instructions.iter().enumerate() {
+        let level = match instruction {
+            Instruction::Simple(simple) => {
+                known.extend(defined(simple));
+                continue;
+            },
+            Instruction::Tele(tele) => {
+                out.append(&mut stray_locals(&tele.instructions, &known));
+                continue;
+            },
+            Instruction::Level(level) => level,
+            Instruction::Image(image) => &image.constructor,
+            _ => continue
+        };
+        known.insert(level.identifier);
+        let mut locals = level.arguments.iter().copied().collect::<HashSet<usize>>();
+        for inner in level.instructions.iter() {
+            if let Instruction::Simple(simple) = inner {
+                locals.extend(defined(simple));
+            }
+        }
+        out.append(&mut stray_locals(&level.instructions, &known.union(&locals).copied().collect()));
+
+        // Redefining a local in the outer scope is fine
+        let used = match instructions.get(i + 1) {
+            Some(Instruction::Simple(simple)) if defined(simple).is_none() => subject(simple),
+            Some(Instruction::Complex(complex)) => subject(&complex.instruction),
+            _ => None
+        };
+        if let Some(id) = used {
+            if locals.contains(&id) && !known.contains(&id) {
+                out.push((level.identifier, id));
+            }
+        }
+    }
+    out
+}
+
+/// Returns the object an instruction (re)defines, if any.
+fn defined(simple: &Simple) -> Option<usize> {
+    match simple {
+        Simple::InitYou(id, _) | Simple::InitYou2(id, _) | Simple::InitGroup(id, _)
+        | Simple::IsValue(id, _, _) | Simple::MimicReference(id, _, _)
+        | Simple::IsEmpty(id) | Simple::IsLiteral(id, _) | Simple::IsNumber(id, _, _)
+        | Simple::IsSum(id, _, _) => Some(*id),
+        _ => None
+    }
+}
+
+/// Returns an InstructionValidationError if a LEVEL parameter or IMAGE attribute
+/// would shadow a built-in object.
+fn check_parameter(id: usize, identifiers: &HashMap<usize, String>) -> Result<(), BabaError> {
+    if is_reserved(id) {
+        return Err(error(
+            ErrorType::InstructionValidationError, 
+            format!("Built-in object {} cannot be used as a parameter or attribute", id),
+            Some((&[id], identifiers))
+        ));
+    }
+    Ok(())
+}
+
+/// Splits the inner scope of a LEVEL into its arguments, i.e. the `HAS` 
+/// statements on the LEVEL itself at the start of its scope, and its body.
+/// 
+/// Returns a description of the problem along with the identifier at fault 
+/// if an argument is declared after the body has started.
+fn split_arguments(level: usize, inner: &[Instruction]) -> Result<(Vec<usize>, Vec<Instruction>), (String, usize)> {
+    let is_argument = |instruction: &Instruction| matches!(
+        instruction, Instruction::Simple(Simple::HasValue(source, _)) if *source == level
+    );
+    let count = inner.iter().take_while(|instruction| is_argument(instruction)).count();
+    let (arguments, body) = inner.split_at(count);
+    if let Some(Instruction::Simple(Simple::HasValue(_, late))) = body.iter().find(|instruction| is_argument(instruction)) {
+        return Err((
+            format!("Argument {} of LEVEL {} must be declared before the body of the LEVEL", late, level),
+            *late
+        ));
+    }
+    let arguments = arguments.iter()
+        .filter_map(|instruction| match instruction {
+            Instruction::Simple(Simple::HasValue(_, target)) => Some(*target),
+            _ => None
+        })
+        .collect();
+    Ok((arguments, body.to_vec()))
+}
+
+/// Returns the arguments and body of a LEVEL, erroring on misplaced or built-in arguments.
+fn level_arguments(level: usize, inner: &[Instruction], identifiers: &HashMap<usize, String>) -> Result<(Vec<usize>, Vec<Instruction>), BabaError> {
+    match split_arguments(level, inner) {
+        Ok((arguments, body)) => {
+            for argument in &arguments {
+                check_parameter(*argument, identifiers)?;
+            }
+            Ok((arguments, body))
+        },
+        Err((message, culprit)) => Err(error(
+            ErrorType::InstructionValidationError, 
+            message,
+            Some((&[culprit], identifiers))
+        ))
+    }
+}
+
+/// Checks that an IMAGE constructor takes the instance being constructed 
+/// as its first argument, followed by distinct arguments.
+/// 
+/// Returns a description of the problem along with the identifier at fault, if any.
+fn constructor_error(image: usize, attributes: &[usize], constructor: &Level) -> Option<(String, usize)> {
+    let (instance, arguments) = match constructor.arguments.split_first() {
+        Some(split) => split,
+        None => return Some((
+            format!("Constructor of IMAGE {} must take the instance as its first argument", image),
+            image
+        ))
+    };
+    if *instance == image || attributes.contains(instance) {
+        return Some((
+            format!("Instance argument {} of the constructor of IMAGE {} shadows the IMAGE or one of its attributes", instance, image),
+            *instance
+        ));
+    }
+    for (i, argument) in arguments.iter().enumerate() {
+        if argument == instance || arguments[..i].contains(argument) {
+            return Some((
+                format!("Argument {} of the constructor of IMAGE {} is declared more than once", argument, image),
+                *argument
+            ));
+        }
+    }
+    None
 }
 
 /// Pushes an instruction to a vector, unless it is a no-op.
@@ -27,13 +181,15 @@ fn parse_inner<'a>(
     statements: &'a [Statement], 
     scope: Option<usize>,
     identifiers: &HashMap<usize, String>
-) -> (Vec<Instruction>, usize) {
+) -> Result<(Vec<Instruction>, usize), BabaError> {
     let mut out = Vec::new();
     let mut iter = statements.iter().enumerate();
     let mut last = 0;
     // This `for` loop is desugared to allow for elements to be skipped
     while let Some((i, statement)) = iter.next() {
         last = i;
+        // Errors in the statement point to it
+        set_span(Some(statement.span));
         let action_type = statement.action_type;
         match action_type {
             Verb::Is => {
@@ -45,88 +201,88 @@ fn parse_inner<'a>(
                             // or ALL IS DONE (in program scope, i.e. None)
                             Property::Done => {
                                 match conditions(statement) {
-                                    (None, None) => {
+                                    (conds, None, _) if conds.is_empty() => {
                                         match statement.subject {
                                             Noun::Identifier(id) => {
                                                 match scope {
                                                     // X IS TELE/LEVEL/IMAGE, ..., X IS DONE
                                                     Some(value) if value == id => {
-                                                        return (out, last);
+                                                        return Ok((out, last));
                                                     }
                                                     _ => {
-                                                        throw_error(
+                                                        return Err(error(
                                                             ErrorType::InstructionParserError, 
                                                             format!(
                                                                 "Cannot exit out of {:?} when in global scope",
                                                                 statement.subject 
                                                             ),
                                                             Some((&[id], identifiers))
-                                                        )
+                                                        ))
                                                     }
                                                 }
                                             },
                                             Noun::All => {
-                                                if let None = scope {
+                                                if scope.is_none() {
                                                     // ALL IS DONE
-                                                    if let false = statement.action_sign {
-                                                        return (out, last);
+                                                    if !statement.action_sign {
+                                                        return Ok((out, last));
                                                     }
                                                     // ALL IS NOT DONE
                                                     else {
-                                                        throw_error_str(
+                                                        return Err(error_str(
                                                             ErrorType::InstructionValidationError, 
                                                             "Cannot call ALL IS DONE"    
-                                                        )
+                                                        ))
                                                     }
                                                 }
                                                 else {
-                                                    throw_error_str(
+                                                    return Err(error_str(
                                                         ErrorType::InstructionParserError, 
                                                         "Unexpected ALL IS DONE in inner scope"
-                                                    )
+                                                    ))
                                                 }
                                             },
                                             _ => {
-                                                throw_error(
+                                                return Err(error(
                                                     ErrorType::InstructionValidationError, 
                                                     format!("Cannot exit out of {:?}", statement.subject),
                                                     None
-                                                )
+                                                ))
                                             }
                                         }
                                     },
                                     _ => {
-                                        throw_error_str(
+                                        return Err(error_str(
                                             ErrorType::InstructionValidationError, 
                                             "Cannot call IS DONE conditionally"    
-                                        )
+                                        ))
                                     }
                                 }
                             },
                             // Initialize primitive objects
-                            Property::You => push_nonempty(&mut out, validate("InitYou", statement, identifiers)),
-                            Property::You2 => push_nonempty(&mut out, validate("InitYou2", statement, identifiers)),
-                            Property::Group => push_nonempty(&mut out, validate("InitGroup", statement, identifiers)),
+                            Property::You => push_nonempty(&mut out, validate("InitYou", statement, identifiers)?),
+                            Property::You2 => push_nonempty(&mut out, validate("InitYou2", statement, identifiers)?),
+                            Property::Group => push_nonempty(&mut out, validate("InitGroup", statement, identifiers)?),
                             Property::Tele => {
-                                if let Instruction::PartialTele(id) = validate("InitTele", statement, identifiers) {
+                                if let Instruction::PartialTele(id) = validate("InitTele", statement, identifiers)? {
                                     // Inner "scope" of tele 
-                                    let (inner, inner_last) = parse_inner(&statements[i + 1..], Some(id), identifiers);
+                                    let (inner, inner_last) = parse_inner(&statements[i + 1..], Some(id), identifiers)?;
                                     // Advance outer parse() call past the last instruction of the
                                     // inner call
                                     iter.nth(inner_last);
                                     push_nonempty(&mut out, Instruction::Tele(Tele {
                                         identifier: id,
-                                        instructions: inner
+                                        instructions: inner.into()
                                     }));
                                 }
                             },
                             // Define a static variable
                             Property::Float => {
-                                if let Instruction::PartialFloat(id) = validate("InitFloat", statement, identifiers) {
+                                if let Instruction::PartialFloat(id) = validate("InitFloat", statement, identifiers)? {
                                     if let Some((next_i, next)) = iter.next() {
                                         if let Some(Target::Property(Property::You)) = next.action_target {
                                             let mut valid = false;
-                                            let init = validate("FloatYou", next, identifiers);
+                                            let init = validate("FloatYou", next, identifiers)?;
                                             if let Instruction::Simple(Simple::InitYou(next_id, _float)) = init {
                                                 if next_id == id {
                                                     valid = true;
@@ -144,15 +300,15 @@ fn parse_inner<'a>(
                                                 push_nonempty(&mut out, init);
                                             }
                                             else {
-                                                throw_error_str(
+                                                return Err(error_str(
                                                     ErrorType::InstructionValidationError, 
                                                     "The subjects of FLOAT and YOU must match."
-                                                );
+                                                ));
                                             }
                                         }
                                         else if let Some(Target::Property(Property::You2)) = next.action_target {
                                             let mut valid = false;
-                                            let init = validate("FloatYou2", next, identifiers);
+                                            let init = validate("FloatYou2", next, identifiers)?;
                                             if let Instruction::Simple(Simple::InitYou2(next_id, _float)) = init {
                                                 if next_id == id {
                                                     valid = true;
@@ -170,14 +326,14 @@ fn parse_inner<'a>(
                                                 push_nonempty(&mut out, init);
                                             }
                                             else {
-                                                throw_error_str(
+                                                return Err(error_str(
                                                     ErrorType::InstructionValidationError, 
                                                     "The subjects of FLOAT and YOU2 must match."
-                                                );
+                                                ));
                                             }
                                         }
                                         else if let Some(Target::Property(Property::Group)) = next.action_target {
-                                            let init = validate("FloatGroup", next, identifiers);
+                                            let init = validate("FloatGroup", next, identifiers)?;
                                             let mut valid = false;
                                             if let Instruction::Simple(Simple::InitGroup(next_id, _float)) = init {
                                                 if next_id == id {
@@ -196,63 +352,43 @@ fn parse_inner<'a>(
                                                 push_nonempty(&mut out, init);
                                             }
                                             else {
-                                                throw_error_str(
+                                                return Err(error_str(
                                                     ErrorType::InstructionValidationError, 
                                                     "The subjects of FLOAT and GROUP must match."
-                                                );
+                                                ));
                                             }
                                         }
                                         else if let Some(Target::Noun(Noun::Level)) = next.action_target {
-                                            if let Instruction::PartialLevel(next_id) = validate("InitLevel", next, identifiers) {
+                                            if let Instruction::PartialLevel(next_id) = validate("InitLevel", next, identifiers)? {
                                                 if id == next_id {
 
                                                     // Instructions in inner scope
-                                                    let (inner, inner_last) = parse_inner(&statements[next_i + 1..], Some(next_id), identifiers);
+                                                    let (inner, inner_last) = parse_inner(&statements[next_i + 1..], Some(next_id), identifiers)?;
                                                     // Advance outer parse() call past the last instruction of the
                                                     // inner call
                                                     iter.nth(inner_last);
                                                     // Parse inner loop for function arguments
-                                                    let mut inner_loop = inner.iter();
-                                                    let mut args = Vec::new();
-                                                    while let Some(Instruction::Simple(Simple::HasValue(source, target))) = inner_loop.next() {
-                                                        if *source == next_id {
-                                                            args.push(*target);
-                                                        }
-                                                        else {
-                                                            break;
-                                                        }
-                                                    }
-                                                    if inner.len() == 0 {
-                                                        push_nonempty(&mut out, Instruction::Level(Level {
-                                                            identifier: id,
-                                                            float: true,
-                                                            arguments: args,
-                                                            instructions: vec![]
-                                                        }));
-                                                    }
-                                                    else {
-                                                        let count = args.len();
-                                                        push_nonempty(&mut out, Instruction::Level(Level {
-                                                            identifier: id,
-                                                            float: true,
-                                                            arguments: args,
-                                                            instructions: inner[count..].to_vec()
-                                                        }));
-                                                    }
+                                                    let (args, body) = level_arguments(next_id, &inner, identifiers)?;
+                                                    push_nonempty(&mut out, Instruction::Level(Level {
+                                                        identifier: id,
+                                                        float: true,
+                                                        arguments: args,
+                                                        instructions: body.into()
+                                                    }));
                                                 }
                                                 else {
-                                                    throw_error_str(
+                                                    return Err(error_str(
                                                         ErrorType::InstructionValidationError, 
                                                         "The subjects of FLOAT and LEVEL must match."
-                                                    );
+                                                    ));
                                                 }
                                             }
                                         }
                                         else if let Some(Target::Noun(Noun::Image)) = next.action_target {
-                                            if let Instruction::PartialImage(next_id) = validate("InitImage", next, identifiers) {
+                                            if let Instruction::PartialImage(next_id) = validate("InitImage", next, identifiers)? {
                                                 if id == next_id {
                                                     // Inner scope of class
-                                                    let (inner, inner_last) = parse_inner(&statements[next_i + 1..], Some(next_id), identifiers);
+                                                    let (inner, inner_last) = parse_inner(&statements[next_i + 1..], Some(next_id), identifiers)?;
                                                     // Advance outer parse() call past the last instruction of the
                                                     // inner call
                                                     iter.nth(inner_last);
@@ -264,30 +400,38 @@ fn parse_inner<'a>(
                                                     while let Some(instr) = inner_loop.next() {
                                                         if let Instruction::Simple(Simple::HasValue(source, target)) = instr {
                                                             if *source == next_id {
+                                                                check_parameter(*target, identifiers)?;
                                                                 args.push(*target);
                                                             }
                                                         }
                                                         else if let Instruction::Level(level) = instr {
                                                             if level.identifier == next_id {
-                                                                if level.arguments.len() >= 1 {
-                                                                    constructor = Some(level);
-                                                                    break;
-                                                                }
-                                                                else {
-                                                                    throw_error_str(
+                                                                if let Some((message, culprit)) = constructor_error(next_id, &args, level) {
+                                                                    return Err(error(
                                                                         ErrorType::InstructionValidationError, 
-                                                                        "Class method must take at least one argument"
-                                                                    )
+                                                                        message,
+                                                                        Some((&[culprit], identifiers))
+                                                                    ));
                                                                 }
+                                                                constructor = Some(level);
+                                                                break;
                                                             }
                                                         }
                                                         else {
-                                                            throw_error_str(
+                                                            return Err(error_str(
                                                                 ErrorType::InstructionParserError, 
                                                                 "IMAGE body may only contain attributes or function definitions"
-                                                            )
+                                                            ))
                                                         }
                                                     }
+                                                    // Attributes declared after the constructor would be ignored
+                                                    if inner_loop.any(|instr| matches!(instr, Instruction::Simple(Simple::HasValue(source, _)) if *source == next_id)) {
+                                                        return Err(error(
+                                                            ErrorType::InstructionValidationError, 
+                                                            format!("Attributes of IMAGE {} must be declared before its constructor", next_id),
+                                                            Some((&[next_id], identifiers))
+                                                        ));
+                                                    }
                                                     if let Some(cons) = constructor {
                                                         push_nonempty(&mut out, Instruction::Image(Image {
                                                             identifier: next_id,
@@ -297,102 +441,86 @@ fn parse_inner<'a>(
                                                         }));
                                                     }
                                                     else {
-                                                        throw_error_str(
+                                                        return Err(error_str(
                                                             ErrorType::InstructionValidationError, 
                                                             "IMAGE objects must define a constructor"
-                                                        )
+                                                        ))
                                                     }
                                                 }
                                                 else {
-                                                    throw_error_str(
+                                                    return Err(error_str(
                                                         ErrorType::InstructionValidationError, 
                                                         "The subjects of FLOAT and IMAGE must match"
-                                                    )
+                                                    ))
                                                 }
                                             }
                                         }
-                                        else if let Instruction::Simple(Simple::Power(_, _)) = validate("LevelPower", next, identifiers) {
-                                            push_nonempty(&mut out, validate("FloatPower", next, identifiers));
+                                        else if let Instruction::Simple(Simple::Power(_, _)) = validate("LevelPower", next, identifiers)? {
+                                            push_nonempty(&mut out, validate("FloatPower", next, identifiers)?);
                                         }
                                         else {
-                                            throw_error(
+                                            return Err(error(
                                                 ErrorType::InstructionValidationError, 
                                                 format!("[{0}] IS FLOAT must be followed by [{0}] IS YOU, YOU2, GROUP, LEVEL or IMAGE", id),
                                                 Some((&[id], identifiers))
-                                            );
+                                            ));
                                         }
                                     }
                                 }
                             },
                             // Type-indifferent instructions
-                            Property::Text => push_nonempty(&mut out, validate("IsText", statement, identifiers)),
-                            Property::Word => push_nonempty(&mut out, validate("IsWord", statement, identifiers)),
-                            Property::Win => push_nonempty(&mut out, validate("IsWin", statement, identifiers)),
-                            Property::Defeat => push_nonempty(&mut out, validate("IsDefeat", statement, identifiers)),
-                            Property::Sleep => push_nonempty(&mut out, validate("IsSleep", statement, identifiers)),
+                            Property::Text => push_nonempty(&mut out, validate("IsText", statement, identifiers)?),
+                            Property::Word => push_nonempty(&mut out, validate("IsWord", statement, identifiers)?),
+                            Property::Line => push_nonempty(&mut out, validate("IsLine", statement, identifiers)?),
+                            Property::Win => push_nonempty(&mut out, validate("IsWin", statement, identifiers)?),
+                            Property::Defeat => push_nonempty(&mut out, validate("IsDefeat", statement, identifiers)?),
+                            Property::Sleep => push_nonempty(&mut out, validate("IsSleep", statement, identifiers)?),
                             // YOU instructions
-                            Property::Move => push_nonempty(&mut out, validate("YouMove", statement, identifiers)),
-                            Property::Turn => push_nonempty(&mut out, validate("YouTurn", statement, identifiers)),
-                            Property::Fall => push_nonempty(&mut out, validate("YouFall", statement, identifiers)),
-                            Property::More => push_nonempty(&mut out, validate("YouMore", statement, identifiers)),
-                            Property::Right => push_nonempty(&mut out, validate("YouRight", statement, identifiers)),
-                            Property::Up => push_nonempty(&mut out, validate("YouUp", statement, identifiers)),
-                            Property::Left => push_nonempty(&mut out, validate("YouLeft", statement, identifiers)),
-                            Property::Down => push_nonempty(&mut out, validate("YouDown", statement, identifiers)),
-                            Property::Chill => push_nonempty(&mut out, validate("YouChill", statement, identifiers)),
+                            Property::Move => push_nonempty(&mut out, validate("YouMove", statement, identifiers)?),
+                            Property::Turn => push_nonempty(&mut out, validate("YouTurn", statement, identifiers)?),
+                            Property::Fall => push_nonempty(&mut out, validate("YouFall", statement, identifiers)?),
+                            Property::More => push_nonempty(&mut out, validate("YouMore", statement, identifiers)?),
+                            Property::Less => push_nonempty(&mut out, validate("YouLess", statement, identifiers)?),
+                            Property::Right => push_nonempty(&mut out, validate("YouRight", statement, identifiers)?),
+                            Property::Up => push_nonempty(&mut out, validate("YouUp", statement, identifiers)?),
+                            Property::Left => push_nonempty(&mut out, validate("YouLeft", statement, identifiers)?),
+                            Property::Down => push_nonempty(&mut out, validate("YouDown", statement, identifiers)?),
+                            Property::Chill => push_nonempty(&mut out, validate("YouChill", statement, identifiers)?),
+                            Property::Both => push_nonempty(&mut out, validate("YouBoth", statement, identifiers)?),
+                            Property::Reset => push_nonempty(&mut out, validate("YouReset", statement, identifiers)?),
                             // GROUP instructions
-                            Property::Shift => push_nonempty(&mut out, validate("GroupShift", statement, identifiers)),
-                            Property::Sink => push_nonempty(&mut out, validate("GroupSink", statement, identifiers)),
-                            Property::Swap => push_nonempty(&mut out, validate("GroupSwap", statement, identifiers)),
+                            Property::Shift => push_nonempty(&mut out, validate("GroupShift", statement, identifiers)?),
+                            Property::Sink => push_nonempty(&mut out, validate("GroupSink", statement, identifiers)?),
+                            Property::Swap => push_nonempty(&mut out, validate("GroupSwap", statement, identifiers)?),
                             // LEVEL instructions
-                            Property::Power => push_nonempty(&mut out, validate("LevelPower", statement, identifiers)),
+                            Property::Power => push_nonempty(&mut out, validate("LevelPower", statement, identifiers)?),
                         }
                     }
                     else if let Target::Noun(noun) = target {
                         if let Noun::Empty = noun {
-                            push_nonempty(&mut out, validate("IsEmpty", statement, identifiers));
+                            push_nonempty(&mut out, validate("IsEmpty", statement, identifiers)?);
                         }
                         else if let Noun::Level = noun {
-                            if let Instruction::PartialLevel(id) = validate("InitLevel", statement, identifiers) {
+                            if let Instruction::PartialLevel(id) = validate("InitLevel", statement, identifiers)? {
                                 // Instructions in inner scope
-                                let (inner, inner_last) = parse_inner(&statements[i + 1..], Some(id), identifiers);
+                                let (inner, inner_last) = parse_inner(&statements[i + 1..], Some(id), identifiers)?;
                                 // Advance outer parse() call past the last instruction of the
                                 // inner call
                                 iter.nth(inner_last);
                                 // Parse inner loop for function arguments
-                                let mut inner_loop = inner.iter();
-                                let mut args = Vec::new();
-                                while let Some(Instruction::Simple(Simple::HasValue(source, target))) = inner_loop.next() {
-                                    if *source == id {
-                                        args.push(*target);
-                                    }
-                                    else {
-                                        break;
-                                    }
-                                }
-                                if inner.len() == 0 {
-                                    push_nonempty(&mut out, Instruction::Level(Level {
-                                        identifier: id,
-                                        float: false,
-                                        arguments: args,
-                                        instructions: vec![]
-                                    }));
-                                }
-                                else {
-                                    let count = args.len();
-                                    push_nonempty(&mut out, Instruction::Level(Level {
-                                        identifier: id,
-                                        float: false,
-                                        arguments: args,
-                                        instructions: inner[count..].to_vec()
-                                    }));
-                                }
+                                let (args, body) = level_arguments(id, &inner, identifiers)?;
+                                push_nonempty(&mut out, Instruction::Level(Level {
+                                    identifier: id,
+                                    float: false,
+                                    arguments: args,
+                                    instructions: body.into()
+                                }));
                             }
                         }
                         else if let Noun::Image = noun {
-                            if let Instruction::PartialImage(id) = validate("InitImage", statement, identifiers) {
+                            if let Instruction::PartialImage(id) = validate("InitImage", statement, identifiers)? {
                                 // Inner scope of class
-                                let (inner, inner_last) = parse_inner(&statements[i + 1..], Some(id), identifiers);
+                                let (inner, inner_last) = parse_inner(&statements[i + 1..], Some(id), identifiers)?;
                                 // Advance outer parse() call past the last instruction of the
                                 // inner call
                                 iter.nth(inner_last);
@@ -404,30 +532,38 @@ fn parse_inner<'a>(
                                 while let Some(instr) = inner_loop.next() {
                                     if let Instruction::Simple(Simple::HasValue(source, target)) = instr {
                                         if *source == id {
+                                            check_parameter(*target, identifiers)?;
                                             args.push(*target);
                                         }
                                     }
                                     else if let Instruction::Level(level) = instr {
                                         if level.identifier == id {
-                                            if level.arguments.len() >= 1 {
-                                                constructor = Some(level.to_owned());
-                                                break;
-                                            }
-                                            else {
-                                                throw_error_str(
+                                            if let Some((message, culprit)) = constructor_error(id, &args, level) {
+                                                return Err(error(
                                                     ErrorType::InstructionValidationError, 
-                                                    "Class method must take at least one argument"
-                                                )
+                                                    message,
+                                                    Some((&[culprit], identifiers))
+                                                ));
                                             }
+                                            constructor = Some(level.to_owned());
+                                            break;
                                         }
                                     }
                                     else {
-                                        throw_error_str(
+                                        return Err(error_str(
                                             ErrorType::InstructionParserError, 
                                             "IMAGE body may only contain attributes or function definitions"
-                                        )
+                                        ))
                                     }
                                 }
+                                // Attributes declared after the constructor would be ignored
+                                if inner_loop.any(|instr| matches!(instr, Instruction::Simple(Simple::HasValue(source, _)) if *source == id)) {
+                                    return Err(error(
+                                        ErrorType::InstructionValidationError, 
+                                        format!("Attributes of IMAGE {} must be declared before its constructor", id),
+                                        Some((&[id], identifiers))
+                                    ));
+                                }
                                 if let Some(cons) = constructor {
                                     push_nonempty(&mut out, Instruction::Image(Image {
                                         identifier: id,
@@ -437,73 +573,356 @@ fn parse_inner<'a>(
                                     }));
                                 }
                                 else {
-                                    throw_error_str(
+                                    return Err(error_str(
                                         ErrorType::InstructionValidationError, 
                                         "IMAGE objects must define a constructor"
-                                    )
+                                    ))
                                 }
                             }
                         }
+                        else if let Noun::Literal(_) = noun {
+                            push_nonempty(&mut out, validate("IsLiteral", statement, identifiers)?);
+                        }
+                        else if let Noun::Number(_) = noun {
+                            push_nonempty(&mut out, validate("IsNumber", statement, identifiers)?);
+                        }
                         else {
-                            push_nonempty(&mut out, validate("IsValue", statement, identifiers));
+                            push_nonempty(&mut out, validate("IsValue", statement, identifiers)?);
                         }
                     }
                 }
                 else if let Some(_) = &statement.action_targets {
-                    push_nonempty(&mut out, validate("YouSum", statement, identifiers));
+                    push_nonempty(&mut out, validate("YouSum", statement, identifiers)?);
                 }
             },
             Verb::Has => {
-                if let Some(target) = statement.action_target {
-                    if let Target::Noun(_) = target {
-                        push_nonempty(&mut out, validate("HasValue", statement, identifiers));
-                    }
+                if let Some(Target::Noun(_)) = statement.action_target {
+                    push_nonempty(&mut out, validate("HasValue", statement, identifiers)?);
                 }
             },
             Verb::Make => {
-                if let Some(target) = statement.action_target {
-                    if let Target::Noun(_) = target {
-                        push_nonempty(&mut out, validate("MakeValue", statement, identifiers));
-                    }
+                if let Some(Target::Noun(_)) = statement.action_target {
+                    push_nonempty(&mut out, validate("MakeValue", statement, identifiers)?);
                 }
             },
             Verb::Follow => {
-                if let Some(target) = statement.action_target {
-                    if let Target::Noun(_) = target {
-                        push_nonempty(&mut out, validate("FollowAttribute", statement, identifiers));
-                    }
+                if let Some(Target::Noun(_)) = statement.action_target {
+                    push_nonempty(&mut out, validate("FollowAttribute", statement, identifiers)?);
                 }
             },
             Verb::Mimic => {
-                if let Some(target) = statement.action_target {
-                    if let Target::Noun(_) = target {
-                        push_nonempty(&mut out, validate("MimicReference", statement, identifiers));
-                    }
+                if let Some(Target::Noun(_)) = statement.action_target {
+                    push_nonempty(&mut out, validate("MimicReference", statement, identifiers)?);
                 }
             },
             Verb::Fear => {
-                if let Some(target) = statement.action_target {
-                    if let Target::Noun(_) = target {
-                        push_nonempty(&mut out, validate("FearTele", statement, identifiers));
-                    }
+                if let Some(Target::Noun(_)) = statement.action_target {
+                    push_nonempty(&mut out, validate("FearTele", statement, identifiers)?);
+                }
+            },
+            Verb::Feed => {
+                if let Some(Target::Noun(_)) = statement.action_target {
+                    push_nonempty(&mut out, validate("FeedLevel", statement, identifiers)?);
                 }
             },
             Verb::Eat => {
-                if let Some(target) = statement.action_target {
-                    if let Target::Noun(_) = target {
-                        push_nonempty(&mut out, validate("EatValue", statement, identifiers));
-                    }
+                if let Some(Target::Noun(_)) = statement.action_target {
+                    push_nonempty(&mut out, validate("EatValue", statement, identifiers)?);
+                }
+            },
+            Verb::Feel => {
+                if let Some(Target::Noun(_)) = statement.action_target {
+                    push_nonempty(&mut out, validate("FeelType", statement, identifiers)?);
+                }
+            },
+            Verb::Equal => {
+                if let Some(Target::Noun(_)) = statement.action_target {
+                    push_nonempty(&mut out, validate("EqualValue", statement, identifiers)?);
+                }
+            },
+            Verb::Write => {
+                if let Some(Target::Noun(_)) = statement.action_target {
+                    push_nonempty(&mut out, validate("WriteValue", statement, identifiers)?);
+                }
+            },
+            Verb::Spell => {
+                if let Some(Target::Noun(_)) = statement.action_target {
+                    push_nonempty(&mut out, validate("SpellDigits", statement, identifiers)?);
+                }
+            },
+            Verb::Track => {
+                if let Some(Target::Noun(_)) = statement.action_target {
+                    push_nonempty(&mut out, validate("TrackIndex", statement, identifiers)?);
+                }
+            },
+            Verb::Peek => {
+                if let Some(Target::Noun(_)) = statement.action_target {
+                    push_nonempty(&mut out, validate("PeekValue", statement, identifiers)?);
+                }
+            },
+            Verb::Play => {
+                if let Some(Target::Noun(_)) = statement.action_target {
+                    push_nonempty(&mut out, validate("Play", statement, identifiers)?);
                 }
             },
-            _ => {
-                throw_error(
-                    ErrorType::InstructionParserError, 
-                    format!("Invalid verb `{:?}` provided to instruction parser", action_type),
-                    None
-                );
-            }
         }
     }
-    (out, last)
+    Ok((out, last))
 }
 
+/// Serializes an instruction tree as JSON, along with the names of its identifiers.
+/// 
+/// The output is an object of the form `{"version": 1, "identifiers": {...}, "ast": [...]}`, 
+/// where `identifiers` maps each identifier ID to its name, and `ast` holds the instructions
+/// in serde's default representation (e.g. `{"Simple": {"InitYou": [3, false]}}`).
+#[cfg(feature = "serde")]
+pub fn ast_to_json(ast: &[Instruction], ids: &HashMap<usize, String>) -> String {
+    // Sorted, so that the output is stable
+    let identifiers = ids.iter().collect::<std::collections::BTreeMap<_, _>>();
+    serde_json::json!({
+        "version": 1,
+        "identifiers": identifiers,
+        "ast": ast,
+    }).to_string()
+}
+
+/// Pretty-prints an instruction tree, one instruction per line, for debugging.
+/// 
+/// The bodies of TELE, LEVEL and IMAGE blocks are indented by their depth, 
+/// and identifiers are replaced by their names, e.g. `Move(baba, false)`.
+pub fn dump(instructions: &[Instruction], identifiers: &HashMap<usize, String>) -> String {
+    let mut out = Vec::new();
+    dump_inner(instructions, 0, identifiers, &mut out);
+    out.join("\n")
+}
+
+/// Adds a line for each instruction, indented by `depth`, to `out`.
+fn dump_inner(instructions: &[Instruction], depth: usize, identifiers: &HashMap<usize, String>, out: &mut Vec<String>) {
+    let indent = "    ".repeat(depth);
+    let name = |id: &usize| spelling(&Token::Noun(Noun::Identifier(*id)), identifiers);
+    let names = |ids: &[usize]| ids.iter().map(name).collect::<Vec<String>>().join(", ");
+    for instruction in instructions {
+        match instruction {
+            Instruction::Simple(simple) => out.push(format!("{}{}", indent, dump_simple(simple, identifiers))),
+            Instruction::Complex(complex) => out.push(format!("{}{}", indent, dump_complex(complex, identifiers))),
+            Instruction::Tele(tele) => {
+                out.push(format!("{}Tele({})", indent, name(&tele.identifier)));
+                dump_inner(&tele.instructions, depth + 1, identifiers, out);
+            },
+            Instruction::Level(level) => {
+                out.push(format!("{}Level({}, [{}], {})", indent, name(&level.identifier), names(&level.arguments), level.float));
+                dump_inner(&level.instructions, depth + 1, identifiers, out);
+            },
+            Instruction::Image(image) => {
+                let constructor = &image.constructor;
+                out.push(format!(
+                    "{}Image({}, [{}], [{}], {})", 
+                    indent, name(&image.identifier), names(&image.attributes), names(&constructor.arguments), image.float
+                ));
+                dump_inner(&constructor.instructions, depth + 1, identifiers, out);
+            },
+            // Partial instructions and no-ops don't refer to any identifiers
+            _ => out.push(format!("{}{:?}", indent, instruction))
+        }
+    }
+}
+
+/// Formats a simple instruction like its `Debug` form, with names in place of identifiers.
+pub fn dump_simple(simple: &Simple, identifiers: &HashMap<usize, String>) -> String {
+    let name = |id: &usize| spelling(&Token::Noun(Noun::Identifier(*id)), identifiers);
+    // The variant name, without any fields
+    let debug = format!("{:?}", simple);
+    let variant = debug.split('(').next().unwrap_or_default();
+    match simple {
+        Simple::AllWin | Simple::AllDefeat
+        | Simple::AllMove(_) | Simple::AllTurn(_) | Simple::AllFall(_)
+        | Simple::AllMore(_) | Simple::AllLess(_) | Simple::AllRight(_)
+        | Simple::AllUp(_) | Simple::AllLeft(_) | Simple::AllDown(_)
+        | Simple::AllChill(_) => debug,
+        Simple::Win(id) | Simple::Defeat(id) | Simple::Sleep(id)
+        | Simple::Text(id) | Simple::Word(id) | Simple::Line(id) | Simple::IsEmpty(id)
+        | Simple::Reset(id) | Simple::Sink(id) | Simple::Swap(id) => format!("{}({})", variant, name(id)),
+        Simple::InitYou(id, flag) | Simple::InitYou2(id, flag) | Simple::InitGroup(id, flag)
+        | Simple::Move(id, flag) | Simple::Turn(id, flag) | Simple::Fall(id, flag)
+        | Simple::More(id, flag) | Simple::Less(id, flag) | Simple::Right(id, flag)
+        | Simple::Up(id, flag) | Simple::Left(id, flag) | Simple::Down(id, flag)
+        | Simple::Chill(id, flag) | Simple::Both(id, flag) | Simple::Shift(id, flag)
+        | Simple::Power(id, flag) => format!("{}({}, {})", variant, name(id), flag),
+        Simple::IsValue(id, target, sign) | Simple::MimicReference(id, target, sign) => {
+            format!("{}({}, {}, {})", variant, name(id), name(target), sign)
+        },
+        Simple::HasValue(id, target) | Simple::MakeValue(id, target)
+        | Simple::FearTele(id, target) | Simple::FollowAttribute(id, target)
+        | Simple::EatValue(id, target) | Simple::FeelType(id, target)
+        | Simple::EqualValue(id, target) | Simple::WriteValue(id, target)
+        | Simple::TrackIndex(id, target) | Simple::SpellDigits(id, target)
+        | Simple::Play(id, target) | Simple::FeedLevel(id, target) | Simple::PeekValue(id, target) => {
+            format!("{}({}, {})", variant, name(id), name(target))
+        },
+        Simple::IsLiteral(id, bytes) | Simple::HasLiteral(id, bytes) => {
+            format!("{}({}, {:?})", variant, name(id), String::from_utf8_lossy(bytes))
+        },
+        Simple::IsNumber(id, number, sign) => format!("{}({}, {}, {})", variant, name(id), number, sign),
+        Simple::HasNumber(id, number) => format!("{}({}, {})", variant, name(id), number),
+        Simple::IsSum(id, nouns, signs) => {
+            let operands = nouns.iter()
+                .zip(signs.iter())
+                .map(|(noun, sign)| {
+                    let spelled = spelling(&Token::Noun(*noun), identifiers);
+                    if *sign { format!("not {}", spelled) } else { spelled }
+                })
+                .collect::<Vec<String>>();
+            format!("{}({}, [{}])", variant, name(id), operands.join(", "))
+        }
+    }
+}
+
+/// Formats a complex instruction as its simple instruction followed by its conditions,
+/// e.g. `Move(baba, false) if lonely and not on keke, me and facing up`.
+fn dump_complex(complex: &Complex, identifiers: &HashMap<usize, String>) -> String {
+    let negate = |sign: bool| if sign { "not " } else { "" };
+    let mut conditions = Vec::new();
+    if let Some(prefix) = &complex.prefix {
+        conditions.push(format!("{}{}", negate(prefix.sign), spelling(&Token::Prefix(prefix.prefix), identifiers)));
+    }
+    for conds in &complex.conditions {
+        let targets = conds.targets.iter()
+            .map(|(target, sign)| format!("{}{}", negate(*sign), target_spelling(target, identifiers)))
+            .collect::<Vec<String>>();
+        conditions.push(format!(
+            "{}{} {}", 
+            negate(conds.sign), spelling(&Token::Conditional(conds.cond_type), identifiers), targets.join(", ")
+        ));
+    }
+    let combinator = match complex.combinator {
+        Combinator::And => " and ",
+        Combinator::Or => " or ",
+    };
+    format!("{} if {}", dump_simple(&complex.instruction, identifiers), conditions.join(combinator))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{constructor_error, split_arguments, stray_locals, dump};
+    use crate::instruction::{Level, Instruction, Simple};
+    use crate::compile;
+    use std::collections::HashSet;
+
+    /// Returns the names of the LEVEL and local object of each stray instruction.
+    fn strays(source: &str) -> Vec<(String, String)> {
+        let (instructions, identifiers) = compile(source.as_bytes()).unwrap();
+        let name = |id| identifiers[&id].clone();
+        stray_locals(&instructions, &HashSet::new()).into_iter().map(|(level, local)| (name(level), name(local))).collect()
+    }
+
+    fn constructor(arguments: Vec<usize>) -> Level {
+        Level { float: false, identifier: 3, arguments, instructions: Vec::new().into() }
+    }
+
+    #[test]
+    fn constructor_valid() {
+        assert_eq!(constructor_error(3, &[4], &constructor(vec![5])), None);
+        assert_eq!(constructor_error(3, &[4], &constructor(vec![5, 6, 7])), None);
+    }
+
+    #[test]
+    fn constructor_malformed() {
+        // No instance argument
+        assert_eq!(constructor_error(3, &[4], &constructor(vec![])).map(|(_, id)| id), Some(3));
+        // Instance shadows the IMAGE or an attribute
+        assert_eq!(constructor_error(3, &[4], &constructor(vec![3])).map(|(_, id)| id), Some(3));
+        assert_eq!(constructor_error(3, &[4], &constructor(vec![4, 5])).map(|(_, id)| id), Some(4));
+        // Repeated arguments
+        assert_eq!(constructor_error(3, &[4], &constructor(vec![5, 5])).map(|(_, id)| id), Some(5));
+        assert_eq!(constructor_error(3, &[4], &constructor(vec![5, 6, 6])).map(|(_, id)| id), Some(6));
+    }
+
+    #[test]
+    fn arguments_before_body() {
+        let has = |target| Instruction::Simple(Simple::HasValue(3, target));
+        let body = Instruction::Simple(Simple::Text(4));
+        assert_eq!(split_arguments(3, &[]), Ok((vec![], vec![])));
+        assert_eq!(split_arguments(3, &[has(4), has(5)]), Ok((vec![4, 5], vec![])));
+        assert_eq!(split_arguments(3, &[has(4), body.clone()]), Ok((vec![4], vec![body.clone()])));
+        // HAS on other objects is part of the body
+        let other = Instruction::Simple(Simple::HasValue(4, 5));
+        assert_eq!(split_arguments(3, &[other.clone(), body.clone()]), Ok((vec![], vec![other, body.clone()])));
+    }
+
+    #[test]
+    fn arguments_after_body() {
+        let has = |target| Instruction::Simple(Simple::HasValue(3, target));
+        let body = Instruction::Simple(Simple::Text(4));
+        assert_eq!(split_arguments(3, &[body.clone(), has(5)]).map_err(|(_, id)| id), Err(5));
+        assert_eq!(split_arguments(3, &[has(4), body, has(6)]).map_err(|(_, id)| id), Err(6));
+    }
+
+    #[test]
+    fn dump_tree() {
+        let source = "
+            baba is you
+            loop is tele
+                lonely baba or not on keke is move
+                keke is baba and not me
+            loop is done
+            f is level f has x
+                x is \"hi\"
+            f is done
+        ";
+        let (instructions, identifiers) = compile(source.as_bytes()).unwrap();
+        assert_eq!(dump(&instructions, &identifiers), "\
+InitYou(baba, false)
+Tele(loop)
+    Move(baba, false) if lonely or not on keke
+    IsSum(keke, [baba, not me])
+Level(f, [x], false)
+    IsLiteral(x, \"hi\")");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn ast_json() {
+        let (instructions, identifiers) = compile(b"baba is you loop is tele baba is move loop is done").unwrap();
+        let json = crate::ast::ast_to_json(&instructions, &identifiers);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["version"], 1);
+        assert_eq!(value["identifiers"]["3"], "baba");
+        assert_eq!(value["identifiers"]["4"], "loop");
+        assert_eq!(value["ast"][0]["Simple"]["InitYou"], serde_json::json!([3, false]));
+        assert_eq!(value["ast"][1]["Tele"]["identifier"], 4);
+        assert_eq!(value["ast"][1]["Tele"]["instructions"][0]["Simple"]["Move"], serde_json::json!([3, false]));
+    }
+
+    #[test]
+    fn stray_after_done() {
+        let found = strays("
+            f is level f has x
+                y is you
+            f is done
+            y is move
+            g is level g has x g is done x is text
+        ");
+        assert_eq!(found, vec![
+            (String::from("f"), String::from("y")),
+            (String::from("g"), String::from("x"))
+        ]);
+    }
+
+    #[test]
+    fn stray_defined_outside() {
+        let found = strays("
+            baba is you
+            f is level f has keke
+                baba is you keke is move
+            f is done
+            baba is move
+            g is level g has keke g is done
+            keke is you
+            loop is tele
+                h is level h has me me is move h is done
+                h has baba
+            loop is done
+        ");
+        assert_eq!(found, Vec::new());
+    }
+}
@@ -0,0 +1,528 @@
+use crate::instruction::{Instruction, Simple, Complex, Conditions, Prefixes, Tele, Level, Image};
+use crate::statement::{Target, Combinator};
+use crate::token::{Token, Noun, parse, spelling};
+
+use std::collections::HashMap;
+
+/// Identifies a precompiled AST file.
+const MAGIC: &[u8] = b"BAST";
+/// The version of the encoding. Bumped whenever the instructions change.
+const VERSION: u8 = 13;
+
+/// Encodes a parsed program, along with its identifiers, into the binary
+/// format read by `decode`.
+///
+/// The encoding starts with `BAST` and a version byte, followed by the identifiers
+/// and the instructions. Integers are stored as 64-bit little-endian values,
+/// and keywords (properties, prefixes and conditionals) by their spelling.
+///
+/// # Arguments
+///
+/// * `ast` - The instructions of the program.
+///
+/// * `identifiers` - A HashMap that associates each token identifier with its name.
+pub fn encode(ast: &[Instruction], identifiers: &HashMap<usize, String>) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    out.push(VERSION);
+    let mut ids = identifiers.iter().collect::<Vec<_>>();
+    ids.sort();
+    write_usize(&mut out, ids.len());
+    for (id, name) in ids {
+        write_usize(&mut out, *id);
+        write_bytes(&mut out, name.as_bytes());
+    }
+    write_instructions(&mut out, ast);
+    out
+}
+
+/// Decodes a program encoded with `encode`.
+/// Returns a description of the problem if the encoding is invalid.
+pub fn decode(bytes: &[u8]) -> Result<(Vec<Instruction>, HashMap<usize, String>), String> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(String::from("Not a precompiled AST file"));
+    }
+    let mut reader = Reader { bytes, position: MAGIC.len() };
+    let version = reader.u8()?;
+    if version != VERSION {
+        return Err(format!("Unsupported AST file version {} (expected {})", version, VERSION));
+    }
+    let mut identifiers = HashMap::new();
+    for _ in 0..reader.usize()? {
+        let id = reader.usize()?;
+        let name = String::from_utf8(reader.bytes()?.to_vec()).map_err(|_| String::from("Invalid identifier"))?;
+        identifiers.insert(id, name);
+    }
+    let ast = reader.instructions()?;
+    if reader.position != bytes.len() {
+        return Err(String::from("Unexpected data after the instructions"));
+    }
+    Ok((ast, identifiers))
+}
+
+fn write_usize(out: &mut Vec<u8>, value: usize) {
+    out.extend_from_slice(&(value as u64).to_le_bytes());
+}
+
+fn write_bool(out: &mut Vec<u8>, value: bool) {
+    out.push(value as u8);
+}
+
+fn write_bytes(out: &mut Vec<u8>, value: &[u8]) {
+    write_usize(out, value.len());
+    out.extend_from_slice(value);
+}
+
+fn write_ids(out: &mut Vec<u8>, ids: &[usize]) {
+    write_usize(out, ids.len());
+    for id in ids {
+        write_usize(out, *id);
+    }
+}
+
+/// Writes a keyword token by its spelling.
+fn write_keyword(out: &mut Vec<u8>, token: Token) {
+    write_bytes(out, spelling(&token, &HashMap::new()).as_bytes());
+}
+
+fn write_noun(out: &mut Vec<u8>, noun: &Noun) {
+    match noun {
+        Noun::All => out.push(0),
+        Noun::Empty => out.push(1),
+        Noun::Level => out.push(2),
+        Noun::Image => out.push(3),
+        Noun::Identifier(id) => {
+            out.push(4);
+            write_usize(out, *id);
+        },
+        Noun::Literal(id) => {
+            out.push(5);
+            write_usize(out, *id);
+        },
+        Noun::Number(number) => {
+            out.push(6);
+            write_usize(out, *number as usize);
+        }
+    }
+}
+
+fn write_target(out: &mut Vec<u8>, target: &Target) {
+    match target {
+        Target::Noun(noun) => {
+            out.push(0);
+            write_noun(out, noun);
+        },
+        Target::Property(property) => {
+            out.push(1);
+            write_keyword(out, Token::Property(*property));
+        }
+    }
+}
+
+fn write_instructions(out: &mut Vec<u8>, instructions: &[Instruction]) {
+    write_usize(out, instructions.len());
+    for instruction in instructions {
+        write_instruction(out, instruction);
+    }
+}
+
+fn write_level(out: &mut Vec<u8>, level: &Level) {
+    write_bool(out, level.float);
+    write_usize(out, level.identifier);
+    write_ids(out, &level.arguments);
+    write_instructions(out, &level.instructions);
+}
+
+fn write_instruction(out: &mut Vec<u8>, instruction: &Instruction) {
+    match instruction {
+        Instruction::NoOp => out.push(0),
+        Instruction::Simple(simple) => {
+            out.push(1);
+            write_simple(out, simple);
+        },
+        Instruction::Complex(complex) => {
+            out.push(2);
+            write_usize(out, complex.conditions.len());
+            for conditions in &complex.conditions {
+                write_keyword(out, Token::Conditional(conditions.cond_type));
+                write_usize(out, conditions.targets.len());
+                for (target, sign) in &conditions.targets {
+                    write_target(out, target);
+                    write_bool(out, *sign);
+                }
+                write_bool(out, conditions.sign);
+            }
+            match &complex.prefix {
+                Some(prefix) => {
+                    write_bool(out, true);
+                    write_keyword(out, Token::Prefix(prefix.prefix));
+                    write_bool(out, prefix.sign);
+                },
+                None => write_bool(out, false)
+            }
+            write_bool(out, complex.combinator == Combinator::Or);
+            write_simple(out, &complex.instruction);
+        },
+        Instruction::PartialTele(id) => {
+            out.push(3);
+            write_usize(out, *id);
+        },
+        Instruction::Tele(tele) => {
+            out.push(4);
+            write_usize(out, tele.identifier);
+            write_instructions(out, &tele.instructions);
+        },
+        Instruction::PartialLevel(id) => {
+            out.push(5);
+            write_usize(out, *id);
+        },
+        Instruction::Level(level) => {
+            out.push(6);
+            write_level(out, level);
+        },
+        Instruction::PartialImage(id) => {
+            out.push(7);
+            write_usize(out, *id);
+        },
+        Instruction::Image(image) => {
+            out.push(8);
+            write_bool(out, image.float);
+            write_usize(out, image.identifier);
+            write_ids(out, &image.attributes);
+            write_level(out, &image.constructor);
+        },
+        Instruction::PartialFloat(id) => {
+            out.push(9);
+            write_usize(out, *id);
+        }
+    }
+}
+
+fn write_simple(out: &mut Vec<u8>, simple: &Simple) {
+    // Each variant is written as a tag followed by its fields
+    match simple {
+        Simple::InitYou(id, float) => { out.push(0); write_usize(out, *id); write_bool(out, *float) },
+        Simple::InitYou2(id, float) => { out.push(1); write_usize(out, *id); write_bool(out, *float) },
+        Simple::InitGroup(id, float) => { out.push(2); write_usize(out, *id); write_bool(out, *float) },
+        Simple::Win(id) => { out.push(3); write_usize(out, *id) },
+        Simple::Defeat(id) => { out.push(4); write_usize(out, *id) },
+        Simple::AllWin => out.push(5),
+        Simple::AllDefeat => out.push(6),
+        Simple::Sleep(id) => { out.push(7); write_usize(out, *id) },
+        Simple::Text(id) => { out.push(8); write_usize(out, *id) },
+        Simple::Word(id) => { out.push(9); write_usize(out, *id) },
+        Simple::IsValue(id, source, sign) => { out.push(10); write_usize(out, *id); write_usize(out, *source); write_bool(out, *sign) },
+        Simple::MimicReference(id, source, weak) => { out.push(11); write_usize(out, *id); write_usize(out, *source); write_bool(out, *weak) },
+        Simple::IsEmpty(id) => { out.push(12); write_usize(out, *id) },
+        Simple::IsLiteral(id, bytes) => { out.push(13); write_usize(out, *id); write_bytes(out, bytes) },
+        Simple::IsSum(id, nouns, signs) => {
+            out.push(14);
+            write_usize(out, *id);
+            write_usize(out, nouns.len());
+            for noun in nouns {
+                write_noun(out, noun);
+            }
+            write_usize(out, signs.len());
+            for sign in signs {
+                write_bool(out, *sign);
+            }
+        },
+        Simple::Move(id, sign) => { out.push(15); write_usize(out, *id); write_bool(out, *sign) },
+        Simple::Turn(id, sign) => { out.push(16); write_usize(out, *id); write_bool(out, *sign) },
+        Simple::Fall(id, sign) => { out.push(17); write_usize(out, *id); write_bool(out, *sign) },
+        Simple::More(id, sign) => { out.push(18); write_usize(out, *id); write_bool(out, *sign) },
+        Simple::Less(id, sign) => { out.push(19); write_usize(out, *id); write_bool(out, *sign) },
+        Simple::Right(id, sign) => { out.push(20); write_usize(out, *id); write_bool(out, *sign) },
+        Simple::Up(id, sign) => { out.push(21); write_usize(out, *id); write_bool(out, *sign) },
+        Simple::Left(id, sign) => { out.push(22); write_usize(out, *id); write_bool(out, *sign) },
+        Simple::Down(id, sign) => { out.push(23); write_usize(out, *id); write_bool(out, *sign) },
+        Simple::Chill(id, sign) => { out.push(24); write_usize(out, *id); write_bool(out, *sign) },
+        Simple::AllMove(sign) => { out.push(25); write_bool(out, *sign) },
+        Simple::AllTurn(sign) => { out.push(26); write_bool(out, *sign) },
+        Simple::AllFall(sign) => { out.push(27); write_bool(out, *sign) },
+        Simple::AllMore(sign) => { out.push(28); write_bool(out, *sign) },
+        Simple::AllLess(sign) => { out.push(29); write_bool(out, *sign) },
+        Simple::AllRight(sign) => { out.push(30); write_bool(out, *sign) },
+        Simple::AllUp(sign) => { out.push(31); write_bool(out, *sign) },
+        Simple::AllLeft(sign) => { out.push(32); write_bool(out, *sign) },
+        Simple::AllDown(sign) => { out.push(33); write_bool(out, *sign) },
+        Simple::AllChill(sign) => { out.push(34); write_bool(out, *sign) },
+        Simple::Shift(id, sign) => { out.push(35); write_usize(out, *id); write_bool(out, *sign) },
+        Simple::Sink(id) => { out.push(36); write_usize(out, *id) },
+        Simple::Swap(id) => { out.push(37); write_usize(out, *id) },
+        Simple::HasValue(id, target) => { out.push(38); write_usize(out, *id); write_usize(out, *target) },
+        Simple::MakeValue(id, target) => { out.push(39); write_usize(out, *id); write_usize(out, *target) },
+        Simple::Power(id, float) => { out.push(40); write_usize(out, *id); write_bool(out, *float) },
+        Simple::FearTele(id, target) => { out.push(41); write_usize(out, *id); write_usize(out, *target) },
+        Simple::FollowAttribute(id, target) => { out.push(42); write_usize(out, *id); write_usize(out, *target) },
+        Simple::EatValue(id, target) => { out.push(43); write_usize(out, *id); write_usize(out, *target) },
+        Simple::FeelType(id, target) => { out.push(44); write_usize(out, *id); write_usize(out, *target) },
+        Simple::EqualValue(id, target) => { out.push(45); write_usize(out, *id); write_usize(out, *target) },
+        Simple::WriteValue(id, target) => { out.push(46); write_usize(out, *id); write_usize(out, *target) },
+        Simple::TrackIndex(id, target) => { out.push(47); write_usize(out, *id); write_usize(out, *target) },
+        Simple::SpellDigits(id, target) => { out.push(48); write_usize(out, *id); write_usize(out, *target) },
+        Simple::Play(id, target) => { out.push(49); write_usize(out, *id); write_usize(out, *target) },
+        Simple::Both(id, sign) => { out.push(50); write_usize(out, *id); write_bool(out, *sign) },
+        Simple::Reset(id) => { out.push(51); write_usize(out, *id) },
+        Simple::FeedLevel(id, target) => { out.push(52); write_usize(out, *id); write_usize(out, *target) },
+        Simple::HasLiteral(id, bytes) => { out.push(53); write_usize(out, *id); write_bytes(out, bytes) },
+        Simple::PeekValue(id, target) => { out.push(54); write_usize(out, *id); write_usize(out, *target) },
+        Simple::Line(id) => { out.push(55); write_usize(out, *id) },
+        Simple::IsNumber(id, number, sign) => {
+            out.push(56);
+            write_usize(out, *id);
+            write_usize(out, *number as usize);
+            write_bool(out, *sign)
+        },
+        Simple::HasNumber(id, number) => { out.push(57); write_usize(out, *id); write_usize(out, *number as usize) },
+    }
+}
+
+/// Reads values written by `encode` from a buffer.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        match self.position.checked_add(count) {
+            Some(end) if end <= self.bytes.len() => {
+                let out = &self.bytes[self.position..end];
+                self.position = end;
+                Ok(out)
+            },
+            _ => Err(String::from("Unexpected end of file"))
+        }
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn usize(&mut self) -> Result<usize, String> {
+        let mut buffer = [0; 8];
+        buffer.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buffer) as usize)
+    }
+
+    fn bool(&mut self) -> Result<bool, String> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => Err(format!("Invalid boolean {}", other))
+        }
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.usize()?;
+        self.take(len)
+    }
+
+    fn ids(&mut self) -> Result<Vec<usize>, String> {
+        (0..self.usize()?).map(|_| self.usize()).collect()
+    }
+
+    /// Reads a keyword token written by its spelling.
+    fn keyword(&mut self) -> Result<Token, String> {
+        let spelled = self.bytes()?;
+        match parse(spelled, &mut HashMap::new()) {
+            Some(Token::Noun(_)) | None => Err(format!("Invalid keyword {:?}", String::from_utf8_lossy(spelled))),
+            Some(token) => Ok(token)
+        }
+    }
+
+    fn noun(&mut self) -> Result<Noun, String> {
+        match self.u8()? {
+            0 => Ok(Noun::All),
+            1 => Ok(Noun::Empty),
+            2 => Ok(Noun::Level),
+            3 => Ok(Noun::Image),
+            4 => Ok(Noun::Identifier(self.usize()?)),
+            5 => Ok(Noun::Literal(self.usize()?)),
+            6 => Ok(Noun::Number(self.usize()? as u32)),
+            other => Err(format!("Invalid noun tag {}", other))
+        }
+    }
+
+    fn target(&mut self) -> Result<Target, String> {
+        match self.u8()? {
+            0 => Ok(Target::Noun(self.noun()?)),
+            1 => match self.keyword()? {
+                Token::Property(property) => Ok(Target::Property(property)),
+                other => Err(format!("Expected a property, got {:?}", other))
+            },
+            other => Err(format!("Invalid target tag {}", other))
+        }
+    }
+
+    fn instructions(&mut self) -> Result<Vec<Instruction>, String> {
+        (0..self.usize()?).map(|_| self.instruction()).collect()
+    }
+
+    fn level(&mut self) -> Result<Level, String> {
+        Ok(Level {
+            float: self.bool()?,
+            identifier: self.usize()?,
+            arguments: self.ids()?,
+            instructions: self.instructions()?.into()
+        })
+    }
+
+    fn instruction(&mut self) -> Result<Instruction, String> {
+        Ok(match self.u8()? {
+            0 => Instruction::NoOp,
+            1 => Instruction::Simple(self.simple()?),
+            2 => {
+                let conditions = (0..self.usize()?).map(|_| {
+                    let cond_type = match self.keyword()? {
+                        Token::Conditional(conditional) => conditional,
+                        other => return Err(format!("Expected a conditional, got {:?}", other))
+                    };
+                    let targets = (0..self.usize()?)
+                        .map(|_| Ok((self.target()?, self.bool()?)))
+                        .collect::<Result<_, String>>()?;
+                    Ok(Conditions { cond_type, targets, sign: self.bool()? })
+                }).collect::<Result<_, _>>()?;
+                let prefix = if self.bool()? {
+                    match self.keyword()? {
+                        Token::Prefix(prefix) => Some(Prefixes { prefix, sign: self.bool()? }),
+                        other => return Err(format!("Expected a prefix, got {:?}", other))
+                    }
+                }
+                else {
+                    None
+                };
+                let combinator = if self.bool()? { Combinator::Or } else { Combinator::And };
+                Instruction::Complex(Complex { conditions, prefix, combinator, instruction: self.simple()? })
+            },
+            3 => Instruction::PartialTele(self.usize()?),
+            4 => Instruction::Tele(Tele { identifier: self.usize()?, instructions: self.instructions()?.into() }),
+            5 => Instruction::PartialLevel(self.usize()?),
+            6 => Instruction::Level(self.level()?),
+            7 => Instruction::PartialImage(self.usize()?),
+            8 => Instruction::Image(Image {
+                float: self.bool()?,
+                identifier: self.usize()?,
+                attributes: self.ids()?,
+                constructor: self.level()?
+            }),
+            9 => Instruction::PartialFloat(self.usize()?),
+            other => return Err(format!("Invalid instruction tag {}", other))
+        })
+    }
+
+    fn simple(&mut self) -> Result<Simple, String> {
+        Ok(match self.u8()? {
+            0 => Simple::InitYou(self.usize()?, self.bool()?),
+            1 => Simple::InitYou2(self.usize()?, self.bool()?),
+            2 => Simple::InitGroup(self.usize()?, self.bool()?),
+            3 => Simple::Win(self.usize()?),
+            4 => Simple::Defeat(self.usize()?),
+            5 => Simple::AllWin,
+            6 => Simple::AllDefeat,
+            7 => Simple::Sleep(self.usize()?),
+            8 => Simple::Text(self.usize()?),
+            9 => Simple::Word(self.usize()?),
+            10 => Simple::IsValue(self.usize()?, self.usize()?, self.bool()?),
+            11 => Simple::MimicReference(self.usize()?, self.usize()?, self.bool()?),
+            12 => Simple::IsEmpty(self.usize()?),
+            13 => Simple::IsLiteral(self.usize()?, self.bytes()?.to_vec()),
+            14 => {
+                let id = self.usize()?;
+                let nouns = (0..self.usize()?).map(|_| self.noun()).collect::<Result<_, _>>()?;
+                let signs = (0..self.usize()?).map(|_| self.bool()).collect::<Result<_, _>>()?;
+                Simple::IsSum(id, nouns, signs)
+            },
+            15 => Simple::Move(self.usize()?, self.bool()?),
+            16 => Simple::Turn(self.usize()?, self.bool()?),
+            17 => Simple::Fall(self.usize()?, self.bool()?),
+            18 => Simple::More(self.usize()?, self.bool()?),
+            19 => Simple::Less(self.usize()?, self.bool()?),
+            20 => Simple::Right(self.usize()?, self.bool()?),
+            21 => Simple::Up(self.usize()?, self.bool()?),
+            22 => Simple::Left(self.usize()?, self.bool()?),
+            23 => Simple::Down(self.usize()?, self.bool()?),
+            24 => Simple::Chill(self.usize()?, self.bool()?),
+            25 => Simple::AllMove(self.bool()?),
+            26 => Simple::AllTurn(self.bool()?),
+            27 => Simple::AllFall(self.bool()?),
+            28 => Simple::AllMore(self.bool()?),
+            29 => Simple::AllLess(self.bool()?),
+            30 => Simple::AllRight(self.bool()?),
+            31 => Simple::AllUp(self.bool()?),
+            32 => Simple::AllLeft(self.bool()?),
+            33 => Simple::AllDown(self.bool()?),
+            34 => Simple::AllChill(self.bool()?),
+            35 => Simple::Shift(self.usize()?, self.bool()?),
+            36 => Simple::Sink(self.usize()?),
+            37 => Simple::Swap(self.usize()?),
+            38 => Simple::HasValue(self.usize()?, self.usize()?),
+            39 => Simple::MakeValue(self.usize()?, self.usize()?),
+            40 => Simple::Power(self.usize()?, self.bool()?),
+            41 => Simple::FearTele(self.usize()?, self.usize()?),
+            42 => Simple::FollowAttribute(self.usize()?, self.usize()?),
+            43 => Simple::EatValue(self.usize()?, self.usize()?),
+            44 => Simple::FeelType(self.usize()?, self.usize()?),
+            45 => Simple::EqualValue(self.usize()?, self.usize()?),
+            46 => Simple::WriteValue(self.usize()?, self.usize()?),
+            47 => Simple::TrackIndex(self.usize()?, self.usize()?),
+            48 => Simple::SpellDigits(self.usize()?, self.usize()?),
+            49 => Simple::Play(self.usize()?, self.usize()?),
+            50 => Simple::Both(self.usize()?, self.bool()?),
+            51 => Simple::Reset(self.usize()?),
+            52 => Simple::FeedLevel(self.usize()?, self.usize()?),
+            53 => Simple::HasLiteral(self.usize()?, self.bytes()?.to_vec()),
+            54 => Simple::PeekValue(self.usize()?, self.usize()?),
+            55 => Simple::Line(self.usize()?),
+            56 => Simple::IsNumber(self.usize()?, self.usize()? as u32, self.bool()?),
+            57 => Simple::HasNumber(self.usize()?, self.usize()? as u32),
+            other => return Err(format!("Invalid simple instruction tag {}", other))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bast::{encode, decode};
+    use crate::compile;
+
+    fn round_trip(bytes: Vec<u8>) {
+        let (instructions, identifiers) = compile(&bytes).unwrap();
+        let encoded = encode(&instructions, &identifiers);
+        assert_eq!(decode(&encoded), Ok((instructions, identifiers)));
+    }
+
+    #[test]
+    fn round_trip_examples() {
+        for example in &["echo", "fibonacci", "hello_world"] {
+            round_trip(std::fs::read(format!("examples/{}.baba", example)).unwrap());
+        }
+    }
+
+    #[test]
+    fn round_trip_constructs() {
+        round_trip(b"
+            baba is you and move and not turn and both keke is you2 and not both and reset me is group it is float
+            it is you it is \"a\\x00\" keke is baba and not it
+            lonely baba near keke and empty is not move
+            not lonely baba or not on keke is move
+            not idle keke facing baba is text
+            all is fall all is win
+            f is level f has x and y x make y f is done
+            loop is tele baba is more loop is done
+            point is image point has px point is level point has self point is done point is done
+            me has baba me make keke baba mimic not keke baba play empty me feed f me has \"ab\"
+        ".to_vec());
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert!(decode(b"").is_err());
+        assert!(decode(b"BAST").is_err());
+        assert!(decode(b"BAST\x06").is_err());
+        let mut truncated = encode(&[], &Default::default());
+        assert_eq!(decode(&truncated), Ok((Vec::new(), Default::default())));
+        truncated.pop();
+        assert!(decode(&truncated).is_err());
+    }
+}
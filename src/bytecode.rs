@@ -0,0 +1,303 @@
+use crate::instruction::{Instruction, Simple, Complex, simple_kind};
+use crate::interpreter::{
+    Interpreter, Options, Scope, Objects, NO_BREAK, PRG_SCOPE, holds, initialize, power_call, store_result,
+    is_halted, enclosing_scope, program_scope, exit_code, level_object, image_object,
+    level_callback, constructor_callback
+};
+use crate::object::{Object, Type};
+use crate::ast::dump_simple;
+use crate::error_handler::{BabaError, log, set_context, clear_context, enter_scope, exit_scope};
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Write};
+
+/// A single operation of a compiled program.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op {
+    /// Executes a simple instruction.
+    Simple(Simple),
+    /// Executes the instruction of a conditional, if its prefix and conditions hold.
+    Complex(Complex),
+    /// Defines a LEVEL or IMAGE, whose callback starts at the offset in `object`.
+    Define { identifier: usize, object: Object, float: bool },
+    /// Starts an iteration of a TELE loop. `exit` is where execution continues
+    /// once the loop is broken.
+    Tele { identifier: usize, exit: usize },
+    /// Ends an iteration of the innermost TELE loop, jumping back to its start.
+    Repeat,
+    /// Returns from the program, or from a LEVEL or IMAGE.
+    Return
+}
+
+/// Compiles an AST into a flat list of operations, to be executed by `run_bytecode`.
+///
+/// The program starts at offset 0. TELE loops are lowered into jumps, and the
+/// callbacks of LEVELs and IMAGEs are placed after the program, so that
+/// POWER calls them by offset.
+pub fn compile(ast: &[Instruction]) -> Vec<Op> {
+    let mut ops = Vec::new();
+    // The callbacks yet to be compiled, along with the offset of their definitions
+    let mut callbacks = VecDeque::new();
+    lower(ast, &mut ops, &mut callbacks);
+    ops.push(Op::Return);
+    while let Some((definition, callback)) = callbacks.pop_front() {
+        let entry = ops.len();
+        if let Op::Define { object, .. } = &mut ops[definition] {
+            match &mut object.obj_type {
+                Type::Level(level) => level.entry = Some(entry),
+                Type::Image(image) => image.constructor.entry = Some(entry),
+                _ => ()
+            }
+        }
+        lower(&callback, &mut ops, &mut callbacks);
+        ops.push(Op::Return);
+    }
+    ops
+}
+
+/// Appends the operations of a scope. See `compile`.
+fn lower(instructions: &[Instruction], ops: &mut Vec<Op>, callbacks: &mut VecDeque<(usize, Vec<Instruction>)>) {
+    for instruction in instructions {
+        match instruction {
+            Instruction::Simple(simple) => ops.push(Op::Simple(simple.clone())),
+            Instruction::Complex(complex) => ops.push(Op::Complex(complex.clone())),
+            Instruction::Tele(tele) => {
+                let start = ops.len();
+                ops.push(Op::Tele { identifier: tele.identifier, exit: 0 });
+                lower(&tele.instructions, ops, callbacks);
+                ops.push(Op::Repeat);
+                ops[start] = Op::Tele { identifier: tele.identifier, exit: ops.len() };
+            },
+            Instruction::Level(level) => {
+                callbacks.push_back((ops.len(), level_callback(level)));
+                ops.push(Op::Define { identifier: level.identifier, object: level_object(level), float: level.float });
+            },
+            Instruction::Image(image) => {
+                callbacks.push_back((ops.len(), constructor_callback(image)));
+                ops.push(Op::Define { identifier: image.identifier, object: image_object(image), float: image.float });
+            },
+            _ => ()
+        }
+    }
+}
+
+/// Executes a compiled program with the given interpreter, as `Interpreter::exec`
+/// does with its AST.
+pub fn run_bytecode<R: Read, W: Write>(
+    interpreter: &mut Interpreter<R, W>,
+    ops: &[Op],
+    identifiers: &HashMap<usize, String>,
+    options: &Options
+) -> Result<Scope, BabaError> {
+    let mut scope = program_scope(identifiers);
+    let mut vm = Vm { interpreter, ops, identifiers, options };
+    let executed = vm.call(0, &mut scope.locals, &mut scope.globals, PRG_SCOPE);
+    clear_context();
+    interpreter.flush();
+    let (result, _) = executed?;
+    scope.exit_code = exit_code(result);
+    Ok(scope)
+}
+
+/// A scope being executed: the program, a call, or an iteration of a TELE loop.
+struct Frame {
+    // The identifier of the TELE, LEVEL or IMAGE, or PRG_SCOPE
+    scope: usize,
+    // The offset of the TELE starting the iteration
+    start: usize,
+    // The offset following the TELE loop
+    exit: usize,
+    // The value returned once the scope is exited
+    return_value: Option<Object>
+}
+
+struct Vm<'a, R: Read, W: Write> {
+    interpreter: &'a mut Interpreter<R, W>,
+    ops: &'a [Op],
+    identifiers: &'a HashMap<usize, String>,
+    options: &'a Options
+}
+
+impl<'a, R: Read, W: Write> Vm<'a, R, W> {
+    /// Executes the operations starting at `entry` in a new scope, until it returns.
+    ///
+    /// Returns the scope exited and the value returned, as `Interpreter::exec_with` does.
+    fn call(
+        &mut self,
+        entry: usize,
+        locals: &mut Objects,
+        globals: &mut Objects,
+        scope: usize
+    ) -> Result<(usize, Option<Object>), BabaError> {
+        let mut frames = vec![Frame { scope, start: entry, exit: entry, return_value: None }];
+        // Errors can report the scopes that lead to them
+        enter_scope(enclosing_scope(scope, self.identifiers));
+        let result = self.run(entry, &mut frames, locals, globals);
+        // The scopes left by an error
+        for _ in &frames {
+            exit_scope();
+        }
+        result
+    }
+
+    /// Executes operations until the outermost frame is exited. See `call`.
+    fn run(
+        &mut self,
+        entry: usize,
+        frames: &mut Vec<Frame>,
+        locals: &mut Objects,
+        globals: &mut Objects
+    ) -> Result<(usize, Option<Object>), BabaError> {
+        let mut pc = entry;
+        loop {
+            let scope = frames.last().map_or(PRG_SCOPE, |frame| frame.scope);
+            let enclosing = enclosing_scope(scope, self.identifiers);
+            let (result, returns) = match &self.ops[pc] {
+                Op::Simple(simple) => {
+                    set_context(simple_kind(simple), enclosing);
+                    self.simple(simple, locals, globals)?
+                },
+                Op::Complex(complex) => {
+                    set_context(simple_kind(&complex.instruction), enclosing);
+                    if holds(complex, locals, globals, self.identifiers, self.options)? {
+                        self.simple(&complex.instruction, locals, globals)?
+                    }
+                    else {
+                        (NO_BREAK, None)
+                    }
+                },
+                Op::Define { identifier, object, float } => {
+                    let kind = if let Type::Image(_) = object.obj_type { "IMAGE" } else { "LEVEL" };
+                    set_context(kind, enclosing);
+                    initialize(*identifier, object.clone(), *float, locals, globals, self.identifiers, self.options)?;
+                    (NO_BREAK, None)
+                },
+                Op::Tele { identifier, exit } => {
+                    set_context("TELE", enclosing);
+                    self.interpreter.count("TELE");
+                    enter_scope(enclosing_scope(*identifier, self.identifiers));
+                    frames.push(Frame { scope: *identifier, start: pc, exit: *exit, return_value: None });
+                    pc += 1;
+                    continue
+                },
+                Op::Repeat | Op::Return => (NO_BREAK, None)
+            };
+            let ends = matches!(self.ops[pc], Op::Repeat | Op::Return);
+            if result == NO_BREAK && returns.is_none() && !ends {
+                pc += 1;
+                continue
+            }
+            // The innermost frame is exited, along with the frames enclosing
+            // it up to the scope broken from
+            let mut returns = returns;
+            loop {
+                let frame = frames.pop().expect("the outermost frame returns");
+                exit_scope();
+                let value = returns.take().or(frame.return_value);
+                let parent = match frames.last_mut() {
+                    Some(parent) => parent,
+                    None => return Ok((result, value))
+                };
+                // Each iteration of a TELE loop replaces the value returned by its scope
+                parent.return_value = value;
+                if result == NO_BREAK {
+                    pc = frame.start;
+                    break
+                }
+                else if result == frame.scope {
+                    pc = frame.exit;
+                    break
+                }
+            }
+        }
+    }
+
+    /// Executes a simple instruction. POWER calls compiled callbacks by offset,
+    /// while everything else is left to the interpreter.
+    fn simple(
+        &mut self,
+        simple: &Simple,
+        locals: &mut Objects,
+        globals: &mut Objects
+    ) -> Result<(usize, Option<Object>), BabaError> {
+        let (id, float) = match simple {
+            Simple::Power(id, float) => (*id, *float),
+            _ => return self.interpreter.exec_simple(simple, locals, globals, self.identifiers, self.options)
+        };
+        self.interpreter.count(simple_kind(simple));
+        if self.options.trace {
+            log(&format!("{}\n", dump_simple(simple, self.identifiers)));
+        }
+        if let Some((callee, mut new_locals)) = power_call(&id, locals, globals, self.identifiers)? {
+            let (result, value) = match callee.entry {
+                Some(entry) => self.call(entry, &mut new_locals, globals, id)?,
+                // Defined outside of the compiled program, e.g. by a LEVEL called with FEED
+                None => self.interpreter.exec_callback(&callee, &mut new_locals, globals, id, self.identifiers, self.options)?
+            };
+            if is_halted(result) {
+                return Ok((result, None))
+            }
+            store_result(id, float, value, locals, globals);
+        }
+        Ok((NO_BREAK, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bytecode::{Op, compile, run_bytecode};
+    use crate::interpreter::{Interpreter, Options, dump_state};
+    use crate::instruction::Simple;
+    use crate::error_handler::ErrorType;
+
+    /// Runs a program through both backends, returning the output and final state of each.
+    fn both(source: &str, input: &[u8]) -> [Result<(Vec<u8>, String), ErrorType>; 2] {
+        let (instructions, identifiers) = crate::compile(source.as_bytes()).unwrap();
+        let options = Options::default();
+        let mut tree = Interpreter::new(input, Vec::new());
+        let tree_scope = tree.exec(&instructions, &identifiers, &options);
+        let mut vm = Interpreter::new(input, Vec::new());
+        let vm_scope = run_bytecode(&mut vm, &compile(&instructions), &identifiers, &options);
+        [(tree_scope, tree.into_output()), (vm_scope, vm.into_output())].map(|(scope, output)| {
+            scope.map(|scope| (output, dump_state(&scope, &identifiers))).map_err(|e| e.kind)
+        })
+    }
+
+    #[test]
+    fn lowered_loops() {
+        let (instructions, _) = crate::compile(b"baba is you loop is tele baba is move baba fear loop loop is done").unwrap();
+        assert_eq!(compile(&instructions), vec![
+            Op::Simple(Simple::InitYou(3, false)),
+            Op::Tele { identifier: 4, exit: 5 },
+            Op::Simple(Simple::Move(3, false)),
+            Op::Simple(Simple::FearTele(3, 4)),
+            Op::Repeat,
+            Op::Return
+        ]);
+    }
+
+    #[test]
+    fn same_as_tree() {
+        let sources = [
+            include_str!("../examples/hello_world.baba"),
+            include_str!("../examples/fibonacci.baba"),
+            // Breaks out of nested loops, and returns from a LEVEL
+            "baba is you baba is move baba is more
+             f is level f has x
+                 inner is tele outer is tele x is move x fear inner outer is done inner is done
+                 loop is tele x is more x is text x fear loop loop is done
+                 f make x
+             f is done
+             a is f a has baba a is power a is text",
+            "point is image point has px point is level point has self px is you self has px point is done point is done
+             p is point p is power p follow px px is text",
+            // Halts from inside a call
+            "baba is you f is level keke is you keke is win f is done f is power baba is text",
+            "baba is you baba is shift",
+        ];
+        for source in sources {
+            let [tree, vm] = both(source, b"input\n");
+            assert_eq!(tree, vm, "{}", source);
+        }
+    }
+}
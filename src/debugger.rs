@@ -0,0 +1,161 @@
+use crate::instruction::{Instruction, is_reserved};
+use crate::interpreter::{Interpreter, Execution, Options, Scope, Objects, enclosing_scope};
+use crate::error_handler::BabaError;
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+/// The state of a paused program: the instruction about to be executed,
+/// and every object it has access to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub instruction: Instruction,
+    // The identifier and name of the enclosing TELE, LEVEL or IMAGE,
+    // or None for the program scope
+    pub scope: Option<(usize, String)>,
+    // The identifier, name and value of each object, in order of identifier
+    pub locals: Vec<(usize, String, String)>,
+    pub globals: Vec<(usize, String, String)>,
+}
+
+impl Snapshot {
+    /// Returns the value of an object by name, looking in the locals first.
+    pub fn find(&self, name: &str) -> Option<&str> {
+        self.locals.iter()
+            .chain(self.globals.iter())
+            .find(|(_, object, _)| object == name)
+            .map(|(_, _, value)| value.as_str())
+    }
+}
+
+/// Runs a program one instruction at a time.
+///
+/// The program is paused before each instruction until it is resumed by `step`
+/// or `finish`. See `Interpreter::step`.
+pub struct Debugger<'a, R: Read, W: Write> {
+    interpreter: &'a mut Interpreter<R, W>,
+    execution: Execution<'a>,
+    identifiers: &'a HashMap<usize, String>,
+    // Whether the program is paused before the instruction of the last snapshot
+    paused: bool,
+    // The error that stopped the program, if any
+    error: Option<BabaError>
+}
+
+impl<'a, R: Read, W: Write> Debugger<'a, R, W> {
+    /// Starts a program on the given interpreter, paused before its first instruction.
+    pub fn new(
+        interpreter: &'a mut Interpreter<R, W>,
+        ast: &'a [Instruction],
+        identifiers: &'a HashMap<usize, String>,
+        options: &Options
+    ) -> Self {
+        let execution = interpreter.start(ast, identifiers, options);
+        Debugger { interpreter, execution, identifiers, paused: false, error: None }
+    }
+
+    /// Runs the program until the next instruction, and returns a snapshot taken
+    /// before it is executed. Returns None once the program has finished.
+    pub fn step(&mut self) -> Option<Snapshot> {
+        if self.paused {
+            self.paused = false;
+            if let Err(error) = self.interpreter.step(&mut self.execution) {
+                self.error = Some(error);
+            }
+        }
+        let instruction = self.execution.instruction()?.clone();
+        self.paused = true;
+        Some(Snapshot {
+            instruction,
+            scope: enclosing_scope(self.execution.scope(), self.identifiers).map(|(id, name)| (id, name.to_string())),
+            locals: summarize(self.execution.locals(), self.identifiers),
+            globals: summarize(self.execution.globals(), self.identifiers),
+        })
+    }
+
+    /// Runs the program to completion without pausing, returning its final scope
+    /// or the first error raised.
+    pub fn finish(self) -> Result<Scope, BabaError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => self.interpreter.finish(self.execution)
+        }
+    }
+}
+
+/// Describes every object in a scope, other than the built-in ones.
+fn summarize(objects: &Objects, identifiers: &HashMap<usize, String>) -> Vec<(usize, String, String)> {
+    objects.iter()
+        .filter(|(id, _)| !is_reserved(*id))
+        .map(|(id, obj)| (id, identifiers.get(&id).cloned().unwrap_or_default(), obj.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::debugger::Debugger;
+    use crate::interpreter::{Interpreter, Options};
+    use crate::instruction::{Instruction, Simple};
+    use crate::error_handler::ErrorType;
+    use crate::compile;
+    use std::io::{empty, sink};
+
+    #[test]
+    fn step_through() {
+        let (instructions, identifiers) = compile(b"baba is you loop is tele baba is move baba fear loop loop is done").unwrap();
+        let mut interpreter = Interpreter::new(empty(), sink());
+        let mut debugger = Debugger::new(&mut interpreter, &instructions, &identifiers, &Options::default());
+        let first = debugger.step().unwrap();
+        assert_eq!(first.instruction, Instruction::Simple(Simple::InitYou(3, false)));
+        assert_eq!(first.find("baba"), None);
+        let second = debugger.step().unwrap();
+        assert_eq!(second.scope, None);
+        assert_eq!(second.find("baba"), Some("YOU (0, 0) facing right"));
+        // Inside the TELE
+        let third = debugger.step().unwrap();
+        assert_eq!(third.instruction, Instruction::Simple(Simple::Move(3, false)));
+        assert_eq!(third.scope, Some((4, String::from("loop"))));
+        assert_eq!(debugger.step().unwrap().find("baba"), Some("YOU (1, 0) facing right"));
+    }
+
+    #[test]
+    fn step_into_call() {
+        let (instructions, identifiers) = compile(
+            b"f is level f has x x is move f is done baba is you g is f g has baba g is power baba is move"
+        ).unwrap();
+        let mut interpreter = Interpreter::new(empty(), sink());
+        let mut debugger = Debugger::new(&mut interpreter, &instructions, &identifiers, &Options::default());
+        let power = (0..5).filter_map(|_| debugger.step()).last().unwrap();
+        assert_eq!(power.scope, None);
+        // Only the argument and the LEVEL are local to the call
+        let call = debugger.step().unwrap();
+        assert!(matches!(call.instruction, Instruction::Simple(Simple::Move(_, false))));
+        assert_eq!(call.scope.as_ref().map(|(_, name)| name.as_str()), Some("g"));
+        assert_eq!((call.find("x"), call.find("baba")), (Some("YOU (0, 0) facing right"), None));
+        let after = debugger.step().unwrap();
+        assert_eq!(after.scope, None);
+        assert_eq!(after.find("baba"), Some("YOU (0, 0) facing right"));
+        assert_eq!(debugger.step(), None);
+    }
+
+    #[test]
+    fn run_to_completion() {
+        let (instructions, identifiers) = compile(b"baba is you baba is move baba is move").unwrap();
+        let mut interpreter = Interpreter::new(empty(), sink());
+        let mut debugger = Debugger::new(&mut interpreter, &instructions, &identifiers, &Options::default());
+        debugger.step().unwrap();
+        let scope = debugger.finish().unwrap();
+        assert_eq!(scope.locals[&3].to_string(), "YOU (2, 0) facing right");
+    }
+
+    #[test]
+    fn step_into_error() {
+        let (instructions, identifiers) = compile(b"baba is you baba is shift").unwrap();
+        let mut interpreter = Interpreter::new(empty(), sink());
+        let mut debugger = Debugger::new(&mut interpreter, &instructions, &identifiers, &Options::default());
+        assert!(debugger.step().is_some());
+        assert!(debugger.step().is_some());
+        assert_eq!(debugger.step(), None);
+        assert_eq!(debugger.finish().unwrap_err().kind, ErrorType::TypeError);
+    }
+}
@@ -1,10 +1,13 @@
-use std::process::exit;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::cell::{Cell, RefCell};
+use std::fmt::{self, Display, Formatter};
 
 use std::io::{stderr, Write};
 
+use crate::lexer::escape_json;
+
 /// Dictates the source of the error.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ErrorType {
     FileError,
     LexerError,
@@ -19,44 +22,646 @@ pub enum ErrorType {
     ConditionError,
 }
 
-/// Throws an exception and panics the current thread.
+/// An error raised by any stage of the interpreter.
+/// 
+/// The diagnostic is formatted when the error is raised, so that it reports 
+/// the context (e.g. the instruction being executed) in which it occurred.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BabaError {
+    pub kind: ErrorType,
+    pub message: String,
+    // The identifiers involved, along with their names
+    pub identifiers: Vec<(usize, String)>,
+    // The diagnostic as written to stderr
+    pub report: String
+}
+
+impl Display for BabaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.kind, self.message)
+    }
+}
+
+/// Creates an error with no identifiers involved.
+/// 
+/// # Arguments
+/// 
+/// * `error_type` - An enum variant that dictates the type of error raised.
+/// 
+/// * `error_message` - The message to display.
+pub fn error_str(error_type: ErrorType, error_message: &str) -> BabaError {
+    error(error_type, error_message.to_string(), None)
+}
+
+/// Creates an error, to be returned up to `main`.
 /// 
 /// # Arguments
 /// 
-/// * `error_type` - An enum variant that dictates the type of error thrown.
+/// * `error_type` - An enum variant that dictates the type of error raised.
+/// 
+/// * `error_message` - The message to display.
+/// 
+/// * `identifiers` - The identifiers involved in the error, along with the
+///   identifier map used to display them.
+pub fn error(
+    error_type: ErrorType, 
+    error_message: String, 
+    identifers: Option<(&[usize], &HashMap<usize, String>)>
+) -> BabaError {
+    let report = format_diagnostic(false, &error_type, &error_message, identifers);
+    BabaError {
+        kind: error_type,
+        identifiers: identifers.map_or(Vec::new(), |(used, ids)| used.iter()
+            .map(|id| (*id, ids.get(id).cloned().unwrap_or_default()))
+            .collect()
+        ),
+        message: error_message,
+        report
+    }
+}
+
+/// Writes the diagnostic of an error to stderr.
+pub fn report(error: &BabaError) {
+    // stderr may have been closed, e.g. when piped into `head`
+    let _ = write!(stderr(), "{}", error.report);
+}
+
+thread_local! {
+    // Diagnostics already reported by `warn`
+    static SEEN: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+    // Whether non-error output to stderr is silenced
+    static QUIET: Cell<bool> = const { Cell::new(false) };
+    // Whether diagnostics are written as JSON
+    static JSON: Cell<bool> = const { Cell::new(false) };
+    // The kind of the instruction being executed and its enclosing scope 
+    // (None for the program scope), if any
+    static CONTEXT: RefCell<(&'static str, Option<(usize, String)>)> = const { RefCell::new(("", None)) };
+    // Whether errors are followed by a backtrace of scopes
+    static BACKTRACE: Cell<bool> = const { Cell::new(false) };
+    // The scopes being executed, from outermost to innermost (None for the program scope)
+    static SCOPES: RefCell<Vec<Option<(usize, String)>>> = const { RefCell::new(Vec::new()) };
+    // The byte offset of each line in the source
+    static LINES: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+    // The byte span of the statement being parsed, if any
+    static SPAN: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+/// Records the instruction being executed (e.g. `MOVE`) and its enclosing scope,
+/// given by identifier and name (None for the program scope), so that 
+/// diagnostics raised at runtime point to where they occurred.
+/// 
+/// This is called for every instruction executed, so the buffer is reused.
+pub fn set_context(instruction: &'static str, scope: Option<(usize, &str)>) {
+    CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        context.0 = instruction;
+        match (&mut context.1, scope) {
+            (Some((old_id, old_name)), Some((id, name))) => {
+                *old_id = id;
+                old_name.clear();
+                old_name.push_str(name);
+            },
+            (old, scope) => *old = scope.map(|(id, name)| (id, name.to_string()))
+        }
+    });
+}
+
+/// Clears the context recorded by `set_context`.
+pub fn clear_context() {
+    CONTEXT.with(|context| context.borrow_mut().0 = "");
+}
+
+/// Records where each line of the source starts, so that the spans given
+/// to `set_span` can be reported along with their line number.
 /// 
-/// * `error_message` - The message to display on panic.
-pub fn throw_error_str(error_type: ErrorType, error_message: &str) {
-    stderr().write(format!("{:?}: {}\n", error_type, error_message).as_bytes()).unwrap();
-    exit(1);
+/// This starts a new run: warnings reported for a previous source (e.g. by an
+/// earlier `run` on the same thread) are reported again.
+pub fn set_source(source: &[u8]) {
+    let lines = std::iter::once(0)
+        .chain(source.iter().enumerate().filter(|&(_, &byte)| byte == b'\n').map(|(i, _)| i + 1))
+        .collect();
+    LINES.with(|cell| *cell.borrow_mut() = lines);
+    SEEN.with(|seen| seen.borrow_mut().clear());
+}
+
+/// Records the byte span of the statement being parsed (None once parsing is done), 
+/// so that diagnostics raised while parsing it point to it.
+pub fn set_span(span: Option<(usize, usize)>) {
+    SPAN.with(|cell| cell.set(span));
+}
+
+/// Returns the line (starting from 1) containing a byte offset of the source.
+fn line_of(offset: usize) -> usize {
+    LINES.with(|lines| {
+        let lines = lines.borrow();
+        // The first line starts at 0, unless no source was given
+        lines.iter().take_while(|&&start| start <= offset).count().max(1)
+    })
+}
+
+/// Follows errors with a backtrace of the scopes being executed,
+/// as recorded by `enter_scope` and `exit_scope`.
+pub fn set_backtrace(backtrace: bool) {
+    BACKTRACE.with(|cell| cell.set(backtrace));
+}
+
+/// Records that a scope, given by identifier and name (None for the program scope),
+/// has been entered. Does nothing unless backtraces are enabled with `set_backtrace`.
+pub fn enter_scope(scope: Option<(usize, &str)>) {
+    if BACKTRACE.with(|cell| cell.get()) {
+        SCOPES.with(|scopes| scopes.borrow_mut().push(scope.map(|(id, name)| (id, name.to_string()))));
+    }
+}
+
+/// Records that the innermost scope entered with `enter_scope` has been exited.
+pub fn exit_scope() {
+    if BACKTRACE.with(|cell| cell.get()) {
+        SCOPES.with(|scopes| scopes.borrow_mut().pop());
+    }
+}
+
+/// Writes diagnostics as JSON objects (one per line) rather than as text.
+pub fn set_json(json: bool) {
+    JSON.with(|cell| cell.set(json));
+}
+
+/// Silences (or restores) all output to stderr other than errors,
+/// i.e. anything written through `log`.
+pub fn set_quiet(quiet: bool) {
+    QUIET.with(|cell| cell.set(quiet));
+}
+
+/// Writes a non-fatal message (e.g. a warning or timing report) to stderr,
+/// unless output has been silenced with `set_quiet`. 
+/// Returns whether the message was written.
+pub fn log(message: &str) -> bool {
+    let quiet = QUIET.with(|cell| cell.get());
+    if !quiet {
+        let _ = write!(stderr(), "{}", message);
+    }
+    !quiet
 }
 
-/// Throws an exception and panics the current thread.
+/// Reports a non-fatal diagnostic without exiting.
+/// 
+/// Identical diagnostics (same type, message and identifiers) are only
+/// reported once, so that a warning raised inside a loop doesn't flood the
+/// output. Returns whether the diagnostic was reported for the first time,
+/// even if it was silenced by `set_quiet`.
 /// 
 /// # Arguments
 /// 
-/// * `error_type` - An enum variant that dictates the type of error thrown.
+/// * `error_type` - An enum variant that dictates the type of error reported.
+/// 
+/// * `error_message` - The message to display.
 /// 
-/// * `error_message` - The message to display on panic.
-pub fn throw_error(
+/// * `identifiers` - The identifiers involved in the error, along with the
+///   identifier map used to display them.
+pub fn warn(
     error_type: ErrorType, 
     error_message: String, 
     identifers: Option<(&[usize], &HashMap<usize, String>)>
-) {
-    let mut handle = stderr();
-    handle.write(format!("{:?}: {}\n", error_type, error_message).as_bytes()).unwrap();
+) -> bool {
+    let formatted = format_diagnostic(true, &error_type, &error_message, identifers);
+    let new = SEEN.with(|seen| seen.borrow_mut().insert(formatted.clone()));
+    if new {
+        log(&formatted);
+    }
+    new
+}
+
+/// Returns the number of distinct diagnostics reported by `warn` 
+/// since the last call to `set_source`.
+pub fn warning_count() -> usize {
+    SEEN.with(|seen| seen.borrow().len())
+}
+
+/// Formats an error or a warning in the format chosen with `set_json`.
+fn format_diagnostic(
+    warning: bool,
+    error_type: &ErrorType, 
+    error_message: &str, 
+    identifers: Option<(&[usize], &HashMap<usize, String>)>
+) -> String {
+    if JSON.with(|cell| cell.get()) {
+        format_json(warning, error_type, error_message, identifers)
+    }
+    else if warning {
+        format!("Warning: {}", format_error(error_type, error_message, identifers))
+    }
+    else {
+        format!("{}{}", format_error(error_type, error_message, identifers), format_backtrace())
+    }
+}
+
+/// Formats the scopes being executed, from innermost to outermost, 
+/// if backtraces are enabled and any scope is being executed.
+fn format_backtrace() -> String {
+    if !BACKTRACE.with(|cell| cell.get()) || SCOPES.with(|scopes| scopes.borrow().is_empty()) {
+        return String::new();
+    }
+    let mut out = String::from("Babalang backtrace:\n");
+    SCOPES.with(|scopes| {
+        for (i, scope) in scopes.borrow().iter().rev().enumerate() {
+            match scope {
+                Some((id, name)) => out.push_str(&format!("{:>5}: scope {} = \"{}\"\n", i, id, name)),
+                None => out.push_str(&format!("{:>5}: the program scope\n", i))
+            }
+        }
+    });
+    out
+}
+
+/// Formats a diagnostic along with the identifiers involved.
+fn format_error(
+    error_type: &ErrorType, 
+    error_message: &str, 
+    identifers: Option<(&[usize], &HashMap<usize, String>)>
+) -> String {
+    let mut out = format!("{:?}: {}\n", error_type, error_message);
     if let Some((used, ids)) = identifers {
-        handle.write("[Identifiers: ".as_bytes()).unwrap();
+        out.push_str("[Identifiers: ");
         for (i, id) in used.iter().enumerate() {
             // Unwrap is used since errors should only be raised for existing values
             if i == 0 {
-                handle.write(format!("{} = \"{}\"", id, ids.get(id).unwrap()).as_bytes()).unwrap();
+                out.push_str(&format!("{} = \"{}\"", id, ids.get(id).unwrap()));
             }
             else {
-                handle.write(format!(", {} = \"{}\"", id, ids.get(id).unwrap()).as_bytes()).unwrap();
+                out.push_str(&format!(", {} = \"{}\"", id, ids.get(id).unwrap()));
+            }
+        }
+        out.push_str("]\n");
+    }
+    if let Some((start, end)) = SPAN.with(|cell| cell.get()) {
+        out.push_str(&format!("[Statement: line {}, bytes {}..{}]\n", line_of(start), start, end));
+    }
+    CONTEXT.with(|context| {
+        match &*context.borrow() {
+            ("", _) => (),
+            (instruction, Some((id, name))) => {
+                out.push_str(&format!("[Instruction: {}, in scope {} = \"{}\"]\n", instruction, id, name));
+            },
+            (instruction, None) => {
+                out.push_str(&format!("[Instruction: {}, in the program scope]\n", instruction));
             }
         }
-        handle.write("]\n".as_bytes()).unwrap();
+    });
+    out
+}
+
+/// Formats a diagnostic as a single-line JSON object, with the fields:
+/// 
+/// * `severity` - Either `"error"` or `"warning"`.
+/// 
+/// * `kind` - The error type, e.g. `"TypeError"`.
+/// 
+/// * `message` - The message describing the diagnostic.
+/// 
+/// * `identifiers` - The identifiers involved, each with its `id` and `name`.
+/// 
+/// * `location` - The `instruction` being executed and its enclosing `scope` (an `id` 
+///   and `name`, or `null` for the program scope), or `null` outside of execution.
+/// 
+/// * `span` - Only while parsing. The `line` of the statement being parsed,
+///   along with the `start` and `end` byte offsets of the statement.
+/// 
+/// * `backtrace` - Only for errors, if backtraces are enabled. The scopes being executed,
+///   from innermost to outermost, in the same form as `scope`.
+fn format_json(
+    warning: bool,
+    error_type: &ErrorType, 
+    error_message: &str, 
+    identifers: Option<(&[usize], &HashMap<usize, String>)>
+) -> String {
+    let identifiers = match identifers {
+        Some((used, ids)) => used.iter()
+            .map(|id| format!(
+                "{{\"id\": {}, \"name\": \"{}\"}}", 
+                id, escape_json(ids.get(id).map_or("", String::as_str))
+            ))
+            .collect::<Vec<String>>(),
+        None => Vec::new()
+    };
+    let location = CONTEXT.with(|context| {
+        match &*context.borrow() {
+            ("", _) => String::from("null"),
+            (instruction, Some((id, name))) => format!(
+                "{{\"instruction\": \"{}\", \"scope\": {{\"id\": {}, \"name\": \"{}\"}}}}", 
+                instruction, id, escape_json(name)
+            ),
+            (instruction, None) => format!("{{\"instruction\": \"{}\", \"scope\": null}}", instruction)
+        }
+    });
+    let span = match SPAN.with(|cell| cell.get()) {
+        Some((start, end)) => format!(
+            ", \"span\": {{\"line\": {}, \"start\": {}, \"end\": {}}}", 
+            line_of(start), start, end
+        ),
+        None => String::new()
+    };
+    let backtrace = if !warning && BACKTRACE.with(|cell| cell.get()) {
+        let scopes = SCOPES.with(|scopes| scopes.borrow().iter().rev()
+            .map(|scope| match scope {
+                Some((id, name)) => format!("{{\"id\": {}, \"name\": \"{}\"}}", id, escape_json(name)),
+                None => String::from("null")
+            })
+            .collect::<Vec<String>>()
+        );
+        format!(", \"backtrace\": [{}]", scopes.join(", "))
+    }
+    else {
+        String::new()
+    };
+    format!(
+        "{{\"severity\": \"{}\", \"kind\": \"{:?}\", \"message\": \"{}\", \"identifiers\": [{}], \"location\": {}{}{}}}\n",
+        if warning { "warning" } else { "error" }, 
+        error_type, 
+        escape_json(error_message), 
+        identifiers.join(", "), 
+        location,
+        span,
+        backtrace
+    )
+}
+
+/// Every error type, in order of their numeric codes (starting from 1).
+pub const ERROR_TYPES: [ErrorType; 11] = [
+    ErrorType::FileError,
+    ErrorType::LexerError,
+    ErrorType::StatementParserError,
+    ErrorType::InstructionParserError,
+    ErrorType::InstructionValidationError,
+    ErrorType::RuntimeError,
+    ErrorType::ObjectNotDefinedError,
+    ErrorType::ObjectAlreadyDefinedError,
+    ErrorType::TypeError,
+    ErrorType::ArgumentError,
+    ErrorType::ConditionError,
+];
+
+/// Finds an error type by its name (e.g. `TypeError`, case-insensitively)
+/// or by its numeric code.
+pub fn find_error_type(name: &str) -> Option<ErrorType> {
+    if let Ok(code) = name.parse::<usize>() {
+        return code.checked_sub(1).and_then(|i| ERROR_TYPES.get(i)).copied();
     }
-    exit(1);
-}
\ No newline at end of file
+    ERROR_TYPES.iter()
+        .find(|error_type| format!("{:?}", error_type).eq_ignore_ascii_case(name))
+        .copied()
+}
+
+/// Returns the numeric code of an error type.
+pub fn code(error_type: &ErrorType) -> usize {
+    match error_type {
+        ErrorType::FileError => 1,
+        ErrorType::LexerError => 2,
+        ErrorType::StatementParserError => 3,
+        ErrorType::InstructionParserError => 4,
+        ErrorType::InstructionValidationError => 5,
+        ErrorType::RuntimeError => 6,
+        ErrorType::ObjectNotDefinedError => 7,
+        ErrorType::ObjectAlreadyDefinedError => 8,
+        ErrorType::TypeError => 9,
+        ErrorType::ArgumentError => 10,
+        ErrorType::ConditionError => 11,
+    }
+}
+
+/// Returns a longer explanation of an error type, along with its common causes.
+pub fn explain(error_type: &ErrorType) -> &'static str {
+    match error_type {
+        ErrorType::FileError => "\
+The source file, or another file given on the command line, could not be used.
+
+Common causes:
+* The path doesn't exist, or can't be read or written.
+* No source file or `-c` program was given.
+* A file passed to `--run` isn't an AST written by `--compile`, or was written by another version.",
+        ErrorType::LexerError => "\
+The source could not be split into tokens.
+
+Common causes:
+* A string literal is missing its closing quote.
+* A string literal contains an unknown escape such as `\\q`, or a malformed `\\x` escape.
+* An unexpected control byte outside of comments, unless `--lenient` is given.",
+        ErrorType::StatementParserError => "\
+The tokens could not be arranged into statements (subject, verb and targets).
+
+Common causes:
+* A statement is missing its verb, e.g. `BABA MOVE` instead of `BABA IS MOVE`.
+* A word appears where the grammar doesn't allow it, e.g. a property after a conditional.
+* The program ends in the middle of a statement.
+* OR is used without a prefix before it.",
+        ErrorType::InstructionParserError => "\
+The statements could not be arranged into blocks of instructions.
+
+Common causes:
+* `X IS DONE` doesn't match the innermost open TELE, LEVEL or IMAGE.
+* A TELE, LEVEL or IMAGE is never closed.
+* An IMAGE body contains something other than attributes and a constructor.",
+        ErrorType::InstructionValidationError => "\
+A statement is well-formed, but doesn't describe a valid instruction.
+
+Common causes:
+* A built-in object (EMPTY, LEVEL, IMAGE or ALL) is used where an identifier is required.
+* A condition or prefix is applied to ALL, or to an initialization such as `X IS YOU`.
+* A LEVEL argument or IMAGE attribute is declared twice, or shadows a built-in object.",
+        ErrorType::RuntimeError => "\
+Something went wrong while executing the program, outside of the other categories.
+
+Common causes:
+* An instruction the interpreter didn't expect, such as an unknown target in a sum.
+  This usually indicates a bug in the interpreter.",
+        ErrorType::ObjectNotDefinedError => "\
+An object was used before being defined in the current scope. Reading such 
+an object only warns and reads it as EMPTY, unless `--strict-undef` is given.
+
+Common causes:
+* A typo in the name of the object.
+* The object is local to a LEVEL, and is used after the LEVEL is DONE.
+* The object was defined inside a LEVEL that hasn't been POWER yet.
+* A weak reference (`X MIMIC NOT Y`) is used after its target stopped existing.",
+        ErrorType::ObjectAlreadyDefinedError => "\
+An object was defined again with a different type.
+
+Common causes:
+* A name is reused for two different purposes, e.g. `BABA IS YOU` and later `BABA IS GROUP`.
+  This is only an error with `--strict`; otherwise a warning is printed.
+* Adding (IS with AND) objects that don't support addition.",
+        ErrorType::TypeError => "\
+An instruction was applied to an object of the wrong type.
+
+Common causes:
+* A YOU property (e.g. MOVE) applied to a GROUP, or a GROUP property (e.g. SHIFT) to a YOU.
+* POWER applied to something other than a LEVEL or IMAGE.
+* A conditional comparing objects that can't be compared.",
+        ErrorType::ArgumentError => "\
+A LEVEL or IMAGE was called with the wrong arguments, or a command-line option was misused.
+
+Common causes:
+* POWER before every argument was supplied with HAS.
+* An IMAGE constructor without an instance argument.
+* An option such as `--max-line` given without its value.",
+        ErrorType::ConditionError => "\
+A condition or prefix could not be evaluated.
+
+Common causes:
+* A condition applied to an instruction without a single subject, such as one applying to ALL.",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{warn, warning_count, log, set_quiet, set_context, clear_context, set_json, format_error, format_diagnostic, set_source, set_span, set_backtrace, enter_scope, exit_scope, format_backtrace, error, error_str, ErrorType, ERROR_TYPES, find_error_type, code, explain};
+    use std::collections::HashMap;
+
+    #[test]
+    fn format_with_identifiers() {
+        let mut ids = HashMap::new();
+        ids.insert(3, String::from("baba"));
+        let formatted = format_error(
+            &ErrorType::TypeError, 
+            "Object 3 is bad", 
+            Some((&[3], &ids))
+        );
+        assert_eq!(formatted, "TypeError: Object 3 is bad\n[Identifiers: 3 = \"baba\"]\n");
+    }
+
+    #[test]
+    fn format_with_context() {
+        set_context("MOVE", Some((3, "baba")));
+        let formatted = format_error(&ErrorType::TypeError, "Object 4 is bad", None);
+        assert_eq!(formatted, "TypeError: Object 4 is bad\n[Instruction: MOVE, in scope 3 = \"baba\"]\n");
+        clear_context();
+        assert_eq!(format_error(&ErrorType::TypeError, "Object 4 is bad", None), "TypeError: Object 4 is bad\n");
+    }
+
+    #[test]
+    fn format_as_json() {
+        let mut ids = HashMap::new();
+        ids.insert(3, String::from("baba"));
+        set_json(true);
+        assert_eq!(
+            format_diagnostic(false, &ErrorType::TypeError, "Object \"3\" is bad", Some((&[3], &ids))),
+            "{\"severity\": \"error\", \"kind\": \"TypeError\", \"message\": \"Object \\\"3\\\" is bad\", \
+            \"identifiers\": [{\"id\": 3, \"name\": \"baba\"}], \"location\": null}\n"
+        );
+        set_context("TEXT", None);
+        assert_eq!(
+            format_diagnostic(true, &ErrorType::RuntimeError, "Bad", None),
+            "{\"severity\": \"warning\", \"kind\": \"RuntimeError\", \"message\": \"Bad\", \
+            \"identifiers\": [], \"location\": {\"instruction\": \"TEXT\", \"scope\": null}}\n"
+        );
+        clear_context();
+        set_json(false);
+        assert_eq!(format_diagnostic(true, &ErrorType::RuntimeError, "Bad", None), "Warning: RuntimeError: Bad\n");
+    }
+
+    #[test]
+    fn error_values() {
+        let mut ids = HashMap::new();
+        ids.insert(3, String::from("baba"));
+        set_context("MOVE", None);
+        let err = error(ErrorType::TypeError, String::from("Object 3 is bad"), Some((&[3], &ids)));
+        // The report is formatted when the error is raised
+        clear_context();
+        assert_eq!(err.identifiers, vec![(3, String::from("baba"))]);
+        assert_eq!(err.to_string(), "TypeError: Object 3 is bad");
+        assert_eq!(err.report, "TypeError: Object 3 is bad\n[Identifiers: 3 = \"baba\"]\n[Instruction: MOVE, in the program scope]\n");
+        let err = error_str(ErrorType::FileError, "File not provided");
+        assert_eq!((err.kind, err.identifiers), (ErrorType::FileError, Vec::new()));
+    }
+
+    #[test]
+    fn format_with_span() {
+        set_source(b"baba is you\n\nkeke is baba\n");
+        set_span(Some((13, 25)));
+        assert_eq!(
+            format_error(&ErrorType::InstructionValidationError, "Bad", None),
+            "InstructionValidationError: Bad\n[Statement: line 3, bytes 13..25]\n"
+        );
+        set_json(true);
+        assert_eq!(
+            format_diagnostic(true, &ErrorType::InstructionValidationError, "Bad", None),
+            "{\"severity\": \"warning\", \"kind\": \"InstructionValidationError\", \"message\": \"Bad\", \
+            \"identifiers\": [], \"location\": null, \"span\": {\"line\": 3, \"start\": 13, \"end\": 25}}\n"
+        );
+        set_json(false);
+        set_span(Some((0, 4)));
+        assert_eq!(format_error(&ErrorType::TypeError, "Bad", None), "TypeError: Bad\n[Statement: line 1, bytes 0..4]\n");
+        set_span(None);
+        assert_eq!(format_error(&ErrorType::TypeError, "Bad", None), "TypeError: Bad\n");
+    }
+
+    #[test]
+    fn format_backtraces() {
+        assert_eq!(format_backtrace(), "");
+        set_backtrace(true);
+        // Errors raised before execution
+        assert_eq!(format_backtrace(), "");
+        enter_scope(None);
+        enter_scope(Some((3, "f")));
+        enter_scope(Some((5, "loop")));
+        assert_eq!(
+            format_diagnostic(false, &ErrorType::TypeError, "Bad", None),
+            "TypeError: Bad\nBabalang backtrace:\n    0: scope 5 = \"loop\"\n    1: scope 3 = \"f\"\n    2: the program scope\n"
+        );
+        // Warnings don't include a backtrace
+        assert_eq!(format_diagnostic(true, &ErrorType::TypeError, "Bad", None), "Warning: TypeError: Bad\n");
+        exit_scope();
+        set_json(true);
+        assert_eq!(
+            format_diagnostic(false, &ErrorType::TypeError, "Bad", None),
+            "{\"severity\": \"error\", \"kind\": \"TypeError\", \"message\": \"Bad\", \"identifiers\": [], \
+            \"location\": null, \"backtrace\": [{\"id\": 3, \"name\": \"f\"}, null]}\n"
+        );
+        set_json(false);
+        exit_scope();
+        exit_scope();
+        set_backtrace(false);
+        assert_eq!(format_backtrace(), "");
+    }
+
+    #[test]
+    fn warn_deduplicates() {
+        let mut ids = HashMap::new();
+        ids.insert(3, String::from("baba"));
+        ids.insert(4, String::from("keke"));
+        let message = || String::from("Duplicate diagnostic");
+        assert!(warn(ErrorType::RuntimeError, message(), Some((&[3], &ids))));
+        assert!(!warn(ErrorType::RuntimeError, message(), Some((&[3], &ids))));
+        // Different location or type is a different diagnostic
+        assert!(warn(ErrorType::RuntimeError, message(), Some((&[4], &ids))));
+        assert!(warn(ErrorType::TypeError, message(), Some((&[3], &ids))));
+        assert_eq!(warning_count(), 3);
+        // Each source is a new run
+        set_source(b"baba is you");
+        assert_eq!(warning_count(), 0);
+        assert!(warn(ErrorType::RuntimeError, message(), Some((&[3], &ids))));
+    }
+
+    #[test]
+    fn quiet_log() {
+        set_quiet(true);
+        assert!(!log("Silenced message\n"));
+        // Silenced warnings are still deduplicated
+        assert!(warn(ErrorType::RuntimeError, String::from("Silenced diagnostic"), None));
+        assert!(!warn(ErrorType::RuntimeError, String::from("Silenced diagnostic"), None));
+        set_quiet(false);
+        assert!(log(""));
+    }
+
+    #[test]
+    fn explain_all() {
+        for (i, error_type) in ERROR_TYPES.iter().enumerate() {
+            assert_eq!(code(error_type), i + 1);
+            assert_eq!(find_error_type(&format!("{:?}", error_type)), Some(*error_type));
+            assert_eq!(find_error_type(&(i + 1).to_string()), Some(*error_type));
+            assert!(!explain(error_type).is_empty());
+        }
+        assert_eq!(find_error_type("typeerror"), Some(ErrorType::TypeError));
+        assert_eq!(find_error_type("0"), None);
+        assert_eq!(find_error_type("12"), None);
+        assert_eq!(find_error_type("Babaerror"), None);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::run_in_memory;
+use crate::error_handler::code;
+
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// Returned by `babalang_run` when the program ran without error,
+/// including when it was stopped by WIN or DEFEAT.
+pub const BABALANG_OK: c_int = 0;
+/// Returned by `babalang_run` when `source` or `out` is null.
+pub const BABALANG_INVALID_ARGUMENT: c_int = -1;
+/// Returned by `babalang_run` when the interpreter panicked.
+pub const BABALANG_PANIC: c_int = -2;
+
+/// Executes a Babalang program from C.
+///
+/// `source` is the program, and `input` the input read by WORD (or null for
+/// no input), both as NUL-terminated strings. They are only borrowed for the
+/// duration of the call.
+///
+/// Returns `BABALANG_OK`, the code of the error that stopped the program
+/// (1 to 11, as with the exit codes of the interpreter), or one of the negative
+/// `BABALANG_*` codes.
+///
+/// Unless `BABALANG_INVALID_ARGUMENT` is returned, `*out` is set to a new
+/// NUL-terminated string holding everything the program wrote, followed by the
+/// diagnostic of the error that stopped it, if any. NUL bytes written by the
+/// program are left out. The string is owned by the caller, and must be freed
+/// with `babalang_free` (not `free`). If the interpreter panicked, `*out` is null.
+///
+/// # Safety
+///
+/// `source` and `input` must be null or point to NUL-terminated strings, and
+/// `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn babalang_run(source: *const c_char, input: *const c_char, out: *mut *mut c_char) -> c_int {
+    if source.is_null() || out.is_null() {
+        return BABALANG_INVALID_ARGUMENT
+    }
+    let source = CStr::from_ptr(source).to_bytes();
+    let input = if input.is_null() { &[] } else { CStr::from_ptr(input).to_bytes() };
+    // Panics must not unwind into the caller
+    let ran = catch_unwind(AssertUnwindSafe(|| run_in_memory(source, input)));
+    let (mut output, result) = match ran {
+        Ok(ran) => ran,
+        Err(_) => {
+            *out = ptr::null_mut();
+            return BABALANG_PANIC
+        }
+    };
+    let status = match result {
+        Ok(_) => BABALANG_OK,
+        Err(e) => {
+            output.extend_from_slice(e.report.as_bytes());
+            code(&e.kind) as c_int
+        }
+    };
+    output.retain(|&byte| byte != 0);
+    // No NUL bytes are left
+    *out = CString::new(output).unwrap_or_default().into_raw();
+    status
+}
+
+/// Frees a string returned through `babalang_run`. Does nothing given null.
+///
+/// # Safety
+///
+/// `out` must be null or a string returned through `babalang_run` that hasn't
+/// been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn babalang_free(out: *mut c_char) {
+    if !out.is_null() {
+        drop(CString::from_raw(out));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ffi::{babalang_run, babalang_free, BABALANG_OK, BABALANG_INVALID_ARGUMENT};
+    use std::ffi::{CStr, CString};
+    use std::os::raw::c_char;
+    use std::ptr;
+
+    /// Runs a program through the FFI, returning the status code and output.
+    fn run(source: &str, input: Option<&str>) -> (i32, String) {
+        let source = CString::new(source).unwrap();
+        let input = input.map(|input| CString::new(input).unwrap());
+        let mut out: *mut c_char = ptr::null_mut();
+        unsafe {
+            let status = babalang_run(
+                source.as_ptr(),
+                input.as_ref().map_or(ptr::null(), |input| input.as_ptr()),
+                &mut out
+            );
+            let output = CStr::from_ptr(out).to_string_lossy().into_owned();
+            babalang_free(out);
+            (status, output)
+        }
+    }
+
+    #[test]
+    fn run_through_ffi() {
+        let echo = "baba is group baba is word baba is text";
+        assert_eq!(run(echo, Some("hi\n")), (BABALANG_OK, String::from("hi\n")));
+        assert_eq!(run(echo, None), (BABALANG_OK, String::new()));
+        // TypeError
+        let (status, output) = run("baba is you baba is shift", None);
+        assert_eq!(status, 9);
+        assert!(output.starts_with("TypeError"), "{:?}", output);
+        unsafe {
+            assert_eq!(babalang_run(ptr::null(), ptr::null(), ptr::null_mut()), BABALANG_INVALID_ARGUMENT);
+            babalang_free(ptr::null_mut());
+        }
+    }
+}
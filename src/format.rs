@@ -0,0 +1,178 @@
+use crate::lexer::lex;
+use crate::statement::{Statement, Target};
+use crate::statement_parser;
+use crate::token::{Token, Noun, Verb, Property, spelling};
+use crate::error_handler::{BabaError, set_source};
+
+use std::collections::HashMap;
+
+/// The indentation of each level of TELE, LEVEL and IMAGE scopes.
+const INDENT: &str = "    ";
+
+/// Formats Babalang source code in a canonical layout.
+///
+/// Each statement is written on its own line, with its tokens separated by
+/// single spaces and its keywords in uppercase. The bodies of TELE, LEVEL and
+/// IMAGE scopes are indented, up to the matching `IS DONE`.
+///
+/// Comments are kept. Those inside a statement, or following it on the same
+/// line, are moved to the end of its line, while the others keep their own lines.
+/// Runs of blank lines are collapsed into one.
+///
+/// Formatting the output again leaves it unchanged.
+pub fn format_source(src: &str) -> Result<String, BabaError> {
+    let buffer = src.as_bytes();
+    set_source(buffer);
+    let (tokens, spans, comments, identifiers) = lex(buffer, false)?;
+    let statements = statement_parser::parse(&tokens, &spans, &identifiers)?;
+    // Statements split from an AND chain (e.g. `X IS LEVEL AND HAS Y`) overlap, 
+    // and share a line
+    let mut lines: Vec<((usize, usize), Vec<&Statement>)> = Vec::new();
+    for statement in &statements {
+        match lines.last_mut() {
+            Some((span, group)) if statement.span.0 < span.1 => {
+                span.1 = span.1.max(statement.span.1);
+                group.push(statement);
+            },
+            _ => lines.push((statement.span, vec![statement]))
+        }
+    }
+
+    let mut out = String::new();
+    let mut depth = 0;
+    // The next token and comment to be written
+    let (mut token, mut comment) = (0, 0);
+    // The end of the last token or comment written
+    let mut last_end = 0;
+    for (i, ((start, end), group)) in lines.iter().enumerate() {
+        let (start, end) = (*start, *end);
+        // Where the next statement starts, if any
+        let next = lines.get(i + 1).map_or(buffer.len(), |((next, _), _)| *next);
+        // Comments before the statement keep their own lines
+        while comment < comments.len() && comments[comment].0 < start {
+            let (comment_start, comment_end) = comments[comment];
+            new_line(&mut out, &buffer[last_end..comment_start], depth);
+            out.push_str(&src[comment_start..comment_end]);
+            last_end = comment_end;
+            comment += 1;
+        }
+        if group.iter().any(|statement| is_scope(statement, Property::Done)) {
+            depth = depth.saturating_sub(1);
+        }
+        new_line(&mut out, &buffer[last_end..start], depth);
+        let mut words = Vec::new();
+        while token < tokens.len() && spans[token].0 < end {
+            words.push(canonical(&tokens[token], &identifiers));
+            token += 1;
+        }
+        out.push_str(&words.join(" "));
+        last_end = end;
+        // Comments inside the statement, or after it on the same line
+        while comment < comments.len() {
+            let (comment_start, comment_end) = comments[comment];
+            let trailing = comment_start < end 
+                || (comment_start < next && !buffer[last_end..comment_start].contains(&b'\n'));
+            if !trailing {
+                break
+            }
+            out.push(' ');
+            out.push_str(&src[comment_start..comment_end]);
+            last_end = last_end.max(comment_end);
+            comment += 1;
+            // Nothing can follow a line comment
+            if src[comment_start..].starts_with("//") {
+                break
+            }
+        }
+        if group.iter().any(|statement| is_scope(statement, Property::Tele) || opens_level(statement)) {
+            depth += 1;
+        }
+    }
+    for &(comment_start, comment_end) in &comments[comment..] {
+        new_line(&mut out, &buffer[last_end..comment_start], depth);
+        out.push_str(&src[comment_start..comment_end]);
+        last_end = comment_end;
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Starts a new line at the given depth, unless nothing has been written yet.
+/// `gap` is the source between the previous item and the next one: a blank
+/// line in it is kept.
+fn new_line(out: &mut String, gap: &[u8], depth: usize) {
+    if !out.is_empty() {
+        out.push('\n');
+        if gap.iter().filter(|&&byte| byte == b'\n').count() > 1 {
+            out.push('\n');
+        }
+    }
+    out.push_str(&INDENT.repeat(depth));
+}
+
+/// Spells a token with its keywords in uppercase. Identifiers and
+/// string literals are spelled as they are.
+fn canonical(token: &Token, identifiers: &HashMap<usize, String>) -> String {
+    match token {
+        Token::Noun(Noun::Identifier(_)) | Token::Noun(Noun::Literal(_)) => spelling(token, identifiers),
+        _ => spelling(token, identifiers).to_uppercase()
+    }
+}
+
+/// Checks whether a statement is `X IS <property>`, e.g. `X IS TELE`.
+fn is_scope(statement: &Statement, property: Property) -> bool {
+    statement.action_type == Verb::Is
+        && !statement.action_sign
+        && statement.action_target == Some(Target::Property(property))
+}
+
+/// Checks whether a statement is `X IS LEVEL` or `X IS IMAGE`.
+fn opens_level(statement: &Statement) -> bool {
+    statement.action_type == Verb::Is
+        && !statement.action_sign
+        && matches!(statement.action_target, Some(Target::Noun(Noun::Level)) | Some(Target::Noun(Noun::Image)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::format_source;
+    use crate::error_handler::ErrorType;
+
+    #[test]
+    fn canonical_layout() {
+        let source = "// Header\nBaba is   YOU keke IS group loop is tele /* body */ baba\tis move and turn\n\n\n\
+            lonely baba   fear loop // exit\n  loop is done f is level f has x x is text f is done\n";
+        let formatted = format_source(source).unwrap();
+        assert_eq!(formatted, "\
+// Header
+baba IS YOU
+keke IS GROUP
+loop IS TELE /* body */
+    baba IS MOVE AND TURN
+
+    LONELY baba FEAR loop // exit
+loop IS DONE
+f IS LEVEL
+    f HAS x
+    x IS TEXT
+f IS DONE
+");
+    }
+
+    #[test]
+    fn fixed_point() {
+        let sources = [
+            "baba is you /* a */ keke /* b */ is you // c\n/* d\n e */ not lonely baba on keke and me is not move",
+            "img is image img has a img is level img has self self is text img is done img is done\n\n\n// end",
+            include_str!("../examples/fibonacci.baba"),
+        ];
+        for source in sources {
+            let once = format_source(source).unwrap();
+            assert_eq!(format_source(&once).unwrap(), once);
+        }
+        assert_eq!(format_source("").unwrap(), "");
+        assert_eq!(format_source("baba is").unwrap_err().kind, ErrorType::StatementParserError);
+    }
+}
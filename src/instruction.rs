@@ -1,11 +1,14 @@
-use crate::error_handler::{throw_error, throw_error_str, ErrorType};
-use crate::statement::{Statement, Target};
+use crate::error_handler::{BabaError, ErrorType, error, error_str};
+use crate::statement::{Statement, Target, Combinator};
 use crate::token::{Noun, Conditional, Prefix};
+use crate::lexer::decode_literal;
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Describes an instruction without conditions.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Simple {
     // init
     InitYou(usize, bool),
@@ -14,28 +17,37 @@ pub enum Simple {
     // any
     Win(usize),
     Defeat(usize),
+    AllWin,
+    AllDefeat,
     Sleep(usize),
     Text(usize),
     Word(usize),
+    Line(usize),
     IsValue(usize, usize, bool),
-    MimicReference(usize, usize),
+    MimicReference(usize, usize, bool),
     IsEmpty(usize),
+    IsLiteral(usize, Vec<u8>),
+    IsNumber(usize, u32, bool),
     // you
     IsSum(usize, Vec<Noun>, Vec<bool>),
     Move(usize, bool),
     Turn(usize, bool),
     Fall(usize, bool),
     More(usize, bool),
+    Less(usize, bool),
     Right(usize, bool),
     Up(usize, bool),
     Left(usize, bool),
     Down(usize, bool),
     Chill(usize, bool),
+    Both(usize, bool),
+    Reset(usize),
     // all (subset of you)
     AllMove(bool),
     AllTurn(bool),
     AllFall(bool),
     AllMore(bool),
+    AllLess(bool),
     AllRight(bool),
     AllUp(bool),
     AllLeft(bool),
@@ -47,6 +59,8 @@ pub enum Simple {
     Swap(usize),
     // group / level
     HasValue(usize, usize),
+    HasLiteral(usize, Vec<u8>),
+    HasNumber(usize, u32),
     MakeValue(usize, usize),
     // level
     Power(usize, bool),
@@ -55,27 +69,44 @@ pub enum Simple {
     // image
     FollowAttribute(usize, usize),
     EatValue(usize, usize),
+    // you
+    FeelType(usize, usize),
+    EqualValue(usize, usize),
+    WriteValue(usize, usize),
+    TrackIndex(usize, usize),
+    Play(usize, usize),
+    // group
+    SpellDigits(usize, usize),
+    FeedLevel(usize, usize),
+    PeekValue(usize, usize),
 }
 
 /// Describes an instruction with some conditions.
 /// Both `conditions` and `prefix` should typically not be None.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Complex {
-    pub conditions: Option<Conditions>,
+    // All of which must hold
+    pub conditions: Vec<Conditions>,
     pub prefix: Option<Prefixes>,
+    // Whether the prefix and the conditions must both hold, or either
+    pub combinator: Combinator,
     pub instruction: Simple
 }
 
 /// Descrives the targeted conditions for a complex instruction.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Conditions{
     pub cond_type: Conditional,
-    pub targets: Vec<Target>,
+    // Each target along with its sign, which applies on top of `sign`
+    pub targets: Vec<(Target, bool)>,
     pub sign: bool
 }
 
 /// Describes the non-targeted (unary) conditions for a complex instruction.
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Prefixes {
     pub prefix: Prefix,
     pub sign: bool,
@@ -83,20 +114,24 @@ pub struct Prefixes {
 
 /// Descibes a TELE instruction (i.e. a loop instruction).
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Tele {
     pub identifier: usize,
-    pub instructions: Vec<Instruction>,
+    pub instructions: Arc<[Instruction]>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Level {
     pub float: bool,
     pub identifier: usize,
     pub arguments: Vec<usize>,
-    pub instructions: Vec<Instruction>
+    // Shared with the LEVEL objects it defines
+    pub instructions: Arc<[Instruction]>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Image {
     pub float: bool,
     pub identifier: usize,
@@ -106,6 +141,7 @@ pub struct Image {
 
 /// Describes an instruction.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Instruction {
     NoOp,
     Simple(Simple),
@@ -119,245 +155,271 @@ pub enum Instruction {
     PartialFloat(usize) // Static variables
 }
 
-/// Validates an instruction. Throws an InstructionValidationError if the attempted
+/// Validates an instruction. Returns an InstructionValidationError if the attempted
 /// instruction can't be constructed from the statement.
-pub fn validate<'a>(
+pub fn validate(
     instruction_type: &str, 
-    statement: &'a Statement,
+    statement: &Statement,
     identifiers: &HashMap<usize, String>
-) -> Instruction {
+) -> Result<Instruction, BabaError> {
     let mut instr = Instruction::NoOp;
     match instruction_type {
-        "InitYou" => instr = generic_init(statement, "YOU", false, &Simple::InitYou),
-        "InitYou2" => instr = generic_init(statement, "YOU2", false, &Simple::InitYou2),
-        "InitGroup" => instr = generic_init(statement, "GROUP", false, &Simple::InitGroup),
-        "InitTele" => instr = generic_partial(statement, "TELE", &Instruction::PartialTele),
-        "InitLevel" => instr = generic_partial(statement, "LEVEL", &Instruction::PartialLevel),
-        "InitImage" => instr = generic_partial(statement, "IMAGE", &Instruction::PartialImage),
-        "InitFloat" => instr = generic_partial(statement, "FLOAT", &Instruction::PartialFloat),
-        "FloatYou" => instr = generic_init(statement, "YOU", true, &Simple::InitYou),
-        "FloatYou2" => instr = generic_init(statement, "YOU2", true, &Simple::InitYou2),
-        "FloatGroup" => instr = generic_init(statement, "GROUP", true, &Simple::InitGroup),
-        "IsText" => instr = generic_any(statement, "TEXT", &Simple::Text),
-        "IsWord" => instr = generic_any(statement, "WORD", &Simple::Word),
-        "IsWin" => instr = generic_any(statement, "WIN", &Simple::Win),
-        "IsDefeat" => instr = generic_any(statement, "DEFEAT", &Simple::Defeat),
-        "IsSleep" => instr = generic_any(statement, "SLEEP", &Simple::Sleep),
-        "IsEmpty" => instr = generic_any(statement, "EMPTY", &Simple::IsEmpty),
+        "InitYou" => instr = generic_init(statement, "YOU", false, &Simple::InitYou)?,
+        "InitYou2" => instr = generic_init(statement, "YOU2", false, &Simple::InitYou2)?,
+        "InitGroup" => instr = generic_init(statement, "GROUP", false, &Simple::InitGroup)?,
+        "InitTele" => instr = generic_partial(statement, "TELE", &Instruction::PartialTele)?,
+        "InitLevel" => instr = generic_partial(statement, "LEVEL", &Instruction::PartialLevel)?,
+        "InitImage" => instr = generic_partial(statement, "IMAGE", &Instruction::PartialImage)?,
+        "InitFloat" => instr = generic_partial(statement, "FLOAT", &Instruction::PartialFloat)?,
+        "FloatYou" => instr = generic_init(statement, "YOU", true, &Simple::InitYou)?,
+        "FloatYou2" => instr = generic_init(statement, "YOU2", true, &Simple::InitYou2)?,
+        "FloatGroup" => instr = generic_init(statement, "GROUP", true, &Simple::InitGroup)?,
+        "IsText" => instr = generic_any(statement, "TEXT", &Simple::Text)?,
+        "IsWord" => instr = generic_any(statement, "WORD", &Simple::Word)?,
+        "IsLine" => instr = generic_any(statement, "LINE", &Simple::Line)?,
+        "IsWin" => instr = generic_any_or_all(statement, "WIN", &Simple::Win, Simple::AllWin)?,
+        "IsDefeat" => instr = generic_any_or_all(statement, "DEFEAT", &Simple::Defeat, Simple::AllDefeat)?,
+        "IsSleep" => instr = generic_any(statement, "SLEEP", &Simple::Sleep)?,
+        "IsEmpty" => instr = generic_any(statement, "EMPTY", &Simple::IsEmpty)?,
+        "IsLiteral" => {
+            if let Some(Target::Noun(Noun::Literal(literal))) = statement.action_target {
+                let bytes = literal_bytes(literal, identifiers);
+                instr = generic_any(statement, "a string literal", &|id| Simple::IsLiteral(id, bytes.clone()))?;
+            }
+        },
+        "IsNumber" => {
+            if let Some(Target::Noun(Noun::Number(number))) = statement.action_target {
+                if let Noun::Identifier(id) = statement.subject {
+                    // NOT inverts the number, as with IS NOT of another YOU
+                    let simple = Simple::IsNumber(id, number, statement.action_sign);
+                    instr = merge(simple, conditions(statement))?;
+                }
+                else {
+                    return Err(error(
+                        ErrorType::InstructionValidationError, 
+                        format!("Cannot make {:?} IS {}", statement.subject, number),
+                        None
+                    ));
+                }
+            }
+        },
         "IsValue" => {
             let conds = conditions(statement);
             if let Noun::Identifier(id) = statement.subject {
                 if let Some(Target::Noun(Noun::Identifier(source))) = statement.action_target {
                     let simple = Simple::IsValue(id, source, statement.action_sign); 
-                    instr = merge(simple, conds);
+                    instr = merge(simple, conds)?;
                 }
             }
             else {
                 if let Some(noun) = statement.action_target {
                     if let Target::Noun(Noun::Identifier(other_id)) = noun {
-                        throw_error(
+                        return Err(error(
                             ErrorType::InstructionValidationError, 
                             format!("Cannot make {:?} IS Identifier({})", statement.subject, other_id),
                             Some((&[other_id], identifiers))
-                        );
+                        ));
                     }
                     else {
-                        throw_error(
+                        return Err(error(
                             ErrorType::InstructionValidationError, 
                             format!("Cannot make {:?} IS {:?}", statement.subject, noun),
                             None
-                        );
+                        ));
                     }
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::InstructionValidationError, 
                         format!("Cannot make {:?} IS any noun", statement.subject),
                         None
-                    );
+                    ));
                 }
             }
         }
-        "HasValue" => instr = generic_verb(statement, "HAS", &Simple::HasValue),
-        "MakeValue" => instr = generic_verb(statement, "MAKE", &Simple::MakeValue),
-        "FollowAttribute" => instr = generic_verb(statement, "FOLLOW", &Simple::FollowAttribute),
-        "EatValue" => instr = generic_verb(statement, "EAT", &Simple::EatValue),
+        "HasValue" => {
+            // Each byte of a literal is pushed as a YOU
+            if let Some(Target::Noun(Noun::Literal(literal))) = statement.action_target {
+                let bytes = literal_bytes(literal, identifiers);
+                instr = generic_any(statement, "HAS a string literal", &|id| Simple::HasLiteral(id, bytes.clone()))?;
+            }
+            else if let Some(Target::Noun(Noun::Number(number))) = statement.action_target {
+                instr = generic_any(statement, "HAS a number", &|id| Simple::HasNumber(id, number))?;
+            }
+            else {
+                instr = generic_verb(statement, "HAS", &Simple::HasValue)?;
+            }
+        },
+        "MakeValue" => instr = generic_verb(statement, "MAKE", &Simple::MakeValue)?,
+        "FollowAttribute" => instr = generic_verb_identifier(statement, "FOLLOW", &Simple::FollowAttribute)?,
+        "EatValue" => instr = generic_verb(statement, "EAT", &Simple::EatValue)?,
+        "FeelType" => instr = generic_verb(statement, "FEEL", &Simple::FeelType)?,
+        "EqualValue" => instr = generic_verb(statement, "EQUAL", &Simple::EqualValue)?,
+        "WriteValue" => instr = generic_verb(statement, "WRITE", &Simple::WriteValue)?,
+        "SpellDigits" => instr = generic_verb(statement, "SPELL", &Simple::SpellDigits)?,
+        "TrackIndex" => instr = generic_verb(statement, "TRACK", &Simple::TrackIndex)?,
+        "Play" => instr = generic_verb(statement, "PLAY", &Simple::Play)?,
+        "FeedLevel" => instr = generic_verb_identifier(statement, "FEED", &Simple::FeedLevel)?,
+        "PeekValue" => instr = generic_verb(statement, "PEEK", &Simple::PeekValue)?,
         "MimicReference" => {
             let conds = conditions(statement);
             if let Noun::Identifier(id) = statement.subject {
                 if let Some(Target::Noun(Noun::Identifier(source))) = statement.action_target {
-                    let simple = Simple::MimicReference(id, source); 
-                    instr = merge(simple, conds);
+                    let simple = Simple::MimicReference(id, source, statement.action_sign); 
+                    instr = merge(simple, conds)?;
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::InstructionValidationError, 
                         format!("Cannot make {} MIMIC {:?}", id, statement.action_target),
                         Some((&[id], identifiers))
-                    );
+                    ));
                 }
             }
             else {
                 if let Some(noun) = statement.action_target {
                     if let Target::Noun(Noun::Identifier(other_id)) = noun {
-                        throw_error(
+                        return Err(error(
                             ErrorType::InstructionValidationError, 
                             format!("Cannot make {:?} MIMIC Identifier({})", statement.subject, other_id),
                             Some((&[other_id], identifiers))
-                        );
+                        ));
                     }
                     else {
-                        throw_error(
+                        return Err(error(
                             ErrorType::InstructionValidationError, 
                             format!("Cannot make {:?} MIMIC {:?}", statement.subject, noun),
                             None
-                        );
+                        ));
                     }
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::InstructionValidationError, 
                         format!("Cannot make {:?} MIMIC any noun", statement.subject),
                         None
-                    );
+                    ));
                 }
             }
         },
-        "FearTele" => instr = generic_verb(statement, "FEAR", &Simple::FearTele),
-        "YouMove" => instr = generic_you(statement, "MOVE", &Simple::Move, &Simple::AllMove),
-        "YouTurn" => instr = generic_you(statement, "TURN", &Simple::Turn, &Simple::AllTurn),
-        "YouFall" => instr = generic_you(statement, "FALL", &Simple::Fall, &Simple::AllFall),
-        "YouMore" => instr = generic_you(statement, "MORE", &Simple::More, &Simple::AllMore),
-        "YouRight" => instr = generic_you(statement, "RIGHT", &Simple::Right, &Simple::AllRight),
-        "YouUp" => instr = generic_you(statement, "UP", &Simple::Up, &Simple::AllUp),
-        "YouLeft" => instr = generic_you(statement, "LEFT", &Simple::Left, &Simple::AllLeft),
-        "YouDown" => instr = generic_you(statement, "DOWN", &Simple::Down, &Simple::AllDown),
-        "YouChill" => instr = generic_you(statement, "CHILL", &Simple::Chill, &Simple::AllChill),
+        "FearTele" => instr = generic_verb_identifier(statement, "FEAR", &Simple::FearTele)?,
+        "YouMove" => instr = generic_you(statement, "MOVE", &Simple::Move, &Simple::AllMove)?,
+        "YouTurn" => instr = generic_you(statement, "TURN", &Simple::Turn, &Simple::AllTurn)?,
+        "YouFall" => instr = generic_you(statement, "FALL", &Simple::Fall, &Simple::AllFall)?,
+        "YouMore" => instr = generic_you(statement, "MORE", &Simple::More, &Simple::AllMore)?,
+        "YouLess" => instr = generic_you(statement, "LESS", &Simple::Less, &Simple::AllLess)?,
+        "YouRight" => instr = generic_you(statement, "RIGHT", &Simple::Right, &Simple::AllRight)?,
+        "YouUp" => instr = generic_you(statement, "UP", &Simple::Up, &Simple::AllUp)?,
+        "YouLeft" => instr = generic_you(statement, "LEFT", &Simple::Left, &Simple::AllLeft)?,
+        "YouDown" => instr = generic_you(statement, "DOWN", &Simple::Down, &Simple::AllDown)?,
+        "YouChill" => instr = generic_you(statement, "CHILL", &Simple::Chill, &Simple::AllChill)?,
+        "YouBoth" => instr = generic_not(statement, "BOTH", &Simple::Both)?,
+        "YouReset" => instr = generic_any(statement, "RESET", &Simple::Reset)?,
         "YouSum" => {
             let conds = conditions(statement);
             instr = if let Noun::Identifier(id) = statement.subject {
                 if let (Some(targets), Some(signs)) = (statement.action_targets.clone(), statement.action_signs.clone()) {
                     let simple = Simple::IsSum(id, targets, signs); 
-                    merge(simple, conds)
+                    merge(simple, conds)?
                 }
                 else {
                     Instruction::NoOp
                 }
             }
             else {
-                throw_error(
+                return Err(error(
                     ErrorType::InstructionValidationError, 
                     format!("Cannot set {:?} to sum of objects", statement.subject),
                     None
-                );
-                Instruction::NoOp
+                ));
             }
         }
-        "GroupShift" => instr = generic_not(statement, "SHIFT", &Simple::Shift),
-        "GroupSink" => instr = generic_any(statement, "SINK", &Simple::Sink),
-        "GroupSwap" => instr = generic_any(statement, "SWAP", &Simple::Swap),
+        "GroupShift" => instr = generic_not(statement, "SHIFT", &Simple::Shift)?,
+        "GroupSink" => instr = generic_any(statement, "SINK", &Simple::Sink)?,
+        "GroupSwap" => instr = generic_any(statement, "SWAP", &Simple::Swap)?,
         // Power is generic_init, 
-        "LevelPower" => instr = generic_init(statement, "POWER", false, &Simple::Power),
-        "FloatPower" => instr = generic_init(statement, "POWER", true,  &Simple::Power),
+        "LevelPower" => instr = generic_init(statement, "POWER", false, &Simple::Power)?,
+        "FloatPower" => instr = generic_init(statement, "POWER", true,  &Simple::Power)?,
         _ => {
-            throw_error_str(
+            return Err(error_str(
                 ErrorType::InstructionValidationError, 
                 &format!("Attempted to parse invalid instruction {}", instruction_type)
-            );
+            ));
         }
     }
-    instr
+    Ok(instr)
 }
 
 /// Retrieves the conditions associated with a statement.
 /// 
-/// Returns a 2-tuple containing the Complex condition and the Prefix condition.
-/// These will be None if the associated condition does not exist.
+/// Returns a 3-tuple containing the Complex conditions, the Prefix condition and
+/// how they are combined. These will be empty or None if they do not exist.
 /// 
 /// # Examples
 /// 
-/// * `BABA IS YOU` -> (None, None)
+/// * `BABA IS YOU` -> ([], None, And)
 /// 
-/// * `LONELY BABA IS YOU` -> (None, Some(<Lonely>))
+/// * `LONELY BABA IS YOU` -> ([], Some(<Lonely>), And)
 /// 
-/// * `BABA ON KEKE IS YOU` -> (Some(<On Keke>), None)
+/// * `BABA ON KEKE IS YOU` -> ([<On Keke>], None, And)
 /// 
-/// * `LONELY BABA NEAR KEKE IS YOU` -> (Some(<Near Keke>), Some(<Lonely>))
+/// * `LONELY BABA NEAR KEKE IS YOU` -> ([<Near Keke>], Some(<Lonely>), And)
 /// 
-pub fn conditions(statement: &Statement) -> (Option<Conditions>, Option<Prefixes>) {
-    if let Some(cond) = statement.cond_type {
-        if let Some(pref) = statement.prefix {
-            // Prefix and condition
-            (
-                Some(Conditions {
-                    cond_type: cond,
-                    sign: statement.cond_sign.unwrap(),
-                    targets: statement.cond_targets.to_owned()
-                }),
-                Some(Prefixes {
-                    prefix: pref,
-                    sign: statement.prefix_sign.unwrap()
-                })
-            )
-        }
-        else {
-            // Only condition
-            (
-                Some(Conditions {
-                    cond_type: cond,
-                    sign: statement.cond_sign.unwrap(),
-                    targets: statement.cond_targets.to_owned()
-                }),
-                None
-            )
-        }
-    }
-    else if let Some(pref) = statement.prefix {
-        // Only prefix
-        (
-            None,
-            Some(Prefixes {
-                prefix: pref,
-                sign: statement.prefix_sign.unwrap()
-            })
-        )
-    }
-    else {
-        // No conditions
-        (None, None)
-    }
+/// * `BABA ON KEKE AND FACING ME IS YOU` -> ([<On Keke>, <Facing Me>], None, And)
+/// 
+pub fn conditions(statement: &Statement) -> (Vec<Conditions>, Option<Prefixes>, Combinator) {
+    let conds = statement.conditions.iter()
+        .map(|(cond_type, sign, targets)| Conditions {
+            cond_type: *cond_type,
+            sign: *sign,
+            targets: targets.to_owned()
+        })
+        .collect::<Vec<Conditions>>();
+    let prefix = statement.prefix.map(|pref| Prefixes {
+        prefix: pref,
+        sign: statement.prefix_sign.unwrap()
+    });
+    // The combinator only matters with both a prefix and conditions
+    let combinator = if prefix.is_some() && !conds.is_empty() { statement.combinator } else { Combinator::And };
+    (conds, prefix, combinator)
 }
 
 /// Merges a simple instruction with conditions into a Complex instruction.
-fn merge<'a>(
+fn merge(
     simple: Simple,
-    conds: (Option<Conditions>, Option<Prefixes>),
-) -> Instruction {
-    let (cond, prefix) = conds;
-    match (cond, prefix) {
-        (None, None) => Instruction::Simple(simple),
-        (Some(c), p) => Instruction::Complex(Complex {
-            conditions: Some(c),
-            prefix: p,
-            instruction: simple
-        }),
-        (None, p) => Instruction::Complex(Complex {
-            conditions: None,
-            prefix: p,
+    conds: (Vec<Conditions>, Option<Prefixes>, Combinator),
+) -> Result<Instruction, BabaError> {
+    let (conditions, prefix, combinator) = conds;
+    // Conditions and prefixes are evaluated relative to a single object
+    if (!conditions.is_empty() || prefix.is_some()) && subject(&simple).is_none() {
+        return Err(error(
+            ErrorType::InstructionValidationError, 
+            format!("Cannot apply a condition or prefix to ALL (in {:?})", simple),
+            None
+        ));
+    }
+    if conditions.is_empty() && prefix.is_none() {
+        Ok(Instruction::Simple(simple))
+    }
+    else {
+        Ok(Instruction::Complex(Complex {
+            conditions,
+            prefix,
+            combinator,
             instruction: simple
-        }),
+        }))
     }
 }
 
 /// Returns a reversible YOU instruction with default parameters.
 /// 
 /// Allows for the use of ALL, as well as NOT to reverse instructions.
-fn generic_you<'a>(
-    statement: &'a Statement,
+fn generic_you(
+    statement: &Statement,
     target: &str,
     simple_factory: &dyn Fn(usize, bool) -> Simple,
     all_factory: &dyn Fn(bool) -> Simple
-) -> Instruction {
+) -> Result<Instruction, BabaError> {
     let conds = conditions(statement);
     if let Noun::Identifier(id) = statement.subject {
         let simple = simple_factory(id, statement.action_sign); 
@@ -368,116 +430,109 @@ fn generic_you<'a>(
         merge(simple, conds)
     }
     else {
-        throw_error(
+        Err(error(
             ErrorType::InstructionValidationError, 
             format!("Cannot apply {} to {:?}", target, statement.subject),
             None
-        );
-        Instruction::NoOp
+        ))
     }
 }
 
 /// Returns a reversible GROUP instruction with default parameters.
 /// 
 /// Allows for the use NOT to reverse instructions.
-fn generic_not<'a>(
-    statement: &'a Statement,
+fn generic_not(
+    statement: &Statement,
     target: &str,
     simple_factory: &dyn Fn(usize, bool) -> Simple,
-) -> Instruction {
+) -> Result<Instruction, BabaError> {
     let conds = conditions(statement);
     if let Noun::Identifier(id) = statement.subject {
         let simple = simple_factory(id, statement.action_sign); 
         merge(simple, conds)
     }
     else {
-        throw_error(
+        Err(error(
             ErrorType::InstructionValidationError, 
             format!("Cannot apply {} to {:?}", target, statement.subject),
             None
-        );
-        Instruction::NoOp
+        ))
     }
 }
 
 /// Returns an INIT instruction with default parameters.
 /// 
 /// Does not allow for conditionals. NOT returns a no-op.
-fn generic_init<'a>(
-    statement: &'a Statement,
+fn generic_init(
+    statement: &Statement,
     target: &str,
     float: bool,
     simple_factory: &dyn Fn(usize, bool) -> Simple,
-) -> Instruction {
+) -> Result<Instruction, BabaError> {
     let conds = conditions(statement);
     if let Noun::Identifier(id) = statement.subject {
         if target == "POWER" { // Hacky way to allow for FLOATing POWER
-            Instruction::Simple(simple_factory(id, float))
+            Ok(Instruction::Simple(simple_factory(id, float)))
         }
-        else if let (None, None) = conds {
+        else if conds.0.is_empty() && conds.1.is_none() {
             if !statement.action_sign {
-                Instruction::Simple(simple_factory(id, float))
+                Ok(Instruction::Simple(simple_factory(id, float)))
             }
             else {
                 // NOT [type] is a no-op
-                Instruction::NoOp
+                Ok(Instruction::NoOp)
             }
         }
         else {
-            throw_error(
+            Err(error(
                 ErrorType::InstructionValidationError, 
                 format!("IS {} cannot be defined with conditions", target),
                 None
-            );
-            Instruction::NoOp
-
+            ))
         }
     }
     else {
-        throw_error(
+        Err(error(
             ErrorType::InstructionValidationError, 
             format!("Cannot initialize {:?} as {}", statement.subject, target),
             None
-        );
-        Instruction::NoOp
+        ))
     }
 }
 
 /// Returns an INIT instruction with default parameters.
 /// 
 /// Does not allow for conditionals. NOT returns a no-op.
-fn generic_partial<'a>(
-    statement: &'a Statement,
+fn generic_partial(
+    statement: &Statement,
     target: &str,
     partial_factory: &dyn Fn(usize) -> Instruction,
-) -> Instruction {
+) -> Result<Instruction, BabaError> {
     let conds = conditions(statement);
     if let Noun::Identifier(id) = statement.subject {
-        if let (None, None) = conds {
+        if conds.0.is_empty() && conds.1.is_none() {
             if !statement.action_sign {
-                partial_factory(id)
+                Ok(partial_factory(id))
             }
             else {
                 // NOT YOU/GROUP/TELE is a no-op
-                Instruction::NoOp
+                Ok(Instruction::NoOp)
             }
         }
         else {
-            throw_error(
+            Err(error(
                 ErrorType::InstructionValidationError, 
                 format!("IS {} cannot be called with conditions", target),
                 None
-            );
-            Instruction::NoOp
+            ))
         }
     }
     else {
-        throw_error(
+        Err(error(
             ErrorType::InstructionValidationError, 
             format!("Cannot initialize {:?} as {}", statement.subject, target),
             None
-        );
-        Instruction::NoOp
+        ))
     }
 }
 
@@ -485,39 +540,182 @@ fn generic_partial<'a>(
 /// Returns a nonreversible YOU/GROUP instruction with default parameters.
 /// 
 /// Negation via NOT returns a no-op.
-fn generic_any<'a>(
-    statement: &'a Statement,
+fn generic_any(
+    statement: &Statement,
     target: &str,
     simple_factory: &dyn Fn(usize) -> Simple,
-) -> Instruction {
+) -> Result<Instruction, BabaError> {
     let conds = conditions(statement);
     if let Noun::Identifier(id) = statement.subject {
-        if let false = statement.action_sign {
+        if !statement.action_sign {
             let simple = simple_factory(id); 
             merge(simple, conds)
         }
         else {
-            Instruction::NoOp
+            Ok(Instruction::NoOp)
         }
     }
     else {
-        throw_error(
+        Err(error(
             ErrorType::InstructionValidationError, 
             format!("Cannot apply {} to {:?}", target, statement.subject),
             None
-        );
-        Instruction::NoOp
+        ))
     }
 }
 
+/// Returns a nonreversible instruction that may also be applied to ALL.
+/// 
+/// Negation via NOT returns a no-op.
+fn generic_any_or_all(
+    statement: &Statement,
+    target: &str,
+    simple_factory: &dyn Fn(usize) -> Simple,
+    all_simple: Simple,
+) -> Result<Instruction, BabaError> {
+    if let Noun::All = statement.subject {
+        if !statement.action_sign {
+            merge(all_simple, conditions(statement))
+        }
+        else {
+            Ok(Instruction::NoOp)
+        }
+    }
+    else {
+        generic_any(statement, target, simple_factory)
+    }
+}
+
+/// Returns a NOUN VERB NOUN instruction whose target must be an identifier
+/// (i.e. not one of the built-in EMPTY, LEVEL or IMAGE objects).
+fn generic_verb_identifier(
+    statement: &Statement,
+    target: &str,
+    simple_factory: &dyn Fn(usize, usize) -> Simple,
+) -> Result<Instruction, BabaError> {
+    match statement.action_target {
+        Some(Target::Noun(Noun::Identifier(_))) => generic_verb(statement, target, simple_factory),
+        Some(Target::Noun(noun)) => {
+            Err(error(
+                ErrorType::InstructionValidationError, 
+                format!("Cannot {} {:?}, since it is built-in", target, noun),
+                None
+            ))
+        },
+        _ => Ok(Instruction::NoOp)
+    }
+}
+
+/// Returns the single object an instruction applies to, i.e. its subject.
+/// Returns None for instructions applying to ALL.
+pub fn subject(simple: &Simple) -> Option<usize> {
+    match simple {
+        Simple::AllWin | Simple::AllDefeat
+        | Simple::AllMove(_) | Simple::AllTurn(_) | Simple::AllFall(_)
+        | Simple::AllMore(_) | Simple::AllLess(_) | Simple::AllRight(_)
+        | Simple::AllUp(_) | Simple::AllLeft(_) | Simple::AllDown(_)
+        | Simple::AllChill(_) => None,
+        Simple::InitYou(id, _) | Simple::InitYou2(id, _) | Simple::InitGroup(id, _)
+        | Simple::Win(id) | Simple::Defeat(id) | Simple::Sleep(id)
+        | Simple::Text(id) | Simple::Word(id) | Simple::Line(id) | Simple::IsEmpty(id)
+        | Simple::IsValue(id, _, _) | Simple::MimicReference(id, _, _)
+        | Simple::IsLiteral(id, _) | Simple::IsNumber(id, _, _) | Simple::IsSum(id, _, _)
+        | Simple::Move(id, _) | Simple::Turn(id, _) | Simple::Fall(id, _)
+        | Simple::More(id, _) | Simple::Less(id, _) | Simple::Right(id, _)
+        | Simple::Up(id, _) | Simple::Left(id, _) | Simple::Down(id, _)
+        | Simple::Chill(id, _) | Simple::Both(id, _) | Simple::Reset(id) | Simple::Shift(id, _) | Simple::Sink(id)
+        | Simple::Swap(id) | Simple::HasValue(id, _) | Simple::HasLiteral(id, _) | Simple::HasNumber(id, _) | Simple::MakeValue(id, _)
+        | Simple::Power(id, _) | Simple::FearTele(id, _) | Simple::FollowAttribute(id, _)
+        | Simple::EatValue(id, _) | Simple::FeelType(id, _) | Simple::EqualValue(id, _)
+        | Simple::WriteValue(id, _) | Simple::TrackIndex(id, _) | Simple::Play(id, _)
+        | Simple::SpellDigits(id, _) | Simple::FeedLevel(id, _) | Simple::PeekValue(id, _) => Some(*id),
+    }
+}
+
+/// Returns the keyword(s) an instruction was written with, e.g. `MOVE` for
+/// both `BABA IS MOVE` and `ALL IS MOVE`.
+pub fn kind(instruction: &Instruction) -> &'static str {
+    let simple = match instruction {
+        Instruction::Simple(simple) => simple,
+        Instruction::Complex(complex) => &complex.instruction,
+        Instruction::Tele(_) | Instruction::PartialTele(_) => return "TELE",
+        Instruction::Level(_) | Instruction::PartialLevel(_) => return "LEVEL",
+        Instruction::Image(_) | Instruction::PartialImage(_) => return "IMAGE",
+        Instruction::PartialFloat(_) => return "FLOAT",
+        Instruction::NoOp => return "",
+    };
+    simple_kind(simple)
+}
+
+/// Returns the keyword(s) a simple instruction was written with. See `kind`.
+pub fn simple_kind(simple: &Simple) -> &'static str {
+    match simple {
+        Simple::InitYou(_, _) => "IS YOU",
+        Simple::InitYou2(_, _) => "IS YOU2",
+        Simple::InitGroup(_, _) => "IS GROUP",
+        Simple::Win(_) | Simple::AllWin => "WIN",
+        Simple::Defeat(_) | Simple::AllDefeat => "DEFEAT",
+        Simple::Sleep(_) => "SLEEP",
+        Simple::Text(_) => "TEXT",
+        Simple::Word(_) => "WORD",
+        Simple::Line(_) => "LINE",
+        Simple::IsValue(_, _, _) | Simple::IsLiteral(_, _) | Simple::IsNumber(_, _, _)
+        | Simple::IsSum(_, _, _) => "IS",
+        Simple::MimicReference(_, _, _) => "MIMIC",
+        Simple::IsEmpty(_) => "IS EMPTY",
+        Simple::Move(_, _) | Simple::AllMove(_) => "MOVE",
+        Simple::Turn(_, _) | Simple::AllTurn(_) => "TURN",
+        Simple::Fall(_, _) | Simple::AllFall(_) => "FALL",
+        Simple::More(_, _) | Simple::AllMore(_) => "MORE",
+        Simple::Less(_, _) | Simple::AllLess(_) => "LESS",
+        Simple::Right(_, _) | Simple::AllRight(_) => "RIGHT",
+        Simple::Up(_, _) | Simple::AllUp(_) => "UP",
+        Simple::Left(_, _) | Simple::AllLeft(_) => "LEFT",
+        Simple::Down(_, _) | Simple::AllDown(_) => "DOWN",
+        Simple::Chill(_, _) | Simple::AllChill(_) => "CHILL",
+        Simple::Both(_, _) => "BOTH",
+        Simple::Reset(_) => "RESET",
+        Simple::Shift(_, _) => "SHIFT",
+        Simple::Sink(_) => "SINK",
+        Simple::Swap(_) => "SWAP",
+        Simple::HasValue(_, _) | Simple::HasLiteral(_, _) | Simple::HasNumber(_, _) => "HAS",
+        Simple::MakeValue(_, _) => "MAKE",
+        Simple::Power(_, _) => "POWER",
+        Simple::FearTele(_, _) => "FEAR",
+        Simple::FollowAttribute(_, _) => "FOLLOW",
+        Simple::EatValue(_, _) => "EAT",
+        Simple::FeelType(_, _) => "FEEL",
+        Simple::EqualValue(_, _) => "EQUAL",
+        Simple::WriteValue(_, _) => "WRITE",
+        Simple::TrackIndex(_, _) => "TRACK",
+        Simple::Play(_, _) => "PLAY",
+        Simple::SpellDigits(_, _) => "SPELL",
+        Simple::FeedLevel(_, _) => "FEED",
+        Simple::PeekValue(_, _) => "PEEK",
+    }
+}
+
+/// Returns the bytes of a string literal, given its identifier.
+fn literal_bytes(literal: usize, identifiers: &HashMap<usize, String>) -> Vec<u8> {
+    // Literals are validated by the lexer, so only the quotes need stripping
+    let raw = identifiers[&literal].as_bytes();
+    decode_literal(&raw[1..raw.len() - 1]).unwrap_or_default()
+}
+
+/// Checks whether an identifier is reserved for a built-in object
+/// (EMPTY, LEVEL or IMAGE).
+pub fn is_reserved(id: usize) -> bool {
+    id <= 2
+}
+
 /// Returns a generic NOUN VERB NOUN instruction.
 /// 
 /// Negation via NOT returns a no-op.
-fn generic_verb<'a>(
-    statement: &'a Statement,
+fn generic_verb(
+    statement: &Statement,
     target: &str,
     simple_factory: &dyn Fn(usize, usize) -> Simple,
-) -> Instruction {
+) -> Result<Instruction, BabaError> {
     let conds = conditions(statement);
     if let Noun::Identifier(id) = statement.subject {
         if let Some(Target::Noun(Noun::Identifier(source))) = statement.action_target {
@@ -533,15 +731,79 @@ fn generic_verb<'a>(
             merge(simple, conds)
         }
         else {
-            Instruction::NoOp
+            Ok(Instruction::NoOp)
         }
     }
     else {
-        throw_error(
+        Err(error(
             ErrorType::InstructionValidationError, 
             format!("Cannot make {:?} {} any noun", statement.subject, target),
             None
-        );
-        Instruction::NoOp
+        ))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instruction::{Instruction, Simple, subject, kind};
+    use crate::compile;
+
+    #[test]
+    fn subject_all() {
+        // These forms are rejected when given a condition or prefix
+        for instruction in compile(b"all is move all is not fall all is win all is chill").unwrap().0 {
+            if let Instruction::Simple(simple) = instruction {
+                assert_eq!(subject(&simple), None, "{:?} has a subject", simple);
+            }
+            else {
+                panic!("{:?} is not a simple instruction", instruction);
+            }
+        }
+    }
+
+    #[test]
+    fn subject_single() {
+        let instructions = compile(b"
+            baba is you lonely baba is move
+            keke is group baba near keke is text
+            not lonely keke is sleep
+        ").unwrap().0;
+        for instruction in &instructions {
+            match instruction {
+                Instruction::Simple(simple) => assert!(subject(simple).is_some()),
+                Instruction::Complex(complex) => assert!(subject(&complex.instruction).is_some()),
+                _ => panic!("{:?} is not a simple or complex instruction", instruction)
+            }
+        }
+        assert_eq!(instructions.len(), 5);
+        assert_eq!(subject(&Simple::Sleep(5)), Some(5));
+    }
+
+    #[test]
+    fn play_verb() {
+        let instructions = compile(b"baba is you baba play keke baba play empty").unwrap().0;
+        assert!(matches!(instructions[1], Instruction::Simple(Simple::Play(_, _))));
+        assert!(matches!(instructions[2], Instruction::Simple(Simple::Play(_, 0))));
+    }
+
+    #[test]
+    fn instruction_kinds() {
+        let instructions = compile(b"baba is you lonely baba is move all is move loop is tele loop is done").unwrap().0;
+        let kinds = instructions.iter().map(kind).collect::<Vec<&str>>();
+        assert_eq!(kinds, vec!["IS YOU", "MOVE", "MOVE", "TELE"]);
+    }
+
+    #[test]
+    fn has_literal() {
+        let instructions = compile(b"baba is group baba has \"\\x05ab\" baba has empty baba has not \"a\"").unwrap().0;
+        assert_eq!(instructions[1], Instruction::Simple(Simple::HasLiteral(3, vec![5, b'a', b'b'])));
+        assert!(matches!(instructions[2], Instruction::Simple(Simple::HasValue(3, 0))));
+        assert_eq!(instructions.len(), 3);
+    }
+
+    #[test]
+    fn feed_verb() {
+        let instructions = compile(b"baba is group keke is level keke is done baba feed keke").unwrap().0;
+        assert!(matches!(instructions[2], Instruction::Simple(Simple::FeedLevel(_, _))));
+    }
+}
@@ -1,2221 +1,4847 @@
 use crate::token::{Noun, Conditional, Prefix, Property};
-use crate::instruction::{Instruction, Simple};
-use crate::statement::Target;
-use crate::error_handler::{ErrorType, throw_error, throw_error_str};
+use crate::instruction::{self, Instruction, Simple, Complex, is_reserved, subject, kind, simple_kind};
+use crate::statement::{Target, Combinator};
+use crate::error_handler::{BabaError, ErrorType, error, error_str, warn, log, set_context, clear_context, enter_scope, exit_scope};
+use crate::ast::dump_simple;
 use crate::object::{
     Object, Type, Level, Image, You, You2, Group, Empty, Reference, ImageInstance,
-    EMPTY, LEVEL
+    EMPTY, builtin_level, is_truthy, is_ordered, is_facing, is_on, holds_value, active_value
 };
 
 use std::collections::HashMap;
-use std::io::{stdin, stdout, Read, Write};
-use std::process::exit;
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::ops::{Deref, Index};
+use std::iter::FromIterator;
+use std::mem::{discriminant, replace};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 use std::time::Duration;
-use std::thread::sleep;
 
 use rand::random;
 
-/// Executes a Babalang AST in the global scope.
-pub fn exec<'a>(ast: &'a [Instruction], identifiers: &HashMap<usize, String>) {
-    let mut locals: HashMap<usize, Object> = HashMap::new();
-    let mut globals: HashMap<usize, Object> = HashMap::new();
-    globals.insert(0, EMPTY);
-    globals.insert(1, LEVEL);
-    // Scopes 0, 1 and 2 are reserved
-    // 0 is used to refer to the program scope
-    // 1 signifies that a function scope has been exited
-    // 2 signifies that a scope should not be exited
-    exec_with(ast, &mut locals, &mut globals, PRG_SCOPE, identifiers);
+/// The objects left over once a program has finished executing.
+/// 
+/// `locals` holds the objects of the program scope, while `globals` holds
+/// the floating objects (including the built-in EMPTY and LEVEL objects).
+/// 
+/// `exit_code` holds the exit code requested by WIN (0) or DEFEAT (1), 
+/// if the program was stopped by one.
+#[derive(Debug, Clone)]
+pub struct Scope {
+    pub locals: Objects,
+    pub globals: Objects,
+    pub exit_code: Option<i32>
+}
+
+/// The objects of a scope, by identifier.
+/// 
+/// Identifiers are allocated sequentially, so the objects are stored in a 
+/// `Vec` indexed by identifier rather than hashed. Iteration is in order 
+/// of identifier.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Objects {
+    slots: Vec<Option<Object>>
+}
+
+impl Objects {
+    /// Creates an empty scope.
+    pub fn new() -> Self {
+        Objects { slots: Vec::new() }
+    }
+
+    /// Creates an empty scope with room for identifiers up to `capacity`.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Objects { slots: vec![None; capacity] }
+    }
+
+    pub fn get(&self, id: &usize) -> Option<&Object> {
+        self.slots.get(*id).and_then(Option::as_ref)
+    }
+
+    pub fn get_mut(&mut self, id: &usize) -> Option<&mut Object> {
+        self.slots.get_mut(*id).and_then(Option::as_mut)
+    }
+
+    pub fn contains_key(&self, id: &usize) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Adds or replaces an object, returning the object replaced.
+    pub fn insert(&mut self, id: usize, obj: Object) -> Option<Object> {
+        if id >= self.slots.len() {
+            self.slots.resize(id + 1, None);
+        }
+        self.slots[id].replace(obj)
+    }
+
+    pub fn remove(&mut self, id: &usize) -> Option<Object> {
+        self.slots.get_mut(*id).and_then(Option::take)
+    }
+
+    /// Iterates over the identifiers and objects, in order of identifier.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Object)> {
+        self.slots.iter()
+            .enumerate()
+            .filter_map(|(id, slot)| slot.as_ref().map(|obj| (id, obj)))
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Object> {
+        self.slots.iter().flatten()
+    }
+}
+
+impl FromIterator<(usize, Object)> for Objects {
+    fn from_iter<I: IntoIterator<Item = (usize, Object)>>(iter: I) -> Self {
+        let mut objects = Objects::new();
+        for (id, obj) in iter {
+            objects.insert(id, obj);
+        }
+        objects
+    }
+}
+
+impl Index<&usize> for Objects {
+    type Output = Object;
+
+    fn index(&self, id: &usize) -> &Object {
+        self.get(id).expect("object is defined")
+    }
+}
+
+/// Returns the scope a program starts with, holding only the built-in objects,
+/// with room for every identifier of the program.
+pub fn program_scope(identifiers: &HashMap<usize, String>) -> Scope {
+    let capacity = identifiers.keys().max().map_or(0, |id| id + 1);
+    let mut scope = Scope {
+        locals: Objects::with_capacity(capacity),
+        globals: Objects::with_capacity(capacity),
+        exit_code: None
+    };
+    scope.globals.insert(0, EMPTY);
+    scope.globals.insert(1, builtin_level());
+    scope
+}
+
+/// Returns the exit code requested by a program, given the scope it was exited with.
+pub fn exit_code(result: usize) -> Option<i32> {
+    match result {
+        WIN_SCOPE => Some(0),
+        DEFEAT_SCOPE => Some(1),
+        _ => None
+    }
+}
+
+/// Describes every object in a scope other than the built-in ones, 
+/// one per line, in order of identifier.
+/// 
+/// Each line holds the identifier, its name and its value, separated by tabs.
+pub fn dump_state(scope: &Scope, identifiers: &HashMap<usize, String>) -> String {
+    let mut objects = scope.locals.iter()
+        .chain(scope.globals.iter())
+        .filter(|(id, _)| !is_reserved(*id))
+        .collect::<Vec<_>>();
+    objects.sort_by_key(|(id, _)| *id);
+    objects.iter()
+        .map(|(id, obj)| format!("{}\t{}\t{}", id, identifiers.get(id).map_or("", String::as_str), obj))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Options affecting the behavior of the interpreter.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    /// Redefining a local object with a different type is an error.
+    pub strict: bool,
+    /// The maximum number of bytes a GROUP reads with WORD or LINE, if any.
+    pub max_line: Option<usize>,
+    /// The byte separating the words a GROUP reads with LINE, if not a space.
+    pub delimiter: Option<u8>,
+    /// The number of bits in each coordinate of a YOU object, if not 8. Must be
+    /// between 1 and 32. TEXT still writes the lowest byte.
+    pub width: Option<u32>,
+    /// Reading an undefined object is an error. Otherwise, it is read as EMPTY.
+    pub strict_undef: bool,
+    /// Each simple instruction is logged to stderr as it is executed, along with 
+    /// the state of its subject before and after if it is a YOU or YOU2.
+    pub trace: bool
+}
+
+/// The largest coordinate of a YOU object, past which it wraps around. See `Options::width`.
+pub fn you_max(options: &Options) -> u32 {
+    u32::MAX >> (32 - options.width.unwrap_or(8))
 }
 
 pub const PRG_SCOPE: usize = 0;
 pub const NO_BREAK: usize = 1;
 pub const _UNUSED_SCOPE: usize = 2;
+// These signify that the program was stopped by WIN or DEFEAT
+pub const WIN_SCOPE: usize = usize::MAX;
+pub const DEFEAT_SCOPE: usize = usize::MAX - 1;
 
-/// Executes a Babalang AST with a limited scope.
-/// 
-/// `locals` is a slice of identifiers if this is in a function scope.
-/// 
-/// `globals` is the set of floating variables. 
+/// The terminal bell, written by PLAY.
+const BEL: u8 = 0x07;
+
+/// What undefined objects are read as, unless `strict_undef` is set.
+static UNDEFINED: Object = EMPTY;
+
+/// A program being executed one instruction at a time, paused before the
+/// instruction it executes next, borrowing its AST and identifiers. 
+/// See `Interpreter::start`.
+#[derive(Debug, Clone)]
+pub struct Execution<'a> {
+    // The objects of the program scope, returned once it finishes
+    scope: Scope,
+    // The scopes being executed, from outermost to innermost. Empty once 
+    // the program has finished (or was stopped by an error)
+    frames: Vec<Frame<'a>>,
+    identifiers: &'a HashMap<usize, String>,
+    options: Options
+}
+
+/// A scope being executed: the scope given to `exec_with` (e.g. the program), 
+/// an iteration of a TELE loop, or a call made by POWER.
+#[derive(Debug, Clone)]
+struct Frame<'a> {
+    instructions: Body<'a>,
+    // The offset of the instruction to execute next
+    next: usize,
+    // The identifier of the TELE, LEVEL or IMAGE, or PRG_SCOPE
+    scope: usize,
+    // The value returned by the scope so far
+    return_value: Option<Object>,
+    // The call made by POWER, if the scope is one
+    call: Option<PowerCall>
+}
+
+impl<'a> Frame<'a> {
+    fn new(instructions: Body<'a>, scope: usize, call: Option<PowerCall>) -> Self {
+        Frame { instructions, next: 0, scope, return_value: None, call }
+    }
+}
+
+/// The instructions of a frame: borrowed from the AST given to `exec_with`,
+/// or shared with the TELE, LEVEL or IMAGE they belong to.
+#[derive(Debug, Clone)]
+enum Body<'a> {
+    Borrowed(&'a [Instruction]),
+    Shared(Arc<[Instruction]>)
+}
+
+impl Deref for Body<'_> {
+    type Target = [Instruction];
+
+    fn deref(&self) -> &[Instruction] {
+        match self {
+            Body::Borrowed(instructions) => instructions,
+            Body::Shared(instructions) => instructions
+        }
+    }
+}
+
+/// A call made by `X POWER` (or `X POWER FLOAT`) that is being executed.
+#[derive(Debug, Clone)]
+struct PowerCall {
+    callee: Level,
+    id: usize,
+    float: bool,
+    locals: Objects
+}
+
+impl Execution<'_> {
+    /// Returns the instruction the program is paused before, or None once it has finished.
+    pub fn instruction(&self) -> Option<&Instruction> {
+        self.frames.last().map(|frame| &frame.instructions[frame.next])
+    }
+
+    /// Returns the scope executing the next instruction: the identifier of its
+    /// TELE, LEVEL or IMAGE, or `PRG_SCOPE`.
+    pub fn scope(&self) -> usize {
+        self.frames.last().map_or(PRG_SCOPE, |frame| frame.scope)
+    }
+
+    /// Returns the locals the next instruction has access to.
+    pub fn locals(&self) -> &Objects {
+        self.frames.iter()
+            .rev()
+            .find_map(|frame| frame.call.as_ref().map(|call| &call.locals))
+            .unwrap_or(&self.scope.locals)
+    }
+
+    /// Returns the globals the next instruction has access to.
+    pub fn globals(&self) -> &Objects {
+        &self.scope.globals
+    }
+}
+
+/// Returns the locals and globals of the innermost frame, given those of the outermost one.
+fn frame_objects<'a>(
+    frames: &'a mut [Frame],
+    locals: &'a mut Objects,
+    globals: &'a mut Objects
+) -> (&'a mut Objects, &'a mut Objects) {
+    let locals = frames.iter_mut()
+        .rev()
+        .find_map(|frame| frame.call.as_mut().map(|call| &mut call.locals))
+        .unwrap_or(locals);
+    (locals, globals)
+}
+
+/// Executes Babalang programs, reading the input of WORD from `input` and
+/// writing the output of TEXT and PLAY to `output`.
 /// 
-/// The built-in EMPTY, LEVEL and IMAGE objects will always be accessible in all scopes.
-fn exec_with<'a>(
-    ast: &'a [Instruction], 
-    locals: &mut HashMap<usize, Object>,
-    globals: &mut HashMap<usize, Object>,
-    _scope: usize, // Possible useful for error messages
-    identifiers: &HashMap<usize, String>
-) -> (usize, Option<Object>) {
-    let (mut return_scope, mut return_value) = (NO_BREAK, None);
-    for instruction in ast {
+/// Output is buffered, and only flushed by PLAY, before reading input, and
+/// once the program has finished.
+pub struct Interpreter<R: Read, W: Write> {
+    input: BufReader<R>,
+    output: BufWriter<W>,
+    // The number of times each kind of instruction was executed, if profiling
+    profile: Option<HashMap<&'static str, u64>>
+}
+
+impl<R: Read, W: Write> Interpreter<R, W> {
+    /// Creates an interpreter with the given streams, e.g. `Interpreter::new(stdin(), stdout())`.
+    pub fn new(input: R, output: W) -> Self {
+        Interpreter { input: BufReader::new(input), output: BufWriter::new(output), profile: None }
+    }
+
+    /// Counts the simple instructions executed, by kind (e.g. `MOVE`), 
+    /// along with the iterations of TELE loops. See `profile`.
+    pub fn enable_profile(&mut self) {
+        self.profile = Some(HashMap::new());
+    }
+
+    /// Returns the number of times each kind of instruction was executed, most
+    /// executed first, or None unless enabled with `enable_profile`.
+    /// 
+    /// TELE counts the iterations of every loop.
+    pub fn profile(&self) -> Option<Vec<(&'static str, u64)>> {
+        let mut counts = self.profile.as_ref()?
+            .iter()
+            .map(|(&kind, &count)| (kind, count))
+            .collect::<Vec<_>>();
+        // Ties are broken by name, so that the output is stable
+        counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+        Some(counts)
+    }
+
+    /// Increments the profile count of a kind of instruction, if profiling.
+    pub fn count(&mut self, kind: &'static str) {
+        if let Some(counts) = &mut self.profile {
+            *counts.entry(kind).or_insert(0) += 1;
+        }
+    }
+
+    /// Consumes the interpreter, returning its output stream
+    /// (e.g. to inspect what a program wrote).
+    pub fn into_output(self) -> W {
+        // Unwrap will catch syscall errors
+        self.output.into_inner().unwrap_or_else(|e| panic!("{}", e.error()))
+    }
+
+    /// Writes out any buffered output. `exec` and `bytecode::run_bytecode`
+    /// do so before returning, even if the program was stopped by an error.
+    pub fn flush(&mut self) {
+        // Unwrap will catch syscall errors
+        self.output.flush().unwrap();
+    }
+
+    /// Executes a Babalang AST in the global scope.
+    /// 
+    /// Returns the final program scope, so that the objects computed
+    /// by the program can be inspected afterwards, or the first error raised.
+    pub fn exec(&mut self, ast: &[Instruction], identifiers: &HashMap<usize, String>, options: &Options) -> Result<Scope, BabaError> {
+        let mut scope = program_scope(identifiers);
+        // Scopes 0, 1 and 2 are reserved
+        // 0 is used to refer to the program scope
+        // 1 signifies that a function scope has been exited
+        // 2 signifies that a scope should not be exited
+        let executed = self.exec_with(ast, &mut scope.locals, &mut scope.globals, PRG_SCOPE, identifiers, options);
+        clear_context();
+        self.flush();
+        let (result, _) = executed?;
+        scope.exit_code = exit_code(result);
+        Ok(scope)
+    }
+
+    /// Executes a Babalang AST with a limited scope.
+    /// 
+    /// `locals` is a slice of identifiers if this is in a function scope.
+    /// 
+    /// `globals` is the set of floating variables. 
+    /// 
+    /// The built-in EMPTY, LEVEL and IMAGE objects will always be accessible in all scopes.
+    pub fn exec_with(
+        &mut self,
+        ast: &[Instruction], 
+        locals: &mut Objects,
+        globals: &mut Objects,
+        scope: usize,
+        identifiers: &HashMap<usize, String>,
+        options: &Options
+    ) -> Result<(usize, Option<Object>), BabaError> {
+        let mut frames = vec![Frame::new(Body::Borrowed(ast), scope, None)];
+        // Errors can report the scopes that lead to them
+        enter_scope(enclosing_scope(scope, identifiers));
+        let result = self.run_frames(&mut frames, locals, globals, identifiers, options);
+        // The scopes left by an error
+        for _ in &frames {
+            exit_scope();
+        }
+        result
+    }
+
+    /// Starts executing a Babalang AST in the global scope one instruction at a
+    /// time, paused before its first instruction. See `step`.
+    pub fn start<'a>(&self, ast: &'a [Instruction], identifiers: &'a HashMap<usize, String>, options: &Options) -> Execution<'a> {
+        let program = Frame::new(Body::Borrowed(ast), PRG_SCOPE, None);
+        Execution {
+            scope: program_scope(identifiers),
+            frames: if ast.is_empty() { Vec::new() } else { vec![program] },
+            identifiers,
+            options: *options
+        }
+    }
+
+    /// Executes the instruction a program started by `start` is paused before,
+    /// and pauses before the next one, at any depth. Does nothing once the 
+    /// program has finished.
+    /// 
+    /// TELE loops and POWER calls are stepped into, while the LEVEL calls made by 
+    /// PLAY and FEED are executed along with the instruction making them.
+    /// 
+    /// Returns the error raised by the instruction, if any, which stops the program.
+    pub fn step(&mut self, execution: &mut Execution<'_>) -> Result<(), BabaError> {
+        let Execution { scope, frames, identifiers, options } = execution;
+        if frames.is_empty() {
+            return Ok(())
+        }
+        // Errors can report the scopes that lead to them
+        for frame in frames.iter() {
+            enter_scope(enclosing_scope(frame.scope, identifiers));
+        }
+        let exited = self.execute(frames, &mut scope.locals, &mut scope.globals, identifiers, options)
+            .and_then(|outcome| self.unwind(outcome, frames, &mut scope.locals, &mut scope.globals, identifiers, options));
+        for _ in frames.iter() {
+            exit_scope();
+        }
+        match &exited {
+            Ok(Some((result, _))) => scope.exit_code = exit_code(*result),
+            Ok(None) => (),
+            Err(_) => frames.clear()
+        }
+        if frames.is_empty() {
+            clear_context();
+        }
+        // The output so far is shown while paused
+        self.flush();
+        exited.map(|_| ())
+    }
+
+    /// Runs a program started by `start` to completion, returning its final 
+    /// scope or the first error raised, as `exec` does.
+    pub fn finish(&mut self, execution: Execution<'_>) -> Result<Scope, BabaError> {
+        let Execution { mut scope, mut frames, identifiers, options } = execution;
+        if frames.is_empty() {
+            return Ok(scope)
+        }
+        for frame in &frames {
+            enter_scope(enclosing_scope(frame.scope, identifiers));
+        }
+        let exited = self.run_frames(&mut frames, &mut scope.locals, &mut scope.globals, identifiers, &options);
+        for _ in &frames {
+            exit_scope();
+        }
+        clear_context();
+        self.flush();
+        let (result, _) = exited?;
+        scope.exit_code = exit_code(result);
+        Ok(scope)
+    }
+
+    /// Executes instructions until the outermost frame is exited, returning the 
+    /// scope exited and the value returned. See `exec_with`.
+    fn run_frames(
+        &mut self,
+        frames: &mut Vec<Frame>,
+        locals: &mut Objects,
+        globals: &mut Objects,
+        identifiers: &HashMap<usize, String>,
+        options: &Options
+    ) -> Result<(usize, Option<Object>), BabaError> {
+        let mut outcome = None;
+        loop {
+            if let Some(exited) = self.unwind(outcome, frames, locals, globals, identifiers, options)? {
+                return Ok(exited)
+            }
+            outcome = self.execute(frames, locals, globals, identifiers, options)?;
+        }
+    }
+
+    /// Executes the next instruction of the innermost frame, given the locals
+    /// and globals of the outermost one.
+    /// 
+    /// Returns the scope exited and the value returned, as `exec_simple` does,
+    /// or None if the instruction entered a new frame.
+    fn execute<'a>(
+        &mut self,
+        frames: &mut Vec<Frame<'a>>,
+        locals: &mut Objects,
+        globals: &mut Objects,
+        identifiers: &HashMap<usize, String>,
+        options: &Options
+    ) -> Result<Option<(usize, Option<Object>)>, BabaError> {
+        let frame = frames.last_mut().expect("a frame is being executed");
+        let instructions = frame.instructions.clone();
+        let instruction = &instructions[frame.next];
+        frame.next += 1;
+        // Runtime errors report the instruction and the scope they occur in
+        set_context(kind(instruction), enclosing_scope(frame.scope, identifiers));
         match instruction {
             Instruction::Level(level) => {
-                let mut new_callback = level.instructions.to_owned();
-                new_callback.push(Instruction::Simple(Simple::MakeValue(level.identifier, 0)));
-                let obj = Object {
-                    reference_count: 0,
-                    obj_type: Type::Level(Level {
-                        identifier: level.identifier,
-                        arguments: level.arguments.to_owned(),
-                        parameters: Vec::new(),
-                        callback: new_callback
-                    })
-                };
-                initialize(level.identifier, obj, level.float, locals, globals, identifiers);
+                let (current, globals) = frame_objects(frames, locals, globals);
+                initialize(level.identifier, level_object(level), level.float, current, globals, identifiers, options)?;
             },
             Instruction::Image(image) => {
-                let attributes: HashMap<usize, Option<Object>> = image.attributes.iter()
-                    .map(|&attr| (attr, None))
-                    .collect();
-                let mut new_callback = image.constructor.instructions.to_vec();
-                new_callback.push(
-                    Instruction::Simple(
-                        Simple::MakeValue(image.identifier, image.constructor.arguments[0])
-                    )
-                );
-                let obj = Object { 
-                    reference_count: 0,
-                    obj_type: Type::Image(Image {
-                        identifier: image.identifier,
-                        attribute_pointer: 0,
-                        attributes: attributes,
-                        constructor: Level {
-                            identifier: image.identifier,
-                            arguments: image.constructor.arguments.to_owned(),
-                            parameters: Vec::new(),
-                            callback: new_callback
-                        }
-                    })
-                };
-                initialize(image.identifier, obj, image.float, locals, globals, identifiers);
+                let (current, globals) = frame_objects(frames, locals, globals);
+                initialize(image.identifier, image_object(image), image.float, current, globals, identifiers, options)?;
             },
             Instruction::Tele(tele) => {
-                loop {
-                    let (result, returns) = exec_with(
-                        &tele.instructions, 
-                        locals, 
-                        globals, 
-                        tele.identifier, 
-                        identifiers
-                    );
-                    return_value = returns;
-                    if result == NO_BREAK {
-                        continue;    
-                    }
-                    else if result == tele.identifier {
-                        break;
-                    }
-                    else {
-                        return_scope = result;
-                        return (return_scope, return_value)
-                    }
-                }
+                self.count("TELE");
+                enter_scope(enclosing_scope(tele.identifier, identifiers));
+                frames.push(Frame::new(Body::Shared(tele.instructions.clone()), tele.identifier, None));
+                return Ok(None)
             },
             Instruction::Complex(complex) => {
-                let conditional_id = match complex.instruction {
-                    Simple::Text(id) => Some(id),
-                    Simple::Word(id) => Some(id),
-                    Simple::Win(id) => Some(id),
-                    Simple::Defeat(id) => Some(id),
-                    Simple::IsValue(id, _, _) => Some(id),
-                    Simple::IsSum(id, _, _) => Some(id),
-                    Simple::MimicReference(id, _) => Some(id),
-                    Simple::IsEmpty(id) => Some(id),
-                    Simple::Move(id, _) => Some(id),
-                    Simple::Turn(id, _) => Some(id),
-                    Simple::Fall(id, _) => Some(id),
-                    Simple::More(id, _) => Some(id),
-                    Simple::Right(id, _) => Some(id),
-                    Simple::Up(id, _) => Some(id),
-                    Simple::Left(id, _) => Some(id),
-                    Simple::Down(id, _) => Some(id),
-                    Simple::Shift(id, _) => Some(id),
-                    Simple::Sink(id) => Some(id),
-                    Simple::Swap(id) => Some(id),
-                    Simple::HasValue(id, _) => Some(id),
-                    Simple::MakeValue(id, _) => Some(id),
-                    Simple::Power(id, _) => Some(id),
-                    Simple::FearTele(id, _) => Some(id),
-                    Simple::FollowAttribute(id, _) => Some(id),
-                    Simple::EatValue(id, _) => Some(id),
-                    _ => None
-                };
-                if let Some(source_id) = conditional_id {
-                    if let Some(source) = find_ref(&source_id, locals, globals, identifiers) {
-                        let mut complete = true;
-                        if let Some(conds) = &complex.conditions {
-                            match conds.cond_type {
-                                Conditional::On => {
-                                    for target in conds.targets.iter() {
-                                        if let Target::Noun(Noun::Identifier(target_id)) = target {
-                                            if let Some(obj) = find_ref(target_id, locals, globals, identifiers) {
-                                                if !((obj.obj_type == source.obj_type) ^ conds.sign) {
-                                                    complete = false;
-                                                }
-                                            }
-                                        }
-                                        else if let Target::Noun(Noun::All) = target {
-                                            if let Type::You(you) = source.obj_type {
-                                                for (_, loc_obj) in locals.iter() {
-                                                    if let Type::You(target_you) = loc_obj.obj_type {
-                                                        if !((you.x == target_you.x && you.y == target_you.y) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else if let Type::You2(target_you) = loc_obj.obj_type {
-                                                        if !((you.x as u16 == target_you.x && you.y as u16 == target_you.y) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                }
-                                                for (_, loc_obj) in globals.iter() {
-                                                    if let Type::You(target_you) = loc_obj.obj_type {
-                                                        if !((you.x == target_you.x && you.y == target_you.y) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else if let Type::You2(target_you) = loc_obj.obj_type {
-                                                        if !((you.x as u16 == target_you.x && you.y as u16 == target_you.y) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                            else if let Type::You2(you) = source.obj_type {
-                                                for (_, loc_obj) in locals.iter() {
-                                                    if let Type::You(target_you) = loc_obj.obj_type {
-                                                        if !((you.x == target_you.x as u16 && you.y == target_you.y as u16) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else if let Type::You2(target_you) = loc_obj.obj_type {
-                                                        if !((you.x == target_you.x && you.y == target_you.y) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                }
-                                                for (_, loc_obj) in globals.iter() {
-                                                    if let Type::You(target_you) = loc_obj.obj_type {
-                                                        if !((you.x == target_you.x as u16 && you.y == target_you.y as u16) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else if let Type::You2(target_you) = loc_obj.obj_type {
-                                                        if !((you.x == target_you.x && you.y == target_you.y) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                            else {
-                                                throw_error_str(ErrorType::TypeError, "Invalid target for ON conditional");
-                                                complete = false;
-                                            }
-                                        }
-                                        else {
-                                            throw_error_str(ErrorType::TypeError, "Invalid target for ON conditional");
-                                            complete = false;
-                                        }
-                                    }
-                                },
-                                Conditional::Near => {
-                                    for target in conds.targets.iter() {
-                                        if let Target::Noun(Noun::Identifier(target_id)) = target {
-                                            if let Some(obj) = find_ref(target_id, locals, globals, identifiers) {
-                                                if is_same_type(obj, source) {
-                                                    if conds.sign {
-                                                        complete = false;
-                                                    }
-                                                }
-                                                else {
-                                                    if !conds.sign {
-                                                        complete = false;
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        else if let Target::Noun(Noun::All) = target {
-                                            for (_, obj) in locals.iter() {
-                                                if is_same_type(obj, source) {
-                                                    if conds.sign {
-                                                        complete = false;
-                                                    }
-                                                }
-                                                else {
-                                                    if !conds.sign {
-                                                        complete = false;
-                                                    }
-                                                }
-                                            }
-                                            for (_, obj) in globals.iter() {
-                                                if is_same_type(obj, source) {
-                                                    if conds.sign {
-                                                        complete = false;
-                                                    }
-                                                }
-                                                else {
-                                                    if !conds.sign {
-                                                        complete = false;
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        else if let Target::Noun(Noun::Empty) = target {
-                                            if let Type::Empty(_) = source.obj_type {
-                                                if conds.sign {
-                                                    complete = false;
-                                                }
-                                            }
-                                            else {
-                                                if !conds.sign {
-                                                    complete = false;
-                                                }
-                                            }
-                                        }
-                                        else if let Target::Noun(Noun::Level) = target {
-                                            if let Type::Level(_) = source.obj_type {
-                                                if conds.sign {
-                                                    complete = false;
-                                                }
-                                            }
-                                            else {
-                                                if !conds.sign {
-                                                    complete = false;
-                                                }
-                                            }
-                                        }
-                                        else if let Target::Noun(Noun::Image) = target {
-                                            if let Type::Image(_) = source.obj_type {
-                                                if conds.sign {
-                                                    complete = false;
-                                                }
-                                            }
-                                            else if let Type::ImageInstance(_) = source.obj_type {
-                                                if conds.sign {
-                                                    complete = false;
-                                                }
-                                            }
-                                            else {
-                                                if !conds.sign {
-                                                    complete = false;
-                                                }
-                                            }
-                                        }
-                                        else {
-                                            complete = false;
-                                            throw_error_str(ErrorType::TypeError, "Invalid target for NEAR conditional");
-                                        }
-                                    }
-                                },
-                                Conditional::Facing => {
-                                    for target in conds.targets.iter() {
-                                        if let Target::Noun(Noun::Identifier(target_id)) = target {
-                                            if let Some(obj) = find_ref(target_id, locals, globals, identifiers) {
-                                                if let Type::You(you) = source.obj_type {
-                                                    if let Type::You(target_obj) = obj.obj_type {
-                                                        if !((you < target_obj) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else if let Type::You2(target_obj) = obj.obj_type {
-                                                        if !((you < target_obj) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else {
-                                                        complete = false;
-                                                        throw_error_str(ErrorType::TypeError, "Invalid target for FACING conditional");
-                                                    }
-                                                }
-                                                else if let Type::You2(you) = source.obj_type {
-                                                    if let Type::You(target_obj) = obj.obj_type {
-                                                        if !((you < target_obj) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else if let Type::You2(target_obj) = obj.obj_type {
-                                                        if !((you < target_obj) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else {
-                                                        complete = false;
-                                                        throw_error_str(ErrorType::TypeError, "Invalid target for FACING conditional");
-                                                    }
-                                                }
-                                                else if let Type::Group(group) = &source.obj_type {
-                                                    if let Type::Group(target_obj) = &obj.obj_type {
-                                                        if !((group < target_obj) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else {
-                                                        complete = false;
-                                                        throw_error_str(ErrorType::TypeError, "Invalid target for FACING conditional");
-                                                    }
-                                                }
-                                                else {
-                                                    complete = false;
-                                                    throw_error_str(ErrorType::TypeError, "Invalid subject for FACING conditional");
-                                                }
-                                            }
-                                        }
-                                        else if let Target::Noun(Noun::All) = target {
-                                            for (_, obj) in locals.iter() {
-                                                if let Type::You(you) = source.obj_type {
-                                                    if let Type::You(target_obj) = obj.obj_type {
-                                                        if !((you < target_obj) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else if let Type::You2(target_obj) = obj.obj_type {
-                                                        if !((you < target_obj) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else {
-                                                        complete = false;
-                                                        throw_error_str(ErrorType::TypeError, "Invalid target for FACING conditional");
-                                                    }
-                                                }
-                                                else if let Type::You2(you) = source.obj_type {
-                                                    if let Type::You(target_obj) = obj.obj_type {
-                                                        if !((you < target_obj) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else if let Type::You2(target_obj) = obj.obj_type {
-                                                        if !((you < target_obj) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else {
-                                                        complete = false;
-                                                        throw_error_str(ErrorType::TypeError, "Invalid target for FACING conditional");
-                                                    }
-                                                }
-                                                else if let Type::Group(group) = &source.obj_type {
-                                                    if let Type::Group(target_obj) = &obj.obj_type {
-                                                        if !((group < target_obj) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else {
-                                                        complete = false;
-                                                        throw_error_str(ErrorType::TypeError, "Invalid target for FACING conditional");
-                                                    }
-                                                }
-                                                else {
-                                                    complete = false;
-                                                    throw_error_str(ErrorType::TypeError, "Invalid subject for FACING conditional");
-                                                }
-                                            }
-                                            for (_, obj) in globals.iter() {
-                                                if let Type::You(you) = source.obj_type {
-                                                    if let Type::You(target_obj) = obj.obj_type {
-                                                        if !((you < target_obj) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else if let Type::You2(target_obj) = obj.obj_type {
-                                                        if !((you < target_obj) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else {
-                                                        complete = false;
-                                                        throw_error_str(ErrorType::TypeError, "Invalid target for FACING conditional");
-                                                    }
-                                                }
-                                                else if let Type::You2(you) = source.obj_type {
-                                                    if let Type::You(target_obj) = obj.obj_type {
-                                                        if !((you < target_obj) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else if let Type::You2(target_obj) = obj.obj_type {
-                                                        if !((you < target_obj) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else {
-                                                        complete = false;
-                                                        throw_error_str(ErrorType::TypeError, "Invalid target for FACING conditional");
-                                                    }
-                                                }
-                                                else if let Type::Group(group) = &source.obj_type {
-                                                    if let Type::Group(target_obj) = &obj.obj_type {
-                                                        if !((group < target_obj) ^ conds.sign) {
-                                                            complete = false;
-                                                        }
-                                                    }
-                                                    else {
-                                                        complete = false;
-                                                        throw_error_str(ErrorType::TypeError, "Invalid target for FACING conditional");
-                                                    }
-                                                }
-                                                else {
-                                                    complete = false;
-                                                    throw_error_str(ErrorType::TypeError, "Invalid subject for FACING conditional");
-                                                }
-                                            }
-                                        }
-                                        else if let Target::Property(Property::Right) = target {
-                                            if let Type::You(you) = &source.obj_type {
-                                                if !((you.dir == 0) ^ conds.sign) {
-                                                    complete = false;
-                                                }
-                                            }
-                                            else if let Type::You2(you) = &source.obj_type {
-                                                if !((you.dir == 0) ^ conds.sign) {
-                                                    complete = false;
-                                                }
-                                            }
-                                            else {
-                                                complete = false;
-                                                throw_error_str(ErrorType::TypeError, "Invalid subject for FACING conditional");
-                                            }
-                                        }
-                                        else if let Target::Property(Property::Up) = target {
-                                            if let Type::You(you) = &source.obj_type {
-                                                if !((you.dir == 1) ^ conds.sign) {
-                                                    complete = false;
-                                                }
-                                            }
-                                            else if let Type::You2(you) = &source.obj_type {
-                                                if !((you.dir == 1) ^ conds.sign) {
-                                                    complete = false;
-                                                }
-                                            }
-                                            else {
-                                                complete = false;
-                                                throw_error_str(ErrorType::TypeError, "Invalid subject for FACING conditional");
-                                            }
-                                        }
-                                        else if let Target::Property(Property::Left) = target {
-                                            if let Type::You(you) = &source.obj_type {
-                                                if !((you.dir == 2) ^ conds.sign) {
-                                                    complete = false;
-                                                }
-                                            }
-                                            else if let Type::You2(you) = &source.obj_type {
-                                                if !((you.dir == 2) ^ conds.sign) {
-                                                    complete = false;
-                                                }
-                                            }
-                                            else {
-                                                complete = false;
-                                                throw_error_str(ErrorType::TypeError, "Invalid subject for FACING conditional");
-                                            }
-                                        }
-                                        else if let Target::Property(Property::Down) = target {
-                                            if let Type::You(you) = &source.obj_type {
-                                                if !((you.dir == 3) ^ conds.sign) {
-                                                    complete = false;
-                                                }
-                                            }
-                                            else if let Type::You2(you) = &source.obj_type {
-                                                if !((you.dir == 3) ^ conds.sign) {
-                                                    complete = false;
-                                                }
-                                            }
-                                            else {
-                                                complete = false;
-                                                throw_error_str(ErrorType::TypeError, "Invalid subject for FACING conditional");
-                                            }
-                                        }
-                                        else {
-                                            complete = false;
-                                            throw_error_str(ErrorType::TypeError, "Invalid target for FACING conditional");
-                                        };
-                                    }
-                                },
-                                Conditional::Without => {
-                                    if let Type::Group(group) = &source.obj_type {
-                                        for target in conds.targets.iter() {
-                                            if let Target::Noun(Noun::Identifier(target_id)) = target {
-                                                if let Some(obj) = find_ref(target_id, locals, globals, identifiers) {
-                                                    let mut contains = false;
-                                                    for element in group.data.iter() {
-                                                        if element.obj_type == obj.obj_type {
-                                                            contains = true;
-                                                        }
-                                                    }
-                                                    if contains ^ conds.sign {
-                                                        complete = false;
-                                                    }
-                                                }
-                                            }
-                                            else if let Target::Noun(Noun::All) = target {
-                                                for (_, obj) in locals.iter() {
-                                                    let mut contains = false;
-                                                    for element in group.data.iter() {
-                                                        if element.obj_type == obj.obj_type {
-                                                            contains = true;
-                                                        }
-                                                    }
-                                                    if contains ^ conds.sign {
-                                                        complete = false;
-                                                    }
-                                                }
-                                                for (_, obj) in globals.iter() {
-                                                    let mut contains = false;
-                                                    for element in group.data.iter() {
-                                                        if element.obj_type == obj.obj_type {
-                                                            contains = true;
-                                                        }
-                                                    }
-                                                    if contains ^ conds.sign {
-                                                        complete = false;
-                                                    }
-                                                }
-                                            }
-                                            else {
-                                                complete = false;
-                                                throw_error_str(ErrorType::TypeError, "Invalid target for WITHOUT conditional");
-                                            }
-                                        }
-                                    }
-                                    else {
-                                        complete = false;
-                                        throw_error_str(ErrorType::TypeError, "Invalid subject for conditional");
-                                    }
-                                },
-                            }
-                        }
-                        if let Some(pref) = complex.prefix {
-                            match pref.prefix {
-                                Prefix::Lonely => {
-                                    if let Type::You(you) = source.obj_type {
-                                        if !((you.x == 0 && you.y == 0) ^ pref.sign) {
-                                            complete = false;
-                                        }
-                                    }
-                                    else if let Type::You2(you) = source.obj_type {
-                                        if !((you.x == 0 && you.y == 0) ^ pref.sign) {
-                                            complete = false;
-                                        }
-                                    }
-                                    else if let Type::Group(group) = &source.obj_type {
-                                        if !((group.data.len() == 0) ^ pref.sign) {
-                                            complete = false;
-                                        }
-                                    }
-                                    else if let Type::Empty(_) = &source.obj_type {
-                                        if pref.sign {
-                                            complete = false;
-                                        }
-                                    }
-                                    else if let Type::Level(_) = &source.obj_type {
-                                        if !pref.sign {
-                                            complete = false;
-                                        }
-                                    }
-                                    else if let Type::Image(img) = &source.obj_type {
-                                        let mut empty = true;
-                                        for (_, attr) in img.attributes.iter() {
-                                            if let Some(_) = attr {
-                                                empty = false;
-                                            }
-                                        }
-                                        if !(empty ^ pref.sign) {
-                                            complete = false;
-                                        }
-                                    }
-                                    else if let Type::ImageInstance(img) = &source.obj_type {
-                                        let mut empty = true;
-                                        for (_, attr) in img.attributes.iter() {
-                                            if let Some(_) = attr {
-                                                empty = false;
-                                            }
-                                        }
-                                        if !(empty ^ pref.sign) {
-                                            complete = false;
-                                        }
-                                    }
-                                },
-                                Prefix::Idle => {
-                                    if let Type::Level(level) = &source.obj_type {
-                                        if !((level.arguments.len() == level.parameters.len()) ^ pref.sign) {
-                                            complete = false;
-                                        }
-                                    }
-                                    if let Type::Image(img) = &source.obj_type {
-                                        if !((img.constructor.arguments.len() - 1 == img.constructor.parameters.len()) ^ pref.sign) {
-                                            complete = false;
-                                        }
-                                    }
-                                    else {
-                                        complete = !pref.sign;
-                                    }
-                                },
-                                Prefix::Often => {
-                                    let dice: f64 = random();
-                                    if (dice > (3.0 / 4.0)) ^ pref.sign {
-                                        complete = false;
-                                    }
-                                },
-                                Prefix::Seldom => {
-                                    let dice: f64 = random();
-                                    if (dice > (1.0 / 6.0)) ^ pref.sign {
-                                        complete = false;
-                                    }
-                                },
-                            }
-                        }
-                        if complete {
-                            let (result, returns) = exec_simple(&complex.instruction, locals, globals, identifiers);
-                            if result != NO_BREAK {
-                                return_scope = result;
-                                if let Some(_) = returns {
-                                    return_value = returns;
-                                }
-                                return (return_scope, return_value);
-                            }
-                            if let Some(_) = returns {
-                                return_value = returns;
-                                return (return_scope, return_value);
-                            }
-                        }
-                    }
-                }
-                else {
-                    throw_error_str(
-                        ErrorType::ConditionError,
-                        "Conditional statements must have a single subject (not ALL, LEVEL or IMAGE)"
-                    )
+                let (current, shared) = frame_objects(frames, locals, globals);
+                if holds(complex, current, shared, identifiers, options)? {
+                    return self.execute_simple(&complex.instruction, frames, locals, globals, identifiers, options)
                 }
             },
             Instruction::Simple(simple) => {
-                let (result, returns) = exec_simple(simple, locals, globals, identifiers);
-                if result != NO_BREAK {
-                    return_scope = result;
-                    if let Some(_) = returns {
-                        return_value = returns;
-                    }
-                    return (return_scope, return_value);
-                }
-                if let Some(_) = returns {
-                    return_value = returns;
-                    return (return_scope, return_value);
-                }
+                return self.execute_simple(simple, frames, locals, globals, identifiers, options)
             },
-            Instruction::NoOp => (),
             _ => ()
         }
+        Ok(Some((NO_BREAK, None)))
     }
-    (return_scope, return_value)
-}
 
-/// Adds an object to either the locals or the globals.
-fn initialize<'a>(
-    id: usize, 
-    obj: Object,
-    float: bool,
-    locals: &mut HashMap<usize, Object>, 
-    globals: &mut HashMap<usize, Object>,
-    _identifiers: &HashMap<usize, String>
-) {
-    let extra_float = if float {
-        if locals.contains_key(&id) {
-            locals.remove(&id);
+    /// Executes a simple instruction in the innermost frame, given the locals
+    /// and globals of the outermost one, entering a new frame for POWER calls. 
+    /// See `execute`.
+    fn execute_simple(
+        &mut self,
+        simple: &Simple,
+        frames: &mut Vec<Frame>,
+        locals: &mut Objects,
+        globals: &mut Objects,
+        identifiers: &HashMap<usize, String>,
+        options: &Options
+    ) -> Result<Option<(usize, Option<Object>)>, BabaError> {
+        let (locals, globals) = frame_objects(frames, locals, globals);
+        let (id, float) = match simple {
+            Simple::Power(id, float) => (*id, *float),
+            _ => return self.exec_simple(simple, locals, globals, identifiers, options).map(Some)
+        };
+        self.count(simple_kind(simple));
+        if options.trace {
+            log(&format!("{}\n", dump_simple(simple, identifiers)));
+        }
+        match power_call(&id, locals, globals, identifiers)? {
+            Some((callee, new_locals)) => {
+                enter_scope(enclosing_scope(id, identifiers));
+                let callback = Body::Shared(callee.callback.clone());
+                frames.push(Frame::new(callback, id, Some(PowerCall { callee, id, float, locals: new_locals })));
+                Ok(None)
+            },
+            None => Ok(Some((NO_BREAK, None)))
         }
-        true
-    }
-    else {
-        globals.contains_key(&id)
-    };
-    if extra_float {
-        globals.insert(id, obj);
-    }
-    else {
-        locals.insert(id, obj);
     }
-}
 
-/// Executes a single simple instruction in the provided scope.
-fn exec_simple<'a>(
-    simple: &Simple, 
-    locals: &mut HashMap<usize, Object>, 
-    globals: &mut HashMap<usize, Object>, 
-    identifiers: &HashMap<usize, String>
-) -> (usize, Option<Object>) {
-    let (mut return_scope, mut return_value) = (NO_BREAK, None);
-    match simple {
-        Simple::InitYou(id, float) => {
-            initialize(*id, Object { 
-                reference_count: 0,
-                obj_type: Type::You(You {
-                    x: 0,
-                    y: 0,
-                    dir: 0
-                })
-            }, *float, locals, globals, identifiers);
-        },
-        Simple::InitYou2(id, float) => {
-            initialize(*id, Object { 
-                reference_count: 0,
-                obj_type: Type::You2(You2 {
-                    x: 0,
-                    y: 0,
-                    dir: 0
-                })
-            }, *float, locals, globals, identifiers);
-        },
-        Simple::InitGroup(id, float) => {
-            initialize(*id, Object { 
-                reference_count: 0,
-                obj_type: Type::Group(Group {
-                    index: 0,
-                    data: Vec::new()
-                })
-            }, *float, locals, globals, identifiers);
-        },
-        Simple::Text(id) => {
-            if let Some(obj) = find_ref(id, locals, globals, identifiers) {
-                print_object(&obj, Some(*id));
-            }
-        },
-        Simple::Word(id) => {
-            if let Some(obj) = find_mut_ref(id, locals, globals, identifiers) {
-                match &mut obj.obj_type {
-                    Type::You(you) => {
-                        let mut buffer: [u8; 1] = [0];
-                        stdin().read(&mut buffer).unwrap();
-                        if you.dir & 1 == 0 {
-                            you.x = buffer[0];
-                        }
-                        else {
-                            you.y = buffer[0];
-                        }
-                    },
-                    Type::You2(you) => {
-                        let mut buffer: [u8; 1] = [0];
-                        stdin().read(&mut buffer).unwrap();
-                        if you.dir & 1 == 0 {
-                            you.x = buffer[0] as u16;
-                        }
-                        else {
-                            you.y = buffer[0] as u16;
-                        }
-                    },
-                    Type::Group(group) => {
-                        let mut buffer = String::new();
-                        stdin().read_line(&mut buffer).unwrap();
-                        let mut objects = buffer
-                            .bytes()
-                            .collect::<Vec<u8>>()
-                            .iter()
-                            .map(|&x| Object {
-                                reference_count: 0,
-                                obj_type: Type::You(You {
-                                    x: x,
-                                    y: 0,
-                                    dir: 0
-                                })
-                            })
-                            .collect();
-                        let mut new = group.data.to_vec();
-                        new.append(&mut objects);
-                        group.data = new;
-                        group.index += objects.len();
-                    },
-                    x => {
-                        throw_error(
-                            ErrorType::TypeError, 
-                            format!("Object {} of type {} cannot be WORD", id, x),
-                            Some((&[*id], identifiers))
-                        );
-                    }
+    /// Exits the frames that are done once an instruction has been executed, 
+    /// given what it returned (if anything), until the next instruction is 
+    /// reached. `locals` and `globals` are those of the outermost frame.
+    /// 
+    /// Returns the scope exited and the value returned once the outermost
+    /// frame is exited, or None otherwise.
+    fn unwind(
+        &mut self,
+        outcome: Option<(usize, Option<Object>)>,
+        frames: &mut Vec<Frame>,
+        locals: &mut Objects,
+        globals: &mut Objects,
+        identifiers: &HashMap<usize, String>,
+        options: &Options
+    ) -> Result<Option<(usize, Option<Object>)>, BabaError> {
+        let mut outcome = outcome;
+        while let Some(frame) = frames.last_mut() {
+            let (result, returns) = match outcome.take() {
+                // The instruction breaks from the scope, or returns a value
+                Some((result, returns)) if result != NO_BREAK || returns.is_some() => {
+                    (result, returns.or_else(|| frame.return_value.take()))
+                },
+                _ if frame.next < frame.instructions.len() => return Ok(None),
+                // The scope runs to the end
+                _ => (NO_BREAK, frame.return_value.take())
+            };
+            let mut frame = frames.pop().expect("a frame is being exited");
+            exit_scope();
+            if let Some(mut call) = frame.call {
+                let (mut result, mut returns) = (result, returns);
+                // Once the callback runs to the end, it returns with MAKE
+                if result == NO_BREAK {
+                    let make = Simple::MakeValue(call.callee.identifier, call.callee.returns);
+                    set_context(simple_kind(&make), enclosing_scope(call.id, identifiers));
+                    let (made_result, made) = self.exec_simple(&make, &mut call.locals, globals, identifiers, options)?;
+                    result = made_result;
+                    returns = made.or(returns);
                 }
-            }
-        },
-        Simple::Win(id) => {
-            if let Some(obj) = find_ref(id, locals, globals, identifiers) {
-                if let Type::You(_) = obj.obj_type {
-                    exit(0);
+                if is_halted(result) {
+                    outcome = Some((result, None));
                 }
-                else if let Type::You2(_) = obj.obj_type {
-                    exit(0);
+                else {
+                    let (locals, globals) = frame_objects(frames, locals, globals);
+                    store_result(call.id, call.float, returns, locals, globals);
+                    outcome = Some((NO_BREAK, None));
                 }
+                continue
             }
-        },
-        Simple::Defeat(id) => {
-            if let Some(obj) = find_ref(id, locals, globals, identifiers) {
-                if let Type::You(_) = obj.obj_type {
-                    exit(1);
-                }
-                else if let Type::You2(_) = obj.obj_type {
-                    exit(1);
-                }
+            let parent = match frames.last_mut() {
+                Some(parent) => parent,
+                None => return Ok(Some((result, returns)))
+            };
+            // Each iteration of a TELE loop replaces the value returned by its scope
+            parent.return_value = returns;
+            if result == NO_BREAK {
+                self.count("TELE");
+                enter_scope(enclosing_scope(frame.scope, identifiers));
+                frame.next = 0;
+                frames.push(frame);
             }
-        },
-        Simple::Sleep(id) => {
-            if let Some(obj) = find_ref(id, locals, globals, identifiers) {
-                if let Type::You(you) = obj.obj_type {
-                    if you.dir & 1 == 0 {
-                        sleep(Duration::from_secs(you.x as u64));
-                    }
-                    else {
-                        sleep(Duration::from_secs(you.y as u64));
-                    }
-                }
-                else if let Type::You2(you) = obj.obj_type {
-                    if you.dir & 1 == 0 {
-                        sleep(Duration::from_millis(you.x as u64));
-                    }
-                    else {
-                        sleep(Duration::from_millis(you.y as u64));
-                    }
-                }
-                else {
-                    throw_error(
-                        ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot be SLEEP", id, obj.obj_type),
-                        Some((&[*id], identifiers))
-                    );
-                }
+            else if result != frame.scope {
+                outcome = Some((result, None));
             }
-        },
-        Simple::IsValue(source_id, target_id, not) => {
-            let glob = globals.contains_key(source_id);
-            let dir = if let Some(source) = try_find_ref(source_id, locals, globals, identifiers) {
-                if let Type::You(you) = source.obj_type {
-                    you.dir
-                }
-                else if let Type::You2(you) = source.obj_type {
-                    you.dir
-                }
-                else {
-                    0
-                }
+        }
+        unreachable!("the outermost frame returns once exited")
+    }
+
+    /// Executes a single simple instruction in the provided scope, 
+    /// tracing it if `options.trace` is set.
+    pub fn exec_simple(
+        &mut self,
+        simple: &Simple, 
+        locals: &mut Objects, 
+        globals: &mut Objects, 
+        identifiers: &HashMap<usize, String>,
+        options: &Options
+    ) -> Result<(usize, Option<Object>), BabaError> {
+        self.count(simple_kind(simple));
+        if !options.trace {
+            return self.run_simple(simple, locals, globals, identifiers, options)
+        }
+        let line = dump_simple(simple, identifiers);
+        let you = subject(simple)
+            .and_then(|id| try_find_ref(&id, locals, globals, identifiers))
+            .filter(|obj| matches!(obj.obj_type, Type::You(_) | Type::You2(_)))
+            .map(Object::to_string);
+        match you {
+            // Instructions on YOU objects are traced once they are done, so that 
+            // the new value can be shown. Any instructions they execute in turn 
+            // (through PLAY or FEED) are traced before them.
+            Some(before) => {
+                let result = self.run_simple(simple, locals, globals, identifiers, options);
+                let after = subject(simple)
+                    .and_then(|id| try_find_ref(&id, locals, globals, identifiers))
+                    .map_or(String::from("undefined"), Object::to_string);
+                log(&format!("{}\t{} -> {}\n", line, before, after));
+                result
+            },
+            None => {
+                log(&format!("{}\n", line));
+                self.run_simple(simple, locals, globals, identifiers, options)
             }
-            else {
-                0
-            };
-            if let Some(target) = find_ref(target_id, locals, globals, identifiers) {
-                if let Type::You(you) = target.obj_type {
-                    if *not {
-                        initialize(*source_id, Object {
-                            reference_count: 0,
-                            obj_type: Type::You(
-                                You {
-                                    x: 255 - you.x,
-                                    y: 255 - you.y,
-                                    dir: dir
-                                }
-                            )
-                        }, glob, locals, globals, identifiers);
-                    }
-                    else {
-                        initialize(*source_id, Object {
-                            reference_count: 0,
-                            obj_type: Type::You(
-                                You {
-                                    x: you.x,
-                                    y: you.y,
-                                    dir: dir
-                                }
-                            )
-                        }, glob, locals, globals, identifiers);
-                    }
-                }
-                else if let Type::You2(you) = target.obj_type {
-                    if *not {
-                        initialize(*source_id, Object {
-                            reference_count: 0,
-                            obj_type: Type::You2(
-                                You2 {
-                                    x: 65535 - you.x,
-                                    y: 65535 - you.y,
-                                    dir: dir
-                                }
-                            )
-                        }, glob, locals, globals, identifiers);
-                    }
-                    else {
-                        initialize(*source_id, Object {
-                            reference_count: 0,
-                            obj_type: Type::You2(
-                                You2 {
-                                    x: you.x,
-                                    y: you.y,
-                                    dir: dir
-                                }
-                            )
-                        }, glob, locals, globals, identifiers);
-                    }
-                }
-                else {
-                    initialize(*source_id, target.clone(), glob, locals, globals, identifiers);
-                }
-            }
-        },
-        Simple::MimicReference(source_id, target_id) => {
-            if let Some(obj) = find_mut_ref(target_id, locals, globals, identifiers) {
-                obj.reference_count += 1;
-            }
-            initialize(*source_id, Object {
-                reference_count: 0, 
-                obj_type: Type::Reference(Reference {
-                    pointer: *target_id
-                })
-            }, false, locals, globals, identifiers);
-        },
-        Simple::IsEmpty(id) => {
-            if let Some(obj) = locals.get_mut(id) {
-                *obj = Object {
+        }
+    }
+
+    /// Executes a single simple instruction in the provided scope. See `exec_simple`.
+    fn run_simple(
+        &mut self,
+        simple: &Simple, 
+        locals: &mut Objects, 
+        globals: &mut Objects, 
+        identifiers: &HashMap<usize, String>,
+        options: &Options
+    ) -> Result<(usize, Option<Object>), BabaError> {
+        let (mut return_scope, mut return_value) = (NO_BREAK, None);
+        match simple {
+            // Sleeping objects ignore anything that would move or turn them
+            Simple::Move(id, _) | Simple::Turn(id, _) | Simple::Fall(id, _) | Simple::More(id, _)
+            | Simple::Less(id, _) | Simple::Right(id, _) | Simple::Up(id, _) | Simple::Left(id, _)
+            | Simple::Down(id, _) | Simple::Chill(id, _) | Simple::Both(id, _)
+                if is_asleep(id, locals, globals, identifiers) => (),
+            Simple::InitYou(id, float) => {
+                initialize(*id, Object { 
                     reference_count: 0,
-                    obj_type: Type::Empty(Empty {})
-                };
-            }
-            else if let Some(obj) = globals.get_mut(id) {
-                *obj = Object {
+                    obj_type: Type::You(You {
+                        x: 0,
+                        y: 0,
+                        dir: 0,
+                        sleeping: false
+                    })
+                }, *float, locals, globals, identifiers, options)?;
+            },
+            Simple::InitYou2(id, float) => {
+                initialize(*id, Object { 
+                    reference_count: 0,
+                    obj_type: Type::You2(You2 {
+                        x: 0,
+                        y: 0,
+                        dir: 0,
+                        sleeping: false
+                    })
+                }, *float, locals, globals, identifiers, options)?;
+            },
+            Simple::InitGroup(id, float) => {
+                initialize(*id, Object { 
+                    reference_count: 0,
+                    obj_type: Type::Group(Group {
+                        index: 0,
+                        data: Arc::new(Vec::new())
+                    })
+                }, *float, locals, globals, identifiers, options)?;
+            },
+            Simple::IsLiteral(id, bytes) => {
+                initialize(*id, Object { 
                     reference_count: 0,
-                    obj_type: Type::Empty(Empty {})
+                    obj_type: Type::Group(Group {
+                        index: 0,
+                        data: bytes.iter()
+                            .map(|&byte| Object {
+                                reference_count: 0,
+                                obj_type: Type::You(You { x: byte as u32, y: 0, dir: 0, sleeping: false })
+                            })
+                            .collect::<Vec<Object>>()
+                            .into()
+                    })
+                }, false, locals, globals, identifiers, options)?;
+            },
+            // As with IS of another YOU, the direction of the object is kept
+            Simple::IsNumber(id, number, not) => {
+                let glob = globals.contains_key(id);
+                let dir = match try_find_ref(id, locals, globals, identifiers).map(|obj| &obj.obj_type) {
+                    Some(Type::You(you)) => you.dir,
+                    Some(Type::You2(you)) => you.dir,
+                    _ => 0
                 };
-            }
-            else {
-                locals.insert(*id, Object {
-                    reference_count: 0, 
-                    obj_type: Type::Empty(Empty {})
-                });
-            }
-        },
-        // Targets are guaranteed to be Noun::Identifier or Noun::All
-        Simple::IsSum(source_id, targets, nots) => {
-            let (mut sum_x, mut sum_y): (u16, u16) = (0, 0);
-            for (target, not) in targets.iter().zip(nots.iter()) {
-                if let Noun::Identifier(id) = target {
-                    if let Some(target_obj) = find_value(id, locals, globals, identifiers) {
-                        if let Type::You(you) = target_obj.obj_type {
-                            if *not {
-                                sum_x = sum_x.wrapping_sub(you.x as u16);
-                                sum_y = sum_y.wrapping_sub(you.y as u16);
-                            }
-                            else {
-                                sum_x = sum_x.wrapping_add(you.x as u16);
-                                sum_y = sum_y.wrapping_add(you.y as u16);
-                            }
-                        }
-                        else if let Type::You2(you) = target_obj.obj_type {
-                            if *not {
-                                sum_x = sum_x.wrapping_sub(you.x);
-                                sum_y = sum_y.wrapping_sub(you.y);
+                let max = you_max(options);
+                let number = number & max;
+                let (x, y) = if *not { (max - number, max) } else { (number, 0) };
+                initialize(*id, Object {
+                    reference_count: 0,
+                    obj_type: Type::You(You { x, y, dir, sleeping: false })
+                }, glob, locals, globals, identifiers, options)?;
+            },
+            Simple::Text(id) => {
+                if let Some(obj) = find_ref(id, locals, globals, identifiers, options)? {
+                    self.print_object(obj, Some(*id))?;
+                }
+            },
+            Simple::Word(id) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    match &mut obj.obj_type {
+                        Type::You(you) => {
+                            let byte = read_byte(&mut self.output, &mut self.input);
+                            if you.dir & 1 == 0 {
+                                you.x = byte as u32;
                             }
                             else {
-                                sum_x = sum_x.wrapping_add(you.x);
-                                sum_y = sum_y.wrapping_add(you.y);
+                                you.y = byte as u32;
                             }
-                        }
-                        else {
-                            if let Noun::Identifier(id) = target {
-                                throw_error(
-                                    ErrorType::ObjectAlreadyDefinedError, 
-                                    format!("Object {} of type {} does not support addition", id, target_obj.obj_type),
-                                    Some((&[*source_id], identifiers))
-                                );
+                        },
+                        Type::You2(you) => {
+                            let byte = read_byte(&mut self.output, &mut self.input);
+                            if you.dir & 1 == 0 {
+                                you.x = byte as u16;
                             }
                             else {
-                                throw_error(
-                                    ErrorType::ObjectAlreadyDefinedError, 
-                                    format!("Object {:?} of type {} does not support addition", id, target_obj.obj_type),
-                                    Some((&[*source_id], identifiers))
-                                );
+                                you.y = byte as u16;
                             }
+                        },
+                        Type::Group(group) => {
+                            let buffer = read_line(&mut self.output, &mut self.input, options.max_line);
+                            append_line(group, &buffer);
+                        },
+                        x => {
+                            return Err(error(
+                                ErrorType::TypeError, 
+                                format!("Object {} of type {} cannot be WORD", id, x),
+                                Some((&[*id], identifiers))
+                            ));
                         }
                     }
                 }
-                // While the use of ALL is not necessarily efficient or fast,
-                // the concept itself is niche and doesn't warrant extended use
-                else if let Noun::All = target {
-                    let (mut all_x, mut all_y): (u16, u16) = (0, 0);
-                    // Get all YOU objects in the current scope
-                    let all_loc = locals.values()
-                        .filter(|x| matches!(
-                            x, Object { reference_count: _, obj_type: Type::You(_)}
-                        ) || matches!(
-                            x, Object { reference_count: _, obj_type: Type::You2(_)}
-                        ))
-                        .map(|x| x.obj_type.clone());
-                    let all_glob = globals.values()
-                        .filter(|x| matches!(
-                            x, Object { reference_count: _, obj_type: Type::You(_)}
-                        ) || matches!(
-                            x, Object { reference_count: _, obj_type: Type::You2(_)}
-                        ))
-                        .map(|x| x.obj_type.clone());
-                    // Take their sum
-                    for value in all_loc {
-                        if let Type::You(you) = value {
-                            all_x = all_x.wrapping_add(you.x as u16);
-                            all_y = all_y.wrapping_add(you.y as u16);
+            },
+            Simple::Line(id) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::Group(group) = &mut obj.obj_type {
+                        let buffer = read_line(&mut self.output, &mut self.input, options.max_line);
+                        append_words(group, &buffer, options.delimiter.unwrap_or(b' '));
+                    }
+                    else {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be LINE", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
+                    }
+                }
+            },
+            Simple::Win(id) => {
+                if let Some(obj) = find_ref(id, locals, globals, identifiers, options)? {
+                    if let Type::You(_) = obj.obj_type {
+                        return_scope = WIN_SCOPE;
+                    }
+                    else if let Type::You2(_) = obj.obj_type {
+                        return_scope = WIN_SCOPE;
+                    }
+                }
+            },
+            Simple::Defeat(id) => {
+                if let Some(obj) = find_ref(id, locals, globals, identifiers, options)? {
+                    if let Type::You(_) = obj.obj_type {
+                        return_scope = DEFEAT_SCOPE;
+                    }
+                    else if let Type::You2(_) = obj.obj_type {
+                        return_scope = DEFEAT_SCOPE;
+                    }
+                }
+            },
+            // ALL IS WIN / DEFEAT trigger if any YOU object exists in scope
+            Simple::AllWin => {
+                if any_you(locals, globals) {
+                    return_scope = WIN_SCOPE;
+                }
+            },
+            Simple::AllDefeat => {
+                if any_you(locals, globals) {
+                    return_scope = DEFEAT_SCOPE;
+                }
+            },
+            // Waits, then puts the object to sleep until it is RESET or redefined
+            Simple::Sleep(id) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::You(you) = &mut obj.obj_type {
+                        if you.dir & 1 == 0 {
+                            sleep(Duration::from_secs(you.x as u64));
                         }
-                        else if let Type::You2(you) = value {
-                            all_x = all_x.wrapping_add(you.x);
-                            all_y = all_y.wrapping_add(you.y);
+                        else {
+                            sleep(Duration::from_secs(you.y as u64));
                         }
+                        you.sleeping = true;
                     }
-                    for value in all_glob {
-                        if let Type::You(you) = value {
-                            all_x = all_x.wrapping_add(you.x as u16);
-                            all_y = all_y.wrapping_add(you.y as u16);
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        if you.dir & 1 == 0 {
+                            sleep(Duration::from_millis(you.x as u64));
                         }
-                        else if let Type::You2(you) = value {
-                            all_x = all_x.wrapping_add(you.x);
-                            all_y = all_y.wrapping_add(you.y);
+                        else {
+                            sleep(Duration::from_millis(you.y as u64));
                         }
+                        you.sleeping = true;
+                    }
+                    else {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be SLEEP", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
                     }
-                    // Add the final ALL sums to our final final sum
-                    if *not {
-                        sum_x = sum_x.wrapping_add(all_x);
-                        sum_y = sum_y.wrapping_add(all_y);
+                }
+            },
+            Simple::IsValue(source_id, target_id, not) => {
+                let glob = globals.contains_key(source_id);
+                let dir = if let Some(source) = try_find_ref(source_id, locals, globals, identifiers) {
+                    if let Type::You(you) = source.obj_type {
+                        you.dir
+                    }
+                    else if let Type::You2(you) = source.obj_type {
+                        you.dir
                     }
                     else {
-                        sum_x = sum_x.wrapping_sub(all_x);
-                        sum_y = sum_y.wrapping_sub(all_y);
+                        0
                     }
                 }
                 else {
-                    // This should never happen, but:
-                    throw_error(
-                        ErrorType::RuntimeError,
-                        format!("Unexpected target {:?} in IsSum expression", target),
-                        None
-                    )
-                }
-            }
-            // Take the result and apply that to our source object
-            if let Some(obj) = locals.get_mut(&source_id) {
-                if let Type::You(you_source) = &mut obj.obj_type {
-                    you_source.x = sum_x as u8;
-                    you_source.y = sum_y as u8;
-                }
-                else if let Type::You2(you_source) = &mut obj.obj_type {
-                    you_source.x = sum_x;
-                    you_source.y = sum_y;
-                }
-            }
-            else if let Some(obj) = globals.get_mut(&source_id) {
-                if let Type::You(you_source) = &mut obj.obj_type {
-                    you_source.x = sum_x as u8;
-                    you_source.y = sum_y as u8;
+                    0
+                };
+                if let Some(target) = find_ref(target_id, locals, globals, identifiers, options)? {
+                    if let Type::You(you) = target.obj_type {
+                        if *not {
+                            initialize(*source_id, Object {
+                                reference_count: 0,
+                                obj_type: Type::You(
+                                    You {
+                                        x: you_max(options) - you.x,
+                                        y: you_max(options) - you.y,
+                                        dir,
+                                        sleeping: false
+                                    }
+                                )
+                            }, glob, locals, globals, identifiers, options)?;
+                        }
+                        else {
+                            initialize(*source_id, Object {
+                                reference_count: 0,
+                                obj_type: Type::You(
+                                    You {
+                                        x: you.x,
+                                        y: you.y,
+                                        dir,
+                                        sleeping: false
+                                    }
+                                )
+                            }, glob, locals, globals, identifiers, options)?;
+                        }
+                    }
+                    else if let Type::You2(you) = target.obj_type {
+                        if *not {
+                            initialize(*source_id, Object {
+                                reference_count: 0,
+                                obj_type: Type::You2(
+                                    You2 {
+                                        x: 65535 - you.x,
+                                        y: 65535 - you.y,
+                                        dir,
+                                        sleeping: false
+                                    }
+                                )
+                            }, glob, locals, globals, identifiers, options)?;
+                        }
+                        else {
+                            initialize(*source_id, Object {
+                                reference_count: 0,
+                                obj_type: Type::You2(
+                                    You2 {
+                                        x: you.x,
+                                        y: you.y,
+                                        dir,
+                                        sleeping: false
+                                    }
+                                )
+                            }, glob, locals, globals, identifiers, options)?;
+                        }
+                    }
+                    // LEVEL and IMAGE objects are copied without any arguments
+                    // already passed to them, so that the copy is a fresh alias
+                    // that can be called independently of the original
+                    else if let Type::Level(level) = &target.obj_type {
+                        if *not {
+                            return Err(error(
+                                ErrorType::TypeError, 
+                                format!("Object {} of type LEVEL cannot be inverted", target_id),
+                                Some((&[*target_id], identifiers))
+                            ));
+                        }
+                        let mut copy = level.clone();
+                        copy.parameters.clear();
+                        initialize(*source_id, Object {
+                            reference_count: 0,
+                            obj_type: Type::Level(copy)
+                        }, glob, locals, globals, identifiers, options)?;
+                    }
+                    else if let Type::Image(image) = &target.obj_type {
+                        if *not {
+                            return Err(error(
+                                ErrorType::TypeError, 
+                                format!("Object {} of type IMAGE cannot be inverted", target_id),
+                                Some((&[*target_id], identifiers))
+                            ));
+                        }
+                        let mut copy = image.clone();
+                        copy.constructor.parameters.clear();
+                        initialize(*source_id, Object {
+                            reference_count: 0,
+                            obj_type: Type::Image(copy)
+                        }, glob, locals, globals, identifiers, options)?;
+                    }
+                    else {
+                        initialize(*source_id, target.clone(), glob, locals, globals, identifiers, options)?;
+                    }
                 }
-                else if let Type::You2(you_source) = &mut obj.obj_type {
-                    you_source.x = sum_x;
-                    you_source.y = sum_y;
+            },
+            // MIMIC NOT creates a weak reference, which doesn't count towards
+            // the reference count of its target
+            Simple::MimicReference(source_id, target_id, weak) => {
+                if find_mut_ref(target_id, locals, globals, identifiers)?.is_some() && !*weak {
+                    incref(*target_id, locals, globals);
                 }
-            }            
-            else {
                 initialize(*source_id, Object {
                     reference_count: 0, 
-                    obj_type: Type::You2(You2 {
-                        x: sum_x,
-                        y: sum_y,
-                        dir: 0
+                    obj_type: Type::Reference(Reference {
+                        pointer: *target_id,
+                        weak: *weak
                     })
-                }, false, locals, globals, identifiers);
-            }
-        },
-        Simple::Move(id, not) => {
-            if let Some(obj) = find_mut_ref(id, locals, globals, identifiers) {
-                if let Type::You(you) = &mut obj.obj_type {
-                    // 0 => Right
-                    // 1 => Up
-                    // 2 => Left
-                    // 3 => Down
-                    match you.dir {
-                        0 => {
-                            if *not {
-                                if you.x == 0 {
-                                    you.x = 255;
-                                }
-                                else {
-                                    you.x -= 1;
-                                }
-                            }
-                            else {
-                                if you.x == 255 {
-                                    you.x = 0;
+                }, false, locals, globals, identifiers, options)?;
+            },
+            Simple::IsEmpty(id) => {
+                // References to the identifier are kept
+                if let Some(obj) = slot_mut(*id, locals, globals) {
+                    let old = replace(&mut obj.obj_type, Type::Empty(Empty {}));
+                    release(&old, locals, globals);
+                }
+                else {
+                    locals.insert(*id, Object {
+                        reference_count: 0, 
+                        obj_type: Type::Empty(Empty {})
+                    });
+                }
+            },
+            // Targets are guaranteed to be Noun::Identifier, Noun::Number or Noun::All
+            Simple::IsSum(source_id, targets, nots) => {
+                let (mut sum_x, mut sum_y): (u32, u32) = (0, 0);
+                for (target, not) in targets.iter().zip(nots.iter()) {
+                    if let Noun::Identifier(id) = target {
+                        if let Some(target_obj) = find_value(id, locals, globals, identifiers, options)? {
+                            if let Type::You(you) = target_obj.obj_type {
+                                if *not {
+                                    sum_x = sum_x.wrapping_sub(you.x);
+                                    sum_y = sum_y.wrapping_sub(you.y);
                                 }
                                 else {
-                                    you.x += 1;
+                                    sum_x = sum_x.wrapping_add(you.x);
+                                    sum_y = sum_y.wrapping_add(you.y);
                                 }
                             }
-                        },
-                        1 => {
-                            if *not {
-                                if you.y == 0 {
-                                    you.y = 255;
+                            else if let Type::You2(you) = target_obj.obj_type {
+                                if *not {
+                                    sum_x = sum_x.wrapping_sub(you.x as u32);
+                                    sum_y = sum_y.wrapping_sub(you.y as u32);
                                 }
                                 else {
-                                    you.y -= 1;
+                                    sum_x = sum_x.wrapping_add(you.x as u32);
+                                    sum_y = sum_y.wrapping_add(you.y as u32);
                                 }
                             }
                             else {
-                                if you.y == 255 {
-                                    you.y = 0;
+                                if let Noun::Identifier(id) = target {
+                                    return Err(error(
+                                        ErrorType::ObjectAlreadyDefinedError, 
+                                        format!("Object {} of type {} does not support addition", id, target_obj.obj_type),
+                                        Some((&[*source_id], identifiers))
+                                    ));
                                 }
                                 else {
-                                    you.y += 1;
+                                    return Err(error(
+                                        ErrorType::ObjectAlreadyDefinedError, 
+                                        format!("Object {:?} of type {} does not support addition", id, target_obj.obj_type),
+                                        Some((&[*source_id], identifiers))
+                                    ));
                                 }
                             }
-                        },
-                        2 => {
-                            if *not {
-                                if you.x == 255 {
-                                    you.x = 0;
-                                }
-                                else {
-                                    you.x += 1;
-                                }
+                        }
+                    }
+                    // Numbers only add to the x coordinate
+                    else if let Noun::Number(number) = target {
+                        if *not {
+                            sum_x = sum_x.wrapping_sub(*number);
+                        }
+                        else {
+                            sum_x = sum_x.wrapping_add(*number);
+                        }
+                    }
+                    // While the use of ALL is not necessarily efficient or fast,
+                    // the concept itself is niche and doesn't warrant extended use
+                    else if let Noun::All = target {
+                        let (mut all_x, mut all_y): (u32, u32) = (0, 0);
+                        // Get all YOU objects in the current scope
+                        let all_loc = sorted(locals).into_iter()
+                            .map(|(_, x)| x)
+                            .filter(|x| matches!(
+                                x, Object { reference_count: _, obj_type: Type::You(_)}
+                            ) || matches!(
+                                x, Object { reference_count: _, obj_type: Type::You2(_)}
+                            ))
+                            .map(|x| x.obj_type.clone());
+                        let all_glob = sorted(globals).into_iter()
+                            .map(|(_, x)| x)
+                            .filter(|x| matches!(
+                                x, Object { reference_count: _, obj_type: Type::You(_)}
+                            ) || matches!(
+                                x, Object { reference_count: _, obj_type: Type::You2(_)}
+                            ))
+                            .map(|x| x.obj_type.clone());
+                        // Take their sum
+                        for value in all_loc {
+                            if let Type::You(you) = value {
+                                all_x = all_x.wrapping_add(you.x);
+                                all_y = all_y.wrapping_add(you.y);
                             }
-                            else {
-                                if you.x == 0 {
-                                    you.x = 255;
+                            else if let Type::You2(you) = value {
+                                all_x = all_x.wrapping_add(you.x as u32);
+                                all_y = all_y.wrapping_add(you.y as u32);
+                            }
+                        }
+                        for value in all_glob {
+                            if let Type::You(you) = value {
+                                all_x = all_x.wrapping_add(you.x);
+                                all_y = all_y.wrapping_add(you.y);
+                            }
+                            else if let Type::You2(you) = value {
+                                all_x = all_x.wrapping_add(you.x as u32);
+                                all_y = all_y.wrapping_add(you.y as u32);
+                            }
+                        }
+                        // Add the final ALL sums to our final final sum. 
+                        // As with identifiers, NOT ALL is subtracted and ALL is added
+                        if *not {
+                            sum_x = sum_x.wrapping_sub(all_x);
+                            sum_y = sum_y.wrapping_sub(all_y);
+                        }
+                        else {
+                            sum_x = sum_x.wrapping_add(all_x);
+                            sum_y = sum_y.wrapping_add(all_y);
+                        }
+                    }
+                    else {
+                        // This should never happen, but:
+                        return Err(error(
+                            ErrorType::RuntimeError,
+                            format!("Unexpected target {:?} in IsSum expression", target),
+                            None
+                        ))
+                    }
+                }
+                // Take the result and apply that to our source object
+                if let Some(obj) = locals.get_mut(source_id) {
+                    if let Type::You(you_source) = &mut obj.obj_type {
+                        you_source.x = sum_x & you_max(options);
+                        you_source.y = sum_y & you_max(options);
+                    }
+                    else if let Type::You2(you_source) = &mut obj.obj_type {
+                        you_source.x = sum_x as u16;
+                        you_source.y = sum_y as u16;
+                    }
+                }
+                else if let Some(obj) = globals.get_mut(source_id) {
+                    if let Type::You(you_source) = &mut obj.obj_type {
+                        you_source.x = sum_x & you_max(options);
+                        you_source.y = sum_y & you_max(options);
+                    }
+                    else if let Type::You2(you_source) = &mut obj.obj_type {
+                        you_source.x = sum_x as u16;
+                        you_source.y = sum_y as u16;
+                    }
+                }            
+                else {
+                    initialize(*source_id, Object {
+                        reference_count: 0, 
+                        obj_type: Type::You2(You2 {
+                            x: sum_x as u16,
+                            y: sum_y as u16,
+                            dir: 0,
+                            sleeping: false
+                        })
+                    }, false, locals, globals, identifiers, options)?;
+                }
+            },
+            Simple::Move(id, not) => {
+                let max = you_max(options);
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::You(you) = &mut obj.obj_type {
+                        // 0 => Right
+                        // 1 => Up
+                        // 2 => Left
+                        // 3 => Down
+                        match you.dir {
+                            0 => {
+                                if *not {
+                                    if you.x == 0 {
+                                        you.x = max;
+                                    }
+                                    else {
+                                        you.x -= 1;
+                                    }
                                 }
                                 else {
-                                    you.x -= 1;
+                                    if you.x == max {
+                                        you.x = 0;
+                                    }
+                                    else {
+                                        you.x += 1;
+                                    }
                                 }
-                            }
-                        },
-                        3 => {
-                            if *not {
-                                if you.y == 255 {
-                                    you.y = 0;
+                            },
+                            1 => {
+                                if *not {
+                                    if you.y == 0 {
+                                        you.y = max;
+                                    }
+                                    else {
+                                        you.y -= 1;
+                                    }
                                 }
                                 else {
-                                    you.y += 1;
+                                    if you.y == max {
+                                        you.y = 0;
+                                    }
+                                    else {
+                                        you.y += 1;
+                                    }
                                 }
-                            }
-                            else {
-                                if you.y == 0 {
-                                    you.y = 255;
+                            },
+                            2 => {
+                                if *not {
+                                    if you.x == max {
+                                        you.x = 0;
+                                    }
+                                    else {
+                                        you.x += 1;
+                                    }
                                 }
                                 else {
-                                    you.y -= 1;
+                                    if you.x == 0 {
+                                        you.x = max;
+                                    }
+                                    else {
+                                        you.x -= 1;
+                                    }
                                 }
-                            }
-                        },
-                        // This should never happen
-                        _ => ()
-                    }
-                }
-                else if let Type::You2(you) = &mut obj.obj_type {
-                    // 0 => Right
-                    // 1 => Up
-                    // 2 => Left
-                    // 3 => Down
-                    match you.dir {
-                        0 => {
-                            if *not {
-                                if you.x == 0 {
-                                    you.x = 65535;
+                            },
+                            3 => {
+                                if *not {
+                                    if you.y == max {
+                                        you.y = 0;
+                                    }
+                                    else {
+                                        you.y += 1;
+                                    }
                                 }
                                 else {
-                                    you.x -= 1;
+                                    if you.y == 0 {
+                                        you.y = max;
+                                    }
+                                    else {
+                                        you.y -= 1;
+                                    }
                                 }
-                            }
-                            else {
-                                if you.x == 65535 {
-                                    you.x = 0;
+                            },
+                            // This should never happen
+                            _ => ()
+                        }
+                    }
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        // 0 => Right
+                        // 1 => Up
+                        // 2 => Left
+                        // 3 => Down
+                        match you.dir {
+                            0 => {
+                                if *not {
+                                    if you.x == 0 {
+                                        you.x = 65535;
+                                    }
+                                    else {
+                                        you.x -= 1;
+                                    }
                                 }
                                 else {
-                                    you.x += 1;
+                                    if you.x == 65535 {
+                                        you.x = 0;
+                                    }
+                                    else {
+                                        you.x += 1;
+                                    }
                                 }
-                            }
-                        },
-                        1 => {
-                            if *not {
-                                if you.y == 0 {
-                                    you.y = 65535;
+                            },
+                            1 => {
+                                if *not {
+                                    if you.y == 0 {
+                                        you.y = 65535;
+                                    }
+                                    else {
+                                        you.y -= 1;
+                                    }
                                 }
                                 else {
-                                    you.y -= 1;
+                                    if you.y == 65535 {
+                                        you.y = 0;
+                                    }
+                                    else {
+                                        you.y += 1;
+                                    }
                                 }
-                            }
-                            else {
-                                if you.y == 65535 {
-                                    you.y = 0;
+                            },
+                            2 => {
+                                if *not {
+                                    if you.x == 65535 {
+                                        you.x = 0;
+                                    }
+                                    else {
+                                        you.x += 1;
+                                    }
                                 }
                                 else {
-                                    you.y += 1;
+                                    if you.x == 0 {
+                                        you.x = 65535;
+                                    }
+                                    else {
+                                        you.x -= 1;
+                                    }
                                 }
-                            }
-                        },
-                        2 => {
-                            if *not {
-                                if you.x == 65535 {
-                                    you.x = 0;
+                            },
+                            3 => {
+                                if *not {
+                                    if you.y == 65535 {
+                                        you.y = 0;
+                                    }
+                                    else {
+                                        you.y += 1;
+                                    }
                                 }
                                 else {
-                                    you.x += 1;
+                                    if you.y == 0 {
+                                        you.y = 65535;
+                                    }
+                                    else {
+                                        you.y -= 1;
+                                    }
                                 }
+                            },
+                            // This should never happen
+                            _ => ()
+                        }
+                    }
+                    else {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be MOVE", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
+                    }
+                }
+            },
+            Simple::Turn(id, not) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::You(you) = &mut obj.obj_type {
+                        if *not {
+                            if you.dir == 0 {
+                                you.dir = 3;
                             }
                             else {
-                                if you.x == 0 {
-                                    you.x = 65535;
-                                }
-                                else {
-                                    you.x -= 1;
-                                }
+                                you.dir -= 1;
                             }
-                        },
-                        3 => {
-                            if *not {
-                                if you.y == 65535 {
-                                    you.y = 0;
-                                }
-                                else {
-                                    you.y += 1;
-                                }
+                        }
+                        else {
+                            if you.dir == 3 {
+                                you.dir = 0;
                             }
                             else {
-                                if you.y == 0 {
-                                    you.y = 65535;
-                                }
-                                else {
-                                    you.y -= 1;
-                                }
+                                you.dir += 1;
                             }
-                        },
-                        // This should never happen
-                        _ => ()
+                        }
                     }
-                }
-                else {
-                    throw_error(
-                        ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot be MOVE", id, obj.obj_type),
-                        Some((&[*id], identifiers))
-                    );
-                }
-            }
-        },
-        Simple::Turn(id, not) => {
-            if let Some(obj) = find_mut_ref(id, locals, globals, identifiers) {
-                if let Type::You(you) = &mut obj.obj_type {
-                    if *not {
-                        if you.dir == 0 {
-                            you.dir = 3;
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        if *not {
+                            if you.dir == 0 {
+                                you.dir = 3;
+                            }
+                            else {
+                                you.dir -= 1;
+                            }
                         }
                         else {
-                            you.dir -= 1;
+                            if you.dir == 3 {
+                                you.dir = 0;
+                            }
+                            else {
+                                you.dir += 1;
+                            }
                         }
                     }
+                    else if let Type::Group(group) = &mut obj.obj_type {
+                        // This ignores NOT, because turning 180 degrees in any direction is equivalent
+                        Arc::make_mut(&mut group.data).reverse();
+                    }
                     else {
-                        if you.dir == 3 {
-                            you.dir = 0;
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be TURN", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
+                    }
+                }
+            },
+            Simple::Fall(id, not) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::You(you) = &mut obj.obj_type {
+                        if *not {
+                            if you.dir & 1 == 0 {
+                                you.x = you_max(options);
+                            }
+                            else {
+                                you.y = you_max(options);
+                            }
                         }
                         else {
-                            you.dir += 1;
+                            if you.dir & 1 == 0 {
+                                you.x = 0;
+                            }
+                            else {
+                                you.y = 0;
+                            }
                         }
                     }
-                }
-                else if let Type::You2(you) = &mut obj.obj_type {
-                    if *not {
-                        if you.dir == 0 {
-                            you.dir = 3;
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        if *not {
+                            if you.dir & 1 == 0 {
+                                you.x = 65535;
+                            }
+                            else {
+                                you.y = 65535;
+                            }
                         }
                         else {
-                            you.dir -= 1;
+                            if you.dir & 1 == 0 {
+                                you.x = 0;
+                            }
+                            else {
+                                you.y = 0;
+                            }
                         }
                     }
-                    else {
-                        if you.dir == 3 {
-                            you.dir = 0;
+                    else if let Type::Group(group) = &mut obj.obj_type {
+                        if *not {
+                            group.index = group.data.len().saturating_sub(1);
                         }
                         else {
-                            you.dir += 1;
+                            group.index = 0;
                         }
                     }
+                    else {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be FALL", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
+                    }
                 }
-                else if let Type::Group(group) = &mut obj.obj_type {
-                    // This ignores NOT, because turning 180 degrees in any direction is equivalent
-                    group.data.reverse();
-                }
-                else {
-                    throw_error(
-                        ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot be TURN", id, obj.obj_type),
-                        Some((&[*id], identifiers))
-                    );
-                }
-            }
-        },
-        Simple::Fall(id, not) => {
-            if let Some(obj) = find_mut_ref(id, locals, globals, identifiers) {
-                if let Type::You(you) = &mut obj.obj_type {
-                    if *not {
-                        if you.dir & 1 == 0 {
-                            you.x = 255;
+            },
+            Simple::More(id, not) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::You(you) = &mut obj.obj_type {
+                        if *not {
+                            if you.dir & 1 == 0 {
+                                you.x >>= 1;
+                            }
+                            else {
+                                you.y >>= 1;
+                            }
                         }
                         else {
-                            you.y = 255;
+                            if you.dir & 1 == 0 {
+                                you.x = (you.x << 1) & you_max(options);
+                            }
+                            else {
+                                you.y = (you.y << 1) & you_max(options);
+                            }
                         }
                     }
-                    else {
-                        if you.dir & 1 == 0 {
-                            you.x = 0;
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        if *not {
+                            if you.dir & 1 == 0 {
+                                you.x >>= 1;
+                            }
+                            else {
+                                you.y >>= 1;
+                            }
                         }
                         else {
-                            you.y = 0;
+                            if you.dir & 1 == 0 {
+                                you.x <<= 1;
+                            }
+                            else {
+                                you.y <<= 1;
+                            }
                         }
                     }
+                    else {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be MORE", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
+                    }
                 }
-                else if let Type::You2(you) = &mut obj.obj_type {
-                    if *not {
-                        if you.dir & 1 == 0 {
-                            you.x = 65535;
+            },
+            // LESS halves rounding half up, unlike NOT MORE which floors
+            Simple::Less(id, not) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::You(you) = &mut obj.obj_type {
+                        if *not {
+                            if you.dir & 1 == 0 {
+                                you.x = (you.x << 1) & you_max(options);
+                            }
+                            else {
+                                you.y = (you.y << 1) & you_max(options);
+                            }
                         }
                         else {
-                            you.y = 65535;
+                            if you.dir & 1 == 0 {
+                                you.x = (you.x >> 1) + (you.x & 1);
+                            }
+                            else {
+                                you.y = (you.y >> 1) + (you.y & 1);
+                            }
                         }
                     }
-                    else {
-                        if you.dir & 1 == 0 {
-                            you.x = 0;
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        if *not {
+                            if you.dir & 1 == 0 {
+                                you.x <<= 1;
+                            }
+                            else {
+                                you.y <<= 1;
+                            }
                         }
                         else {
-                            you.y = 0;
+                            if you.dir & 1 == 0 {
+                                you.x = (you.x >> 1) + (you.x & 1);
+                            }
+                            else {
+                                you.y = (you.y >> 1) + (you.y & 1);
+                            }
                         }
                     }
+                    else {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be LESS", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
+                    }
                 }
-                else if let Type::Group(group) = &mut obj.obj_type {
-                    if *not {
-                        group.index = group.data.len() - 1;
+            },
+            Simple::Right(id, not) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::You(you) = &mut obj.obj_type {
+                        if *not {
+                            you.dir = 2;
+                        }
+                        else {
+                            you.dir = 0;
+                        }
+                    }
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        if *not {
+                            you.dir = 2;
+                        }
+                        else {
+                            you.dir = 0;
+                        }
                     }
                     else {
-                        group.index = 0;
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be RIGHT", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
                     }
                 }
-                else {
-                    throw_error(
-                        ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot be FALL", id, obj.obj_type),
-                        Some((&[*id], identifiers))
-                    );
-                }
-            }
-        },
-        Simple::More(id, not) => {
-            if let Some(obj) = find_mut_ref(id, locals, globals, identifiers) {
-                if let Type::You(you) = &mut obj.obj_type {
-                    if *not {
-                        if you.dir & 1 == 0 {
-                            you.x = you.x >> 1;
+            },
+            Simple::Up(id, not) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::You(you) = &mut obj.obj_type {
+                        if *not {
+                            you.dir = 3;
                         }
                         else {
-                            you.y = you.y >> 1;
+                            you.dir = 1;
                         }
                     }
-                    else {
-                        if you.dir & 1 == 0 {
-                            you.x = you.x << 1;
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        if *not {
+                            you.dir = 3;
                         }
                         else {
-                            you.y = you.y << 1;
+                            you.dir = 1;
                         }
                     }
+                    else {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be UP", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
+                    }
                 }
-                else if let Type::You2(you) = &mut obj.obj_type {
-                    if *not {
-                        if you.dir & 1 == 0 {
-                            you.x = you.x >> 1;
+            },
+            Simple::Left(id, not) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::You(you) = &mut obj.obj_type {
+                        if *not {
+                            you.dir = 0;
                         }
                         else {
-                            you.y = you.y >> 1;
+                            you.dir = 2;
                         }
                     }
-                    else {
-                        if you.dir & 1 == 0 {
-                            you.x = you.x << 1;
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        if *not {
+                            you.dir = 0;
                         }
                         else {
-                            you.y = you.y << 1;
+                            you.dir = 2;
                         }
                     }
+                    else {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be LEFT", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
+                    }
                 }
-                else {
-                    throw_error(
-                        ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot be MORE", id, obj.obj_type),
-                        Some((&[*id], identifiers))
-                    );
+            },
+            Simple::Down(id, not) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::You(you) = &mut obj.obj_type {
+                        if *not {
+                            you.dir = 1;
+                        }
+                        else {
+                            you.dir = 3;
+                        }
+                    }
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        if *not {
+                            you.dir = 1;
+                        }
+                        else {
+                            you.dir = 3;
+                        }
+                    }
+                    else {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be DOWN", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
+                    }
                 }
-            }
-        },
-        Simple::Right(id, not) => {
-            if let Some(obj) = find_mut_ref(id, locals, globals, identifiers) {
-                if let Type::You(you) = &mut obj.obj_type {
-                    if *not {
-                        you.dir = 2;
+            },
+            Simple::Chill(id, not) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::You(you) = &mut obj.obj_type {
+                        if *not {
+                            // Whatever
+                        }
+                        else {
+                            if you.dir & 1 == 0 {
+                                you.x = random::<u32>() & you_max(options);
+                            }
+                            else {
+                                you.y = random::<u32>() & you_max(options);
+                            }
+                        }
+                    }
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        if *not {
+                            // Whatever
+                        }
+                        else {
+                            if you.dir & 1 == 0 {
+                                you.x = random::<u16>();
+                            }
+                            else {
+                                you.y = random::<u16>();
+                            }
+                        }
                     }
                     else {
-                        you.dir = 0;
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be CHILL", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
                     }
                 }
-                else if let Type::You2(you) = &mut obj.obj_type {
-                    if *not {
-                        you.dir = 2;
+            },
+            // Copies the axis being faced into the other axis, or the other way around with NOT
+            Simple::Both(id, not) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::You(you) = &mut obj.obj_type {
+                        if (you.dir & 1 == 0) ^ *not {
+                            you.y = you.x;
+                        }
+                        else {
+                            you.x = you.y;
+                        }
+                    }
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        if (you.dir & 1 == 0) ^ *not {
+                            you.y = you.x;
+                        }
+                        else {
+                            you.x = you.y;
+                        }
                     }
                     else {
-                        you.dir = 0;
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be BOTH", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
                     }
                 }
-                else {
-                    throw_error(
-                        ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot be RIGHT", id, obj.obj_type),
-                        Some((&[*id], identifiers))
-                    );
+            },
+            // Moves a YOU back to the origin, facing right and awake, keeping any references to it
+            Simple::Reset(id) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    match &mut obj.obj_type {
+                        Type::You(you) => *you = You { x: 0, y: 0, dir: 0, sleeping: false },
+                        Type::You2(you) => *you = You2 { x: 0, y: 0, dir: 0, sleeping: false },
+                        x => {
+                            return Err(error(
+                                ErrorType::TypeError, 
+                                format!("Object {} of type {} cannot be RESET", id, x),
+                                Some((&[*id], identifiers))
+                            ));
+                        }
+                    }
                 }
-            }
-        },
-        Simple::Up(id, not) => {
-            if let Some(obj) = find_mut_ref(id, locals, globals, identifiers) {
-                if let Type::You(you) = &mut obj.obj_type {
-                    if *not {
-                        you.dir = 3;
+            },
+            Simple::AllMove(not) => {
+                self.exec_all(&Simple::Move, *not, locals, globals, identifiers, options)?;
+            },
+            Simple::AllTurn(not) => {
+                self.exec_all(&Simple::Turn, *not, locals, globals, identifiers, options)?;
+            },
+            Simple::AllFall(not) => {
+                self.exec_all(&Simple::Fall, *not, locals, globals, identifiers, options)?;
+            },
+            Simple::AllMore(not) => {
+                self.exec_all(&Simple::More, *not, locals, globals, identifiers, options)?;
+            },
+            Simple::AllLess(not) => {
+                self.exec_all(&Simple::Less, *not, locals, globals, identifiers, options)?;
+            },
+            Simple::AllRight(not) => {
+                self.exec_all(&Simple::Right, *not, locals, globals, identifiers, options)?;
+            },
+            Simple::AllUp(not) => {
+                self.exec_all(&Simple::Up, *not, locals, globals, identifiers, options)?;
+            },
+            Simple::AllLeft(not) => {
+                self.exec_all(&Simple::Left, *not, locals, globals, identifiers, options)?;
+            },
+            Simple::AllDown(not) => {
+                self.exec_all(&Simple::Down, *not, locals, globals, identifiers, options)?;
+            },
+            Simple::AllChill(not) => {
+                self.exec_all(&Simple::Chill, *not, locals, globals, identifiers, options)?;
+            },
+            Simple::Shift(id, not) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::Group(group) = &mut obj.obj_type {
+                        // An empty GROUP has nowhere to SHIFT to
+                        if group.data.is_empty() {
+                            group.index = 0;
+                        }
+                        else if *not {
+                            if group.index == 0 {
+                                group.index = group.data.len() - 1;
+                            }
+                            else {
+                                group.index -= 1;
+                            }
+                        }
+                        else {
+                            if group.index == group.data.len() - 1 {
+                                group.index = 0;
+                            }
+                            else {
+                                group.index += 1;
+                            }
+
+                        }
                     }
                     else {
-                        you.dir = 1;
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be SHIFT", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
                     }
                 }
-                else if let Type::You2(you) = &mut obj.obj_type {
-                    if *not {
-                        you.dir = 3;
+            },
+            Simple::Sink(id) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::Group(group) = &mut obj.obj_type {
+                        Arc::make_mut(&mut group.data).pop();
+                        // Keep the index on the last element if it was popped
+                        group.index = group.index.min(group.data.len().saturating_sub(1));
                     }
                     else {
-                        you.dir = 1;
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be SINK", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
                     }
                 }
-                else {
-                    throw_error(
-                        ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot be UP", id, obj.obj_type),
-                        Some((&[*id], identifiers))
-                    );
-                }
-            }
-        },
-        Simple::Left(id, not) => {
-            if let Some(obj) = find_mut_ref(id, locals, globals, identifiers) {
-                if let Type::You(you) = &mut obj.obj_type {
-                    if *not {
-                        you.dir = 0;
+            },
+            Simple::Swap(id) => {
+                if let Some(obj) = find_mut_ref(id, locals, globals, identifiers)? {
+                    if let Type::Group(group) = &mut obj.obj_type {
+                        // Nothing to SWAP in an empty GROUP
+                        if let Some(last) = group.data.len().checked_sub(1) {
+                            Arc::make_mut(&mut group.data).swap(group.index.min(last), last);
+                        }
                     }
                     else {
-                        you.dir = 2;
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be SWAP", id, obj.obj_type),
+                            Some((&[*id], identifiers))
+                        ));
                     }
                 }
-                else if let Type::You2(you) = &mut obj.obj_type {
-                    if *not {
-                        you.dir = 0;
+            },
+            Simple::HasValue(source_id, target_id) => {
+                let maybe_target = find_value(target_id, locals, globals, identifiers, options)?.map(Cow::into_owned);
+                if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers)? {
+                    if let Type::Group(group) = &mut obj.obj_type {
+                        if let Some(target) = maybe_target {
+                            Arc::make_mut(&mut group.data).push(target);
+                        }
                     }
-                    else {
-                        you.dir = 2;
+                    else if let Type::Level(level) = &mut obj.obj_type {
+                        if let Some(target) = maybe_target {
+                            level.parameters.push(target);
+                        }
+                    }
+                    else if let Type::Image(image) = &mut obj.obj_type {
+                        if let Some(target) = maybe_target {
+                            image.constructor.parameters.push(target);
+                        }
                     }
                 }
-                else {
-                    throw_error(
-                        ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot be LEFT", id, obj.obj_type),
-                        Some((&[*id], identifiers))
-                    );
+            },
+            // Pushes each byte of a literal as a YOU
+            Simple::HasLiteral(source_id, bytes) => {
+                let objects = bytes.iter().map(|&byte| Object {
+                    reference_count: 0,
+                    obj_type: Type::You(You { x: byte as u32, y: 0, dir: 0, sleeping: false })
+                });
+                if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers)? {
+                    if let Type::Group(group) = &mut obj.obj_type {
+                        Arc::make_mut(&mut group.data).extend(objects);
+                    }
+                    else if let Type::Level(level) = &mut obj.obj_type {
+                        level.parameters.extend(objects);
+                    }
+                    else if let Type::Image(image) = &mut obj.obj_type {
+                        image.constructor.parameters.extend(objects);
+                    }
                 }
-            }
-        },
-        Simple::Down(id, not) => {
-            if let Some(obj) = find_mut_ref(id, locals, globals, identifiers) {
-                if let Type::You(you) = &mut obj.obj_type {
-                    if *not {
-                        you.dir = 1;
+            },
+            // Pushes a number as a YOU, wrapped to the width of YOU objects
+            Simple::HasNumber(source_id, number) => {
+                let object = Object {
+                    reference_count: 0,
+                    obj_type: Type::You(You { x: number & you_max(options), y: 0, dir: 0, sleeping: false })
+                };
+                if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers)? {
+                    if let Type::Group(group) = &mut obj.obj_type {
+                        Arc::make_mut(&mut group.data).push(object);
                     }
-                    else {
-                        you.dir = 3;
+                    else if let Type::Level(level) = &mut obj.obj_type {
+                        level.parameters.push(object);
+                    }
+                    else if let Type::Image(image) = &mut obj.obj_type {
+                        image.constructor.parameters.push(object);
                     }
                 }
-                else if let Type::You2(you) = &mut obj.obj_type {
-                    if *not {
-                        you.dir = 1;
+            },
+            Simple::MakeValue(source_id, target_id) => {
+                let collection_type = if let Some(obj) = find_ref(source_id, locals, globals, identifiers, options)? {
+                    if let Type::Group(_) = &obj.obj_type {
+                        1
+                    }
+                    else if let Type::Level(_) = &obj.obj_type {
+                        2
+                    }
+                    else if let Type::Image(_) = &obj.obj_type {
+                        3
+                    }
+                    else if let Type::ImageInstance(_) = &obj.obj_type {
+                        3
                     }
                     else {
-                        you.dir = 3;
+                        0
                     }
                 }
-                else {
-                    throw_error(
-                        ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot be DOWN", id, obj.obj_type),
-                        Some((&[*id], identifiers))
-                    );
+                else {0};
+                // Popping an element or attribute into EMPTY / LEVEL / IMAGE
+                // would shadow the built-in object
+                if collection_type != 2 && is_reserved(*target_id) {
+                    return Err(error(
+                        ErrorType::ObjectAlreadyDefinedError, 
+                        format!("Built-in object {} cannot be overwritten", target_id),
+                        Some((&[*target_id], identifiers))
+                    ));
                 }
-            }
-        },
-        Simple::Chill(id, not) => {
-            if let Some(obj) = find_mut_ref(id, locals, globals, identifiers) {
-                if let Type::You(you) = &mut obj.obj_type {
-                    if *not {
-                        // Whatever
-                    }
-                    else {
-                        if you.dir & 1 == 0 {
-                            you.x = random::<u8>();
+                match collection_type {
+                    1 => {
+                        let maybe_element = if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers)? {
+                            if let Type::Group(group) = &mut obj.obj_type {
+                                Arc::make_mut(&mut group.data).pop()
+                            } else {None}
+                        } else {None};
+                        if let Some(obj) = maybe_element {
+                            locals.insert(*target_id, obj);
                         }
-                        else {
-                            you.y = random::<u8>();
+                    },
+                    2 => {
+                        if let Some(obj) = find_value(target_id, locals, globals, identifiers, options)? {
+                            return_value = Some(obj.into_owned());
+                            return_scope = *source_id;
                         }
                     }
-                }
-                else if let Type::You2(you) = &mut obj.obj_type {
-                    if *not {
-                        // Whatever
-                    }
-                    else {
-                        if you.dir & 1 == 0 {
-                            you.x = random::<u16>();
+                    3 => {
+                        let maybe_attr = if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers)? {
+                            if let Type::Image(image) = &mut obj.obj_type {
+                                image.attributes[&image.attribute_pointer].clone()
+                            } 
+                            else if let Type::ImageInstance(image) = &mut obj.obj_type {
+                                image.attributes[&image.attribute_pointer].clone()
+                            } else {None}
+                        } else {None};
+                        if let Some(obj) = maybe_attr {
+                            locals.insert(*target_id, obj);
                         }
-                        else {
-                            you.y = random::<u16>();
+                    },
+                    _ => {
+                        if let Some(obj) = find_ref(source_id, locals, globals, identifiers, options)? {
+                            return Err(error(
+                                ErrorType::TypeError, 
+                                format!("Object {} of type {} cannot MAKE anything", source_id, obj.obj_type),
+                                Some((&[*source_id], identifiers))
+                            ));
                         }
                     }
                 }
-                else {
-                    throw_error(
-                        ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot be CHILL", id, obj.obj_type),
-                        Some((&[*id], identifiers))
-                    );
+
+            },
+            Simple::Power(id, float) => {
+                if let Some((callee, mut new_locals)) = power_call(id, locals, globals, identifiers)? {
+                    let (result, fn_ret_val) = self.exec_callback(
+                        &callee, 
+                        &mut new_locals, 
+                        globals,
+                        *id, 
+                        identifiers,
+                        options
+                    )?;
+                    if is_halted(result) {
+                        return Ok((result, None))
+                    }
+                    store_result(*id, *float, fn_ret_val, locals, globals);
                 }
-            }
-        },
-        Simple::AllMove(not) => {
-            exec_all(&Simple::Move, *not, locals, globals, identifiers);
-        },
-        Simple::AllTurn(not) => {
-            exec_all(&Simple::Turn, *not, locals, globals, identifiers);
-        },
-        Simple::AllFall(not) => {
-            exec_all(&Simple::Fall, *not, locals, globals, identifiers);
-        },
-        Simple::AllMore(not) => {
-            exec_all(&Simple::More, *not, locals, globals, identifiers);
-        },
-        Simple::AllRight(not) => {
-            exec_all(&Simple::Right, *not, locals, globals, identifiers);
-        },
-        Simple::AllUp(not) => {
-            exec_all(&Simple::Up, *not, locals, globals, identifiers);
-        },
-        Simple::AllLeft(not) => {
-            exec_all(&Simple::Left, *not, locals, globals, identifiers);
-        },
-        Simple::AllDown(not) => {
-            exec_all(&Simple::Down, *not, locals, globals, identifiers);
-        },
-        Simple::AllChill(not) => {
-            exec_all(&Simple::Chill, *not, locals, globals, identifiers);
-        },
-        Simple::Shift(id, not) => {
-            if let Some(obj) = find_mut_ref(id, locals, globals, identifiers) {
-                if let Type::Group(group) = &mut obj.obj_type {
-                    if *not {
-                        if group.index == 0 {
-                            group.index = group.data.len() - 1;
+            },
+            Simple::FearTele(source_id, target_id) => {
+                // Set the return scope (the final scope to be broken from)
+                // to the specified tele loop (if it exists)
+                if let Some(_valid_obj) = find_ref(source_id, locals, globals, identifiers, options)? {
+                    return_scope = *target_id;
+                }
+            },
+            Simple::FollowAttribute(source_id, attr_id) => {
+                if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers)? {
+                    if let Type::Image(image) = &mut obj.obj_type {
+                        image.attribute_pointer = *attr_id;
+                    }
+                    else if let Type::ImageInstance(image) = &mut obj.obj_type {
+                        image.attribute_pointer = *attr_id;
+                    }
+                    else {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot FOLLOW anything", source_id, obj.obj_type),
+                            Some((&[*source_id], identifiers))
+                        ));
+                    }
+                }
+            },
+            Simple::EatValue(source_id, target_id) => {
+                let maybe_target = find_value(target_id, locals, globals, identifiers, options)?.map(Cow::into_owned);
+                if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers)? {
+                    if let Type::Image(image) = &mut obj.obj_type {
+                        if let Some(target) = maybe_target {
+                            image.attributes.insert(image.attribute_pointer, Some(target));
                         }
-                        else {
-                            group.index -= 1;
+                    }
+                    else if let Type::ImageInstance(image) = &mut obj.obj_type {
+                        if let Some(target) = maybe_target {
+                            image.attributes.insert(image.attribute_pointer, Some(target));
                         }
                     }
                     else {
-                        if group.index == group.data.len() - 1 {
-                            group.index = 0;
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot EAT anything", source_id, obj.obj_type),
+                            Some((&[*source_id], identifiers))
+                        ));
+                    }
+                }
+            },
+            Simple::FeelType(source_id, target_id) => {
+                let maybe_tag = find_ref(target_id, locals, globals, identifiers, options)?.map(|obj| type_tag(&obj.obj_type));
+                if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers)? {
+                    if let Type::You(you) = &mut obj.obj_type {
+                        if let Some(tag) = maybe_tag {
+                            you.x = tag as u32;
+                        }
+                    }
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        if let Some(tag) = maybe_tag {
+                            you.x = tag as u16;
+                        }
+                    }
+                    else {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot FEEL anything", source_id, obj.obj_type),
+                            Some((&[*source_id], identifiers))
+                        ));
+                    }
+                }
+            },
+            // A EQUAL B sets the axis A is facing to its largest value if A was 
+            // equal to B beforehand, and to 0 otherwise
+            Simple::EqualValue(source_id, target_id) => {
+                let maybe_target = find_value(target_id, locals, globals, identifiers, options)?.map(Cow::into_owned);
+                if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers)? {
+                    let equal = match &maybe_target {
+                        Some(target) => obj.obj_type == target.obj_type,
+                        None => false
+                    };
+                    if let Type::You(you) = &mut obj.obj_type {
+                        let value = if equal { you_max(options) } else { 0 };
+                        if you.dir & 1 == 0 {
+                            you.x = value;
                         }
                         else {
-                            group.index += 1;
+                            you.y = value;
                         }
-
+                    }
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        let value = if equal { u16::MAX } else { 0 };
+                        if you.dir & 1 == 0 {
+                            you.x = value;
+                        }
+                        else {
+                            you.y = value;
+                        }
+                    }
+                    else {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be EQUAL to anything", source_id, obj.obj_type),
+                            Some((&[*source_id], identifiers))
+                        ));
                     }
                 }
-                else {
-                    throw_error(
-                        ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot be SHIFT", id, obj.obj_type),
-                        Some((&[*id], identifiers))
-                    );
+            },
+            // A WRITE B sets the axis A is facing to the value of B on the axis 
+            // B is facing, leaving the other axis of A untouched
+            Simple::WriteValue(source_id, target_id) => {
+                let value = match find_value(target_id, locals, globals, identifiers, options)?.as_deref() {
+                    Some(Object { reference_count: _, obj_type: Type::You(you) }) => {
+                        if you.dir & 1 == 0 { you.x } else { you.y }
+                    },
+                    Some(Object { reference_count: _, obj_type: Type::You2(you) }) => {
+                        if you.dir & 1 == 0 { you.x as u32 } else { you.y as u32 }
+                    },
+                    Some(obj) => {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be written", target_id, obj.obj_type),
+                            Some((&[*target_id], identifiers))
+                        ));
+                    },
+                    None => 0
+                };
+                if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers)? {
+                    if let Type::You(you) = &mut obj.obj_type {
+                        if you.dir & 1 == 0 {
+                            you.x = value & you_max(options);
+                        }
+                        else {
+                            you.y = value & you_max(options);
+                        }
+                    }
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        if you.dir & 1 == 0 {
+                            you.x = value as u16;
+                        }
+                        else {
+                            you.y = value as u16;
+                        }
+                    }
+                    else {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot WRITE anything", source_id, obj.obj_type),
+                            Some((&[*source_id], identifiers))
+                        ));
+                    }
                 }
-            }
-        },
-        Simple::Sink(id) => {
-            if let Some(obj) = find_mut_ref(id, locals, globals, identifiers) {
-                if let Type::Group(group) = &mut obj.obj_type {
-                    group.data.pop();
+            },
+            // G SPELL A fills G with the decimal digits (as text) of the value of A
+            Simple::SpellDigits(source_id, target_id) => {
+                let value = match find_value(target_id, locals, globals, identifiers, options)?.as_deref() {
+                    Some(Object { reference_count: _, obj_type: Type::You(you) }) => {
+                        if you.dir & 1 == 0 { you.x } else { you.y }
+                    },
+                    Some(Object { reference_count: _, obj_type: Type::You2(you) }) => {
+                        if you.dir & 1 == 0 { you.x as u32 } else { you.y as u32 }
+                    },
+                    Some(obj) => {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be spelled", target_id, obj.obj_type),
+                            Some((&[*target_id], identifiers))
+                        ));
+                    },
+                    None => 0
+                };
+                if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers)? {
+                    if let Type::Group(group) = &mut obj.obj_type {
+                        group.data = value.to_string()
+                            .bytes()
+                            .map(|digit| Object {
+                                reference_count: 0,
+                                obj_type: Type::You(You { x: digit as u32, y: 0, dir: 0, sleeping: false })
+                            })
+                            .collect::<Vec<Object>>()
+                            .into();
+                        group.index = 0;
+                    }
+                    else {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot SPELL anything", source_id, obj.obj_type),
+                            Some((&[*source_id], identifiers))
+                        ));
+                    }
                 }
-                else {
-                    throw_error(
-                        ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot be SINK", id, obj.obj_type),
-                        Some((&[*id], identifiers))
-                    );
+            },
+            // X PLAY EMPTY rings the terminal bell (writes BEL) as many times as 
+            // the value of the axis X is facing
+            Simple::Play(source_id, 0) => {
+                let count = match find_value(source_id, locals, globals, identifiers, options)?.as_deref() {
+                    Some(Object { reference_count: _, obj_type: Type::You(you) }) => {
+                        if you.dir & 1 == 0 { you.x as usize } else { you.y as usize }
+                    },
+                    Some(Object { reference_count: _, obj_type: Type::You2(you) }) => {
+                        if you.dir & 1 == 0 { you.x as usize } else { you.y as usize }
+                    },
+                    Some(obj) => {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot PLAY anything", source_id, obj.obj_type),
+                            Some((&[*source_id], identifiers))
+                        ));
+                    },
+                    None => 0
+                };
+                play(&mut self.output, count);
+            },
+            // X PLAY Y calls the LEVEL Y with X as its only argument, 
+            // and replaces X with the value it returns
+            Simple::Play(source_id, target_id) => {
+                let level = match find_value(target_id, locals, globals, identifiers, options)?.map(Cow::into_owned) {
+                    Some(Object { reference_count: _, obj_type: Type::Level(level) }) => level,
+                    Some(obj) => {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be PLAYED, only LEVEL and EMPTY can", target_id, obj.obj_type),
+                            Some((&[*target_id], identifiers))
+                        ));
+                    },
+                    None => return Ok((return_scope, return_value))
+                };
+                if level.arguments.len() != 1 {
+                    return Err(error(
+                        ErrorType::ArgumentError, 
+                        format!(
+                            "Object {} of type LEVEL must take exactly 1 argument to be PLAYED, not {}", 
+                            target_id, level.arguments.len()
+                        ),
+                        Some((&[*target_id], identifiers))
+                    ));
                 }
-            }
-        },
-        Simple::Swap(id) => {
-            if let Some(obj) = find_mut_ref(id, locals, globals, identifiers) {
-                if let Type::Group(group) = &mut obj.obj_type {
-                    let last = group.data.len() - 1;
-                    group.data.swap(group.index, last);
+                let argument = match find_value(source_id, locals, globals, identifiers, options)? {
+                    Some(obj) => obj.into_owned(),
+                    None => return Ok((return_scope, return_value))
+                };
+                let (result, fn_ret_val) = self.call_level(&level, *target_id, argument.clone(), globals, identifiers, options)?;
+                if is_halted(result) {
+                    return Ok((result, None))
                 }
-                else {
-                    throw_error(
-                        ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot be SWAP", id, obj.obj_type),
-                        Some((&[*id], identifiers))
-                    );
+                if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers)? {
+                    obj.obj_type = fn_ret_val.unwrap_or(argument).obj_type;
                 }
-            }
-        },
-        Simple::HasValue(source_id, target_id) => {
-            let maybe_target = find_value(target_id, locals, globals, identifiers);
-            if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers) {
-                if let Type::Group(group) = &mut obj.obj_type {
-                    if let Some(target) = maybe_target {
-                        group.data.push(target);
+            },
+            // A TRACK G sets the axis A is facing to the current index of G
+            Simple::TrackIndex(source_id, target_id) => {
+                let index = match find_ref(target_id, locals, globals, identifiers, options)? {
+                    Some(Object { reference_count: _, obj_type: Type::Group(group) }) => group.index,
+                    Some(obj) => {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be tracked", target_id, obj.obj_type),
+                            Some((&[*target_id], identifiers))
+                        ));
+                    },
+                    None => 0
+                };
+                if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers)? {
+                    if let Type::You(you) = &mut obj.obj_type {
+                        let value = index.min(you_max(options) as usize) as u32;
+                        if you.dir & 1 == 0 {
+                            you.x = value;
+                        }
+                        else {
+                            you.y = value;
+                        }
                     }
-                }
-                else if let Type::Level(level) = &mut obj.obj_type {
-                    if let Some(target) = maybe_target {
-                        level.parameters.push(target);
+                    else if let Type::You2(you) = &mut obj.obj_type {
+                        let value = index.min(u16::MAX as usize) as u16;
+                        if you.dir & 1 == 0 {
+                            you.x = value;
+                        }
+                        else {
+                            you.y = value;
+                        }
                     }
-                }
-                else if let Type::Image(image) = &mut obj.obj_type {
-                    if let Some(target) = maybe_target {
-                        image.constructor.parameters.push(target);
+                    else {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot TRACK anything", source_id, obj.obj_type),
+                            Some((&[*source_id], identifiers))
+                        ));
                     }
                 }
-            }
-        },
-        Simple::MakeValue(source_id, target_id) => {
-            let collection_type = if let Some(obj) = find_ref(source_id, locals, globals, identifiers) {
-                if let Type::Group(_) = &obj.obj_type {
-                    1
-                }
-                else if let Type::Level(_) = &obj.obj_type {
-                    2
+            },
+            // G FEED F calls the LEVEL F once on each element of the GROUP G,
+            // replacing the element with the value F returns
+            Simple::FeedLevel(source_id, target_id) => {
+                let level = match find_value(target_id, locals, globals, identifiers, options)?.map(Cow::into_owned) {
+                    Some(Object { reference_count: _, obj_type: Type::Level(level) }) => level,
+                    Some(obj) => {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot be fed to a GROUP", target_id, obj.obj_type),
+                            Some((&[*target_id], identifiers))
+                        ));
+                    },
+                    None => return Ok((return_scope, return_value))
+                };
+                if level.arguments.len() != 1 {
+                    return Err(error(
+                        ErrorType::ArgumentError, 
+                        format!(
+                            "Object {} of type LEVEL must take exactly 1 argument to be fed to a GROUP, not {}", 
+                            target_id, level.arguments.len()
+                        ),
+                        Some((&[*target_id], identifiers))
+                    ));
                 }
-                else if let Type::Image(_) = &obj.obj_type {
-                    3
+                let elements = match find_ref(source_id, locals, globals, identifiers, options)? {
+                    Some(Object { reference_count: _, obj_type: Type::Group(group) }) => group.data.clone(),
+                    Some(obj) => {
+                        return Err(error(
+                            ErrorType::TypeError, 
+                            format!("Object {} of type {} cannot FEED anything", source_id, obj.obj_type),
+                            Some((&[*source_id], identifiers))
+                        ));
+                    },
+                    None => return Ok((return_scope, return_value))
+                };
+                let mut results = Vec::with_capacity(elements.len());
+                for element in elements.iter() {
+                    let (result, fn_ret_val) = self.call_level(&level, *target_id, element.clone(), globals, identifiers, options)?;
+                    if is_halted(result) {
+                        return Ok((result, None))
+                    }
+                    results.push(fn_ret_val.unwrap_or_else(|| element.clone()));
                 }
-                else if let Type::ImageInstance(_) = &obj.obj_type {
-                    3
+                if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers)? {
+                    if let Type::Group(group) = &mut obj.obj_type {
+                        group.data = Arc::new(results);
+                    }
                 }
-                else {
-                    0
+            },
+            // G PEEK X copies the element of G at its index into X, 
+            // unlike MAKE, which removes it
+            Simple::PeekValue(source_id, target_id) => {
+                if is_reserved(*target_id) {
+                    return Err(error(
+                        ErrorType::ObjectAlreadyDefinedError, 
+                        format!("Built-in object {} cannot be overwritten", target_id),
+                        Some((&[*target_id], identifiers))
+                    ));
                 }
-            }
-            else {0};
-            match collection_type {
-                1 => {
-                    let maybe_element = if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers) {
-                        if let Type::Group(group) = &mut obj.obj_type {
-                            group.data.pop()
-                        } else {None}
-                    } else {None};
-                    if let Some(obj) = maybe_element {
-                        locals.insert(*target_id, obj);
-                    }
-                },
-                2 => {
-                    if let Some(obj) = find_value(target_id, locals, globals, identifiers) {
-                        return_value = Some(obj);
-                        return_scope = *source_id;
-                    }
-                }
-                3 => {
-                    let maybe_attr = if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers) {
-                        if let Type::Image(image) = &mut obj.obj_type {
-                            image.attributes[&image.attribute_pointer].clone()
-                        } 
-                        else if let Type::ImageInstance(image) = &mut obj.obj_type {
-                            image.attributes[&image.attribute_pointer].clone()
-                        } else {None}
-                    } else {None};
-                    if let Some(obj) = maybe_attr {
-                        locals.insert(*target_id, obj);
-                    }
-                },
-                _ => {
-                    if let Some(obj) = find_ref(source_id, locals, globals, identifiers) {
-                        throw_error(
+                let element = match find_ref(source_id, locals, globals, identifiers, options)? {
+                    Some(Object { reference_count: _, obj_type: Type::Group(group) }) => {
+                        match group.data.get(group.index) {
+                            Some(element) => element.clone(),
+                            None => {
+                                return Err(error(
+                                    ErrorType::RuntimeError, 
+                                    format!(
+                                        "Object {} of type GROUP has no element at index {}, as it holds {}", 
+                                        source_id, group.index, group.data.len()
+                                    ),
+                                    Some((&[*source_id], identifiers))
+                                ));
+                            }
+                        }
+                    },
+                    Some(obj) => {
+                        return Err(error(
                             ErrorType::TypeError, 
-                            format!("Object {} of type {} cannot MAKE anything", source_id, obj.obj_type),
+                            format!("Object {} of type {} cannot PEEK anything", source_id, obj.obj_type),
                             Some((&[*source_id], identifiers))
-                        );
-                    }
-                }
+                        ));
+                    },
+                    None => return Ok((return_scope, return_value))
+                };
+                locals.insert(*target_id, element);
             }
+        }
+        // println!("LOCALS {:#?}\nGLOBALS {:#?}", locals, globals);
+        Ok((return_scope, return_value))
+    }
 
-        },
-        Simple::Power(id, float) => {
-            // This line is here to avoid borrow conflicts
-            let mut new_globals = globals.clone();
-            let mut new_locals = locals.clone();
-            let mut ret_val = None;
-            let self_ref = find_value(id, locals, globals, identifiers);
-            
-            if let Some(obj) = find_mut_ref(id, locals, globals, identifiers) {
-                if let Type::Level(level) = &mut obj.obj_type {
-                    if level.arguments.len() == level.parameters.len() {
-                        for (arg, param) in level.arguments.iter().zip(level.parameters.iter()) {
-                            new_locals.insert(*arg, param.clone());
-                        }
-                        new_locals.insert(level.identifier, self_ref.unwrap());
-                        let (_, fn_ret_val) = exec_with(
-                            &level.callback, 
-                            &mut new_locals, 
-                            &mut new_globals,
-                            *id, 
-                            identifiers
-                        );
-                        ret_val = fn_ret_val
-                    }
-                    else {
-                        throw_error(
-                            ErrorType::ArgumentError, 
-                            format!(
-                                "Expected {} arguments when calling POWER on object {} of type LEVEL, got {} arguments",
-                                level.arguments.len(), 
-                                id, 
-                                level.parameters.len()
-                            ),
-                            Some((&[*id], identifiers))
-                        );
-                    }
-                }
-                else if let Type::Image(image) = &mut obj.obj_type {
-                    if image.constructor.arguments.len() - 1 == image.constructor.parameters.len() {
-                        for (arg, param) in image.constructor.arguments
-                            .iter()
-                            .skip(1)
-                            .zip(image.constructor.parameters.iter()) 
-                        {
-                            new_locals.insert(*arg, param.clone());
-                        }
-                        new_locals.insert(image.identifier, Object {
-                            reference_count: 0, obj_type: Type::Level(image.constructor.clone()
-                        )});
-                        new_locals.insert(
-                            image.constructor.arguments[0], 
-                            Object {
-                                reference_count: 0, obj_type: Type::ImageInstance(ImageInstance {
-                                    class: image.identifier,
-                                    attribute_pointer: image.attribute_pointer,
-                                    attributes: image.attributes.clone(),
-                                })
-                            }
-                        );
-                        let (_, fn_ret_val) = exec_with(
-                            &image.constructor.callback, 
-                            &mut new_locals, 
-                            &mut new_globals,
-                            *id, 
-                            identifiers
-                        );
-                        ret_val = fn_ret_val
-                    }
-                    else {
-                        throw_error(
-                            ErrorType::ArgumentError, 
-                            format!(
-                                "Expected {} arguments when calling POWER on object {} of type LEVEL, got self + {} arguments",
-                                image.constructor.arguments.len(), 
-                                id, 
-                                image.constructor.parameters.len()
-                            ),
-                            Some((&[*id], identifiers))
-                        );
-                    }
+    /// Calls a LEVEL (given by its identifier `id`) taking a single argument.
+    /// As with POWER, the locals of the call only hold the argument and the
+    /// LEVEL itself, while the globals are shared.
+    fn call_level(
+        &mut self,
+        level: &Level,
+        id: usize,
+        argument: Object,
+        globals: &mut Objects,
+        identifiers: &HashMap<usize, String>,
+        options: &Options
+    ) -> Result<(usize, Option<Object>), BabaError> {
+        let mut new_locals = Objects::new();
+        new_locals.insert(level.arguments[0], argument);
+        new_locals.insert(level.identifier, Object {
+            reference_count: 0, obj_type: Type::Level(level.clone())
+        });
+        self.exec_callback(level, &mut new_locals, globals, id, identifiers, options)
+    }
+
+    /// Executes the callback of a LEVEL or IMAGE in the scope of a call,
+    /// returning the scope exited and the value returned, as `exec_with` does.
+    /// 
+    /// Once the callback runs to the end, it returns `level.returns` with MAKE.
+    pub fn exec_callback(
+        &mut self,
+        level: &Level,
+        locals: &mut Objects,
+        globals: &mut Objects,
+        id: usize,
+        identifiers: &HashMap<usize, String>,
+        options: &Options
+    ) -> Result<(usize, Option<Object>), BabaError> {
+        let (result, returns) = self.exec_with(&level.callback, locals, globals, id, identifiers, options)?;
+        if result != NO_BREAK {
+            return Ok((result, returns))
+        }
+        let make = Simple::MakeValue(level.identifier, level.returns);
+        set_context(simple_kind(&make), enclosing_scope(id, identifiers));
+        let (result, made) = self.exec_simple(&make, locals, globals, identifiers, options)?;
+        Ok((result, made.or(returns)))
+    }
+
+    /// Executes a simple YOU instruction for every YOU object in the current scope.
+    fn exec_all(
+        &mut self,
+        simple_factory: &dyn Fn(usize, bool) -> Simple,
+        not: bool,
+        locals: &mut Objects,
+        globals: &mut Objects,
+        identifiers: &HashMap<usize, String>,
+        options: &Options
+    ) -> Result<(), BabaError> {
+        // Get all YOU keys in the current scope, in a stable order
+        let all_loc: Vec<usize> = sorted(locals).into_iter()
+            .filter(|(_, v)| matches!(
+                v, Object { reference_count: _, obj_type: Type::You(_)}
+            ) || matches!(
+                v, Object { reference_count: _, obj_type: Type::You2(_)}
+            ))
+            .map(|(k, _)| k)
+            .collect();
+        let all_glob: Vec<usize> = sorted(globals).into_iter()
+            .filter(|(_, v)| matches!(
+                v, Object { reference_count: _, obj_type: Type::You(_)}
+            ) || matches!(
+                v, Object { reference_count: _, obj_type: Type::You2(_)}
+            ))
+            .map(|(k, _)| k)
+            .collect();
+        for id in all_loc {
+            self.exec_simple(&simple_factory(id, not), locals, globals, identifiers, options)?;
+        }
+        for id in all_glob {
+            self.exec_simple(&simple_factory(id, not), locals, globals, identifiers, options)?;
+        }
+        Ok(())
+    }
+
+    /// Prints an object's data (`X IS TEXT`) to stdout.
+    /// Returns an error if the object type doesn't support TEXT.
+    /// 
+    /// For YOU objects, prints the 8-bit character associated
+    /// with the object's active axis (active axis === right-left VS up-down?).
+    /// Only the lowest byte is printed, whatever the width of the interpreter.
+    /// 
+    /// For GROUP objects, recursively calls `print_object` on each
+    /// element of the group.
+    /// 
+    /// For EMPTY objects, does nothing.
+    /// 
+    /// For LEVEL / IMAGE objects, returns a TypeError.
+    fn print_object(&mut self, obj: &Object, id: Option<usize>) -> Result<(), BabaError> {
+        match &obj.obj_type {
+            Type::You(you) => {
+                if you.dir & 1 == 0 {
+                    // Unwrap will catch syscall errors
+                    let out = &mut self.output;
+                    out.write_all(&[you.x as u8]).unwrap();
                 }
                 else {
-                    throw_error(
-                        ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot be POWER", id, obj.obj_type),
-                        Some((&[*id], identifiers))
-                    );
+                    let out = &mut self.output;
+                    out.write_all(&[you.y as u8]).unwrap();
                 }
-            }
-            if let Some(obj) = ret_val {
-                if *float {
-                    if locals.contains_key(id) {
-                        locals.remove(id);
+            },
+            Type::You2(you) => {
+                if you.dir & 1 == 0 {
+                    // Unwrap will catch syscall errors
+                    let out = &mut self.output;
+                    let top = (you.x >> 8) as u8;
+                    if top == 0 {
+                        out.write_all(&[you.x as u8]).unwrap();
+                    }
+                    else {
+                        out.write_all(&[(you.x >> 8) as u8, you.x as u8]).unwrap();
                     }
-                    globals.insert(*id, obj);
-                }
-                else if globals.contains_key(id) {
-                    globals.insert(*id, obj);
                 }
                 else {
-                    locals.insert(*id, obj);
+                    let out = &mut self.output;
+                    out.write_all(&[(you.y >> 8) as u8, you.y as u8]).unwrap();
                 }
-            }
-        },
-        Simple::FearTele(source_id, target_id) => {
-            // Set the return scope (the final scope to be broken from)
-            // to the specified tele loop (if it exists)
-            if let Some(_valid_obj) = find_ref(source_id, locals, globals, identifiers) {
-                return_scope = *target_id;
-            }
-        },
-        Simple::FollowAttribute(source_id, attr_id) => {
-            if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers) {
-                if let Type::Image(image) = &mut obj.obj_type {
-                    image.attribute_pointer = *attr_id;
+            },
+            Type::Group(group) => {
+                for object in group.data.iter() {
+                    self.print_object(object, None)?;
                 }
-                else if let Type::ImageInstance(image) = &mut obj.obj_type {
-                    image.attribute_pointer = *attr_id;
+            },
+            x => {
+                if let Some(i) = id {
+                    return Err(error(
+                        ErrorType::TypeError, 
+                        format!("Object {} of type {} cannot be TEXT", i, x),
+                        None
+                    ));
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot FOLLOW anything", source_id, obj.obj_type),
-                        Some((&[*source_id], identifiers))
-                    );
+                        format!("[Unnamed Object] (element of GROUP) of type {} cannot be TEXT", x),
+                        None
+                    ));
                 }
             }
-        },
-        Simple::EatValue(source_id, target_id) => {
-            let maybe_target = find_value(target_id, locals, globals, identifiers);
-            if let Some(obj) = find_mut_ref(source_id, locals, globals, identifiers) {
-                if let Type::Image(image) = &mut obj.obj_type {
-                    if let Some(target) = maybe_target {
-                        image.attributes.insert(image.attribute_pointer, Some(target));
+        }
+        Ok(())
+    }
+}
+
+/// Checks whether the prefix and conditions of a conditional instruction hold,
+/// for its subject in the provided scope.
+pub fn holds(
+    complex: &Complex,
+    locals: &Objects,
+    globals: &Objects,
+    identifiers: &HashMap<usize, String>,
+    options: &Options
+) -> Result<bool, BabaError> {
+    let source_id = match subject(&complex.instruction) {
+        Some(id) => id,
+        // Rejected by the parser, see `instruction::merge`
+        None => return Err(error_str(
+            ErrorType::ConditionError,
+            "Conditional statements must have a single subject (not ALL, LEVEL or IMAGE)"
+        ))
+    };
+    // Conditions don't distinguish between objects that hold EMPTY
+    // and objects that were never defined (or weak references whose
+    // target no longer exists): both are treated as EMPTY. That is,
+    // for such an object X (and any other EMPTY or undefined Y):
+    // - X NEAR EMPTY, X ON EMPTY, X NEAR Y and X ON Y hold
+    // - LONELY X holds
+    let empty = EMPTY;
+    let source = try_find_ref(&source_id, locals, globals, identifiers).unwrap_or(&empty);
+    let mut complete = true;
+    // Each conditional must hold
+    for conds in &complex.conditions {
+        match conds.cond_type {
+            Conditional::On => {
+                for (target, target_sign) in conds.targets.iter() {
+                    let sign = conds.sign ^ target_sign;
+                    if let Target::Noun(Noun::Identifier(target_id)) = target {
+                        if let Some(obj) = try_find_ref(target_id, locals, globals, identifiers).or(Some(&empty)) {
+                            if !(is_on(source, obj) ^ sign) {
+                                complete = false;
+                            }
+                        }
                     }
-                }
-                else if let Type::ImageInstance(image) = &mut obj.obj_type {
-                    if let Some(target) = maybe_target {
-                        image.attributes.insert(image.attribute_pointer, Some(target));
+                    else if let Target::Noun(Noun::Empty) = target {
+                        if !((source.obj_type == empty.obj_type) ^ sign) {
+                            complete = false;
+                        }
+                    }
+                    else if let Target::Noun(Noun::All) = target {
+                        if let Type::You(you) = source.obj_type {
+                            for (_, loc_obj) in sorted(locals) {
+                                if let Type::You(target_you) = loc_obj.obj_type {
+                                    if !((you.x == target_you.x && you.y == target_you.y) ^ sign) {
+                                        complete = false;
+                                    }
+                                }
+                                else if let Type::You2(target_you) = loc_obj.obj_type {
+                                    if !((you.x == target_you.x as u32 && you.y == target_you.y as u32) ^ sign) {
+                                        complete = false;
+                                    }
+                                }
+                            }
+                            for (_, loc_obj) in sorted(globals) {
+                                if let Type::You(target_you) = loc_obj.obj_type {
+                                    if !((you.x == target_you.x && you.y == target_you.y) ^ sign) {
+                                        complete = false;
+                                    }
+                                }
+                                else if let Type::You2(target_you) = loc_obj.obj_type {
+                                    if !((you.x == target_you.x as u32 && you.y == target_you.y as u32) ^ sign) {
+                                        complete = false;
+                                    }
+                                }
+                            }
+                        }
+                        else if let Type::You2(you) = source.obj_type {
+                            for (_, loc_obj) in sorted(locals) {
+                                if let Type::You(target_you) = loc_obj.obj_type {
+                                    if !((you.x as u32 == target_you.x && you.y as u32 == target_you.y) ^ sign) {
+                                        complete = false;
+                                    }
+                                }
+                                else if let Type::You2(target_you) = loc_obj.obj_type {
+                                    if !((you.x == target_you.x && you.y == target_you.y) ^ sign) {
+                                        complete = false;
+                                    }
+                                }
+                            }
+                            for (_, loc_obj) in sorted(globals) {
+                                if let Type::You(target_you) = loc_obj.obj_type {
+                                    if !((you.x as u32 == target_you.x && you.y as u32 == target_you.y) ^ sign) {
+                                        complete = false;
+                                    }
+                                }
+                                else if let Type::You2(target_you) = loc_obj.obj_type {
+                                    if !((you.x == target_you.x && you.y == target_you.y) ^ sign) {
+                                        complete = false;
+                                    }
+                                }
+                            }
+                        }
+                        else {
+                            return Err(error_str(ErrorType::TypeError, "Invalid target for ON conditional"));
+                        }
+                    }
+                    else {
+                        return Err(error_str(ErrorType::TypeError, "Invalid target for ON conditional"));
                     }
                 }
-                else {
-                    throw_error(
-                        ErrorType::TypeError, 
-                        format!("Object {} of type {} cannot EAT anything", source_id, obj.obj_type),
-                        Some((&[*source_id], identifiers))
-                    );
-                }
-            }
-        }
+            },
+            Conditional::Near => {
+                for (target, target_sign) in conds.targets.iter() {
+                    let sign = conds.sign ^ target_sign;
+                    if let Target::Noun(Noun::Identifier(target_id)) = target {
+                        if let Some(obj) = try_find_ref(target_id, locals, globals, identifiers).or(Some(&empty)) {
+                            if is_same_type(obj, source) {
+                                if sign {
+                                    complete = false;
+                                }
+                            }
+                            else {
+                                if !sign {
+                                    complete = false;
+                                }
+                            }
+                        }
+                    }
+                    else if let Target::Noun(Noun::All) = target {
+                        for (_, obj) in sorted(locals) {
+                            if is_same_type(obj, source) {
+                                if sign {
+                                    complete = false;
+                                }
+                            }
+                            else {
+                                if !sign {
+                                    complete = false;
+                                }
+                            }
+                        }
+                        for (_, obj) in sorted(globals) {
+                            if is_same_type(obj, source) {
+                                if sign {
+                                    complete = false;
+                                }
+                            }
+                            else {
+                                if !sign {
+                                    complete = false;
+                                }
+                            }
+                        }
+                    }
+                    else if let Target::Noun(Noun::Empty) = target {
+                        if let Type::Empty(_) = source.obj_type {
+                            if sign {
+                                complete = false;
+                            }
+                        }
+                        else {
+                            if !sign {
+                                complete = false;
+                            }
+                        }
+                    }
+                    else if let Target::Noun(Noun::Level) = target {
+                        if let Type::Level(_) = source.obj_type {
+                            if sign {
+                                complete = false;
+                            }
+                        }
+                        else {
+                            if !sign {
+                                complete = false;
+                            }
+                        }
+                    }
+                    else if let Target::Noun(Noun::Image) = target {
+                        if let Type::Image(_) = source.obj_type {
+                            if sign {
+                                complete = false;
+                            }
+                        }
+                        else if let Type::ImageInstance(_) = source.obj_type {
+                            if sign {
+                                complete = false;
+                            }
+                        }
+                        else {
+                            if !sign {
+                                complete = false;
+                            }
+                        }
+                    }
+                    else {
+                        return Err(error_str(ErrorType::TypeError, "Invalid target for NEAR conditional"));
+                    }
+                }
+            },
+            Conditional::Facing => {
+                for (target, target_sign) in conds.targets.iter() {
+                    let sign = conds.sign ^ target_sign;
+                    if let Target::Noun(Noun::Identifier(_)) | Target::Noun(Noun::All) = target {
+                        // Only YOU, YOU2 and GROUP objects are ordered
+                        if !is_ordered(source) {
+                            return Err(error(
+                                ErrorType::TypeError, 
+                                format!(
+                                    "Object {} of type {} cannot be FACING other objects, only YOU, YOU2 and GROUP can", 
+                                    source_id, source.obj_type
+                                ),
+                                Some((&[source_id], identifiers))
+                            ));
+                        }
+                    }
+                    if let Target::Noun(Noun::Identifier(target_id)) = target {
+                        if let Some(obj) = find_ref(target_id, locals, globals, identifiers, options)? {
+                            match is_facing(source, obj) {
+                                Some(facing) => if !(facing ^ sign) {
+                                    complete = false;
+                                },
+                                None => {
+                                    return Err(error(
+                                        ErrorType::TypeError, 
+                                        format!(
+                                            "Object {} of type {} cannot be FACING object {} of type {}", 
+                                            source_id, source.obj_type, target_id, obj.obj_type
+                                        ),
+                                        Some((&[source_id, *target_id], identifiers))
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                    else if let Target::Noun(Noun::All) = target {
+                        // Objects that can't be compared with the subject are skipped
+                        for (_, obj) in sorted(locals).into_iter().chain(sorted(globals)) {
+                            if let Some(facing) = is_facing(source, obj) {
+                                if !(facing ^ sign) {
+                                    complete = false;
+                                }
+                            }
+                        }
+                    }
+                    else if let Target::Property(Property::Right) = target {
+                        if let Type::You(you) = &source.obj_type {
+                            if !((you.dir == 0) ^ sign) {
+                                complete = false;
+                            }
+                        }
+                        else if let Type::You2(you) = &source.obj_type {
+                            if !((you.dir == 0) ^ sign) {
+                                complete = false;
+                            }
+                        }
+                        else {
+                            return Err(facing_direction_error(source_id, source, identifiers));
+                        }
+                    }
+                    else if let Target::Property(Property::Up) = target {
+                        if let Type::You(you) = &source.obj_type {
+                            if !((you.dir == 1) ^ sign) {
+                                complete = false;
+                            }
+                        }
+                        else if let Type::You2(you) = &source.obj_type {
+                            if !((you.dir == 1) ^ sign) {
+                                complete = false;
+                            }
+                        }
+                        else {
+                            return Err(facing_direction_error(source_id, source, identifiers));
+                        }
+                    }
+                    else if let Target::Property(Property::Left) = target {
+                        if let Type::You(you) = &source.obj_type {
+                            if !((you.dir == 2) ^ sign) {
+                                complete = false;
+                            }
+                        }
+                        else if let Type::You2(you) = &source.obj_type {
+                            if !((you.dir == 2) ^ sign) {
+                                complete = false;
+                            }
+                        }
+                        else {
+                            return Err(facing_direction_error(source_id, source, identifiers));
+                        }
+                    }
+                    else if let Target::Property(Property::Down) = target {
+                        if let Type::You(you) = &source.obj_type {
+                            if !((you.dir == 3) ^ sign) {
+                                complete = false;
+                            }
+                        }
+                        else if let Type::You2(you) = &source.obj_type {
+                            if !((you.dir == 3) ^ sign) {
+                                complete = false;
+                            }
+                        }
+                        else {
+                            return Err(facing_direction_error(source_id, source, identifiers));
+                        }
+                    }
+                    else {
+                        return Err(error_str(ErrorType::TypeError, "Invalid target for FACING conditional"));
+                    };
+                }
+            },
+            // GROUPs are without objects they don't hold, IMAGEs without objects none of their
+            // attributes hold, and YOU without YOU of another active value. See `holds_value`.
+            Conditional::Without => {
+                if let Type::Group(_) | Type::Image(_) | Type::ImageInstance(_) | Type::You(_) | Type::You2(_) = &source.obj_type {
+                    for (target, target_sign) in conds.targets.iter() {
+                        let sign = conds.sign ^ target_sign;
+                        if let Target::Noun(Noun::Identifier(target_id)) = target {
+                            if let Some(obj) = find_ref(target_id, locals, globals, identifiers, options)? {
+                                match holds_value(source, obj) {
+                                    Some(contains) => if contains ^ sign {
+                                        complete = false;
+                                    },
+                                    None => {
+                                        return Err(error(
+                                            ErrorType::TypeError, 
+                                            format!(
+                                                "Object {} of type {} cannot be WITHOUT object {} of type {}", 
+                                                source_id, source.obj_type, target_id, obj.obj_type
+                                            ),
+                                            Some((&[source_id, *target_id], identifiers))
+                                        ));
+                                    }
+                                }
+                            }
+                        }
+                        else if let Target::Noun(Noun::All) = target {
+                            // Objects that can't be compared with the subject are skipped
+                            for (_, obj) in sorted(locals).into_iter().chain(sorted(globals)) {
+                                if let Some(contains) = holds_value(source, obj) {
+                                    if contains ^ sign {
+                                        complete = false;
+                                    }
+                                }
+                            }
+                        }
+                        else {
+                            return Err(error_str(ErrorType::TypeError, "Invalid target for WITHOUT conditional"));
+                        }
+                    }
+                }
+                else {
+                    return Err(error_str(ErrorType::TypeError, "Invalid subject for conditional"));
+                }
+            },
+            // Compares the lengths of GROUPs, with EMPTY as an empty GROUP
+            Conditional::Above => {
+                if let Type::Group(group) = &source.obj_type {
+                    for (target, target_sign) in conds.targets.iter() {
+                        let sign = conds.sign ^ target_sign;
+                        let len = match target {
+                            Target::Noun(Noun::Identifier(target_id)) => {
+                                match find_ref(target_id, locals, globals, identifiers, options)? {
+                                    Some(Object { reference_count: _, obj_type: Type::Group(target_obj) }) => Some(target_obj.data.len()),
+                                    Some(_) => None,
+                                    None => continue
+                                }
+                            },
+                            Target::Noun(Noun::Empty) => Some(0),
+                            _ => None
+                        };
+                        if let Some(len) = len {
+                            if !((group.data.len() > len) ^ sign) {
+                                complete = false;
+                            }
+                        }
+                        else {
+                            return Err(error_str(ErrorType::TypeError, "Invalid target for ABOVE conditional"));
+                        }
+                    }
+                }
+                else {
+                    return Err(error_str(ErrorType::TypeError, "Invalid subject for ABOVE conditional"));
+                }
+            },
+            // Compares the active values of YOU objects, see `active_value`
+            Conditional::Over => {
+                let value = match active_value(source) {
+                    Some(value) => value,
+                    None => return Err(error(
+                        ErrorType::TypeError, 
+                        format!("Object {} of type {} cannot be OVER other objects, only YOU and YOU2 can", source_id, source.obj_type),
+                        Some((&[source_id], identifiers))
+                    ))
+                };
+                for (target, target_sign) in conds.targets.iter() {
+                    let sign = conds.sign ^ target_sign;
+                    if let Target::Noun(Noun::Identifier(target_id)) = target {
+                        if let Some(obj) = find_ref(target_id, locals, globals, identifiers, options)? {
+                            match active_value(obj) {
+                                Some(other) => if !((value > other) ^ sign) {
+                                    complete = false;
+                                },
+                                None => {
+                                    return Err(error(
+                                        ErrorType::TypeError, 
+                                        format!(
+                                            "Object {} of type {} cannot be OVER object {} of type {}", 
+                                            source_id, source.obj_type, target_id, obj.obj_type
+                                        ),
+                                        Some((&[source_id, *target_id], identifiers))
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                    else if let Target::Noun(Noun::All) = target {
+                        // Objects other than YOU are skipped
+                        for (_, obj) in sorted(locals).into_iter().chain(sorted(globals)) {
+                            if let Some(other) = active_value(obj) {
+                                if !((value > other) ^ sign) {
+                                    complete = false;
+                                }
+                            }
+                        }
+                    }
+                    else {
+                        return Err(error_str(ErrorType::TypeError, "Invalid target for OVER conditional"));
+                    }
+                }
+            },
+        }
+    }
+    // With OR, the prefix is evaluated on its own, and either it
+    // or the conditions must hold
+    let conditions_hold = complete;
+    if let Combinator::Or = complex.combinator {
+        complete = true;
+    }
+    if let Some(pref) = complex.prefix {
+        match pref.prefix {
+            Prefix::Lonely => {
+                let lonely = is_lonely(&source.obj_type);
+                if !(lonely ^ pref.sign) {
+                    complete = false;
+                }
+            },
+            Prefix::Idle => {
+                // IDLE holds for a LEVEL or IMAGE that has been given
+                // all of its arguments, and for nothing else
+                let idle = match &source.obj_type {
+                    Type::Level(level) => level.arguments.len() == level.parameters.len(),
+                    // The instance argument isn't passed explicitly
+                    Type::Image(img) => {
+                        img.constructor.arguments.len().saturating_sub(1) == img.constructor.parameters.len()
+                    },
+                    // This includes unresolved references, treated as EMPTY
+                    _ => false
+                };
+                if !(idle ^ pref.sign) {
+                    complete = false;
+                }
+            },
+            Prefix::Often => {
+                let dice: f64 = random();
+                if (dice > (3.0 / 4.0)) ^ pref.sign {
+                    complete = false;
+                }
+            },
+            Prefix::Seldom => {
+                let dice: f64 = random();
+                if (dice > (1.0 / 6.0)) ^ pref.sign {
+                    complete = false;
+                }
+            },
+            Prefix::Powered => {
+                if !(is_truthy(source) ^ pref.sign) {
+                    complete = false;
+                }
+            },
+        }
+    }
+    if let Combinator::Or = complex.combinator {
+        complete |= conditions_hold;
+    }
+    Ok(complete)
+}
+
+/// Returns the instructions executed by a call to a LEVEL in a compiled
+/// program: its body, followed by the return of the LEVEL itself.
+pub fn level_callback(level: &instruction::Level) -> Vec<Instruction> {
+    let mut callback = level.instructions.to_vec();
+    callback.push(Instruction::Simple(Simple::MakeValue(level.identifier, 0)));
+    callback
+}
+
+/// Returns the instructions executed by the constructor of an IMAGE in a
+/// compiled program: its body, followed by the return of the new instance.
+pub fn constructor_callback(image: &instruction::Image) -> Vec<Instruction> {
+    let mut callback = image.constructor.instructions.to_vec();
+    // The parser guarantees that the constructor takes the instance
+    if let Some(instance) = image.constructor.arguments.first() {
+        callback.push(Instruction::Simple(Simple::MakeValue(image.identifier, *instance)));
+    }
+    callback
+}
+
+/// Creates the object defined by `X IS LEVEL`.
+pub fn level_object(level: &instruction::Level) -> Object {
+    Object {
+        reference_count: 0,
+        obj_type: Type::Level(Level {
+            identifier: level.identifier,
+            arguments: level.arguments.to_owned(),
+            parameters: Vec::new(),
+            callback: level.instructions.clone(),
+            returns: 0,
+            entry: None
+        })
+    }
+}
+
+/// Creates the object defined by `X IS IMAGE`.
+pub fn image_object(image: &instruction::Image) -> Object {
+    let attributes: HashMap<usize, Option<Object>> = image.attributes.iter()
+        .map(|&attr| (attr, None))
+        .collect();
+    Object { 
+        reference_count: 0,
+        obj_type: Type::Image(Image {
+            identifier: image.identifier,
+            attribute_pointer: 0,
+            attributes,
+            constructor: Level {
+                identifier: image.identifier,
+                arguments: image.constructor.arguments.to_owned(),
+                parameters: Vec::new(),
+                callback: image.constructor.instructions.clone(),
+                // The parser guarantees that the constructor takes the instance
+                returns: image.constructor.arguments.first().copied().unwrap_or(0),
+                entry: None
+            }
+        })
+    }
+}
+
+/// Prepares a call to a LEVEL or IMAGE (given by its identifier `id`) by POWER.
+/// 
+/// Returns the LEVEL to execute (the constructor, for an IMAGE) along with the
+/// locals of the call, or None if the object is undefined. The locals of the
+/// call only hold its arguments and the LEVEL itself (and the new instance,
+/// for an IMAGE), while the globals are shared with the caller.
+pub fn power_call(
+    id: &usize,
+    locals: &mut Objects,
+    globals: &mut Objects,
+    identifiers: &HashMap<usize, String>
+) -> Result<Option<(Level, Objects)>, BabaError> {
+    // The callee is copied so that the call can borrow the globals,
+    // which it shares with the caller. This is cheap, as its callback is shared
+    let obj = match find_mut_ref(id, locals, globals, identifiers)?.cloned() {
+        Some(obj) => obj,
+        None => return Ok(None)
+    };
+    let mut new_locals = Objects::new();
+    if let Type::Level(level) = &obj.obj_type {
+        if level.arguments.len() == level.parameters.len() {
+            for (arg, param) in level.arguments.iter().zip(level.parameters.iter()) {
+                new_locals.insert(*arg, param.clone());
+            }
+            new_locals.insert(level.identifier, obj.clone());
+            Ok(Some((level.clone(), new_locals)))
+        }
+        else {
+            Err(error(
+                ErrorType::ArgumentError, 
+                argument_error(*id, "LEVEL", &level.arguments, level.parameters.len(), identifiers),
+                Some((&[*id], identifiers))
+            ))
+        }
+    }
+    else if let Type::Image(image) = &obj.obj_type {
+        let (instance, arguments) = match image.constructor.arguments.split_first() {
+            Some((instance, arguments)) => (*instance, arguments),
+            None => {
+                return Err(error(
+                    ErrorType::ArgumentError, 
+                    format!("Constructor of object {} of type IMAGE takes no instance argument", id),
+                    Some((&[*id], identifiers))
+                ));
+            }
+        };
+        if arguments.len() == image.constructor.parameters.len() {
+            for (arg, param) in arguments.iter().zip(image.constructor.parameters.iter()) {
+                new_locals.insert(*arg, param.clone());
+            }
+            new_locals.insert(image.identifier, Object {
+                reference_count: 0, obj_type: Type::Level(image.constructor.clone()
+            )});
+            new_locals.insert(
+                instance, 
+                Object {
+                    reference_count: 0, obj_type: Type::ImageInstance(ImageInstance {
+                        class: image.identifier,
+                        attribute_pointer: image.attribute_pointer,
+                        attributes: image.attributes.clone(),
+                    })
+                }
+            );
+            Ok(Some((image.constructor.clone(), new_locals)))
+        }
+        else {
+            Err(error(
+                ErrorType::ArgumentError, 
+                argument_error(*id, "IMAGE", arguments, image.constructor.parameters.len(), identifiers),
+                Some((&[*id], identifiers))
+            ))
+        }
+    }
+    else {
+        Err(error(
+            ErrorType::TypeError, 
+            format!("Object {} of type {} cannot be POWER", id, obj.obj_type),
+            Some((&[*id], identifiers))
+        ))
+    }
+}
+
+/// Stores the value returned by a call to `X POWER` (if any) in X, 
+/// floating it if the call was `X POWER FLOAT`.
+pub fn store_result(
+    id: usize,
+    float: bool,
+    value: Option<Object>,
+    locals: &mut Objects,
+    globals: &mut Objects
+) {
+    if let Some(obj) = value {
+        if float {
+            if locals.contains_key(&id) {
+                locals.remove(&id);
+            }
+            globals.insert(id, obj);
+        }
+        else if globals.contains_key(&id) {
+            globals.insert(id, obj);
+        }
+        else {
+            locals.insert(id, obj);
+        }
+    }
+}
+
+/// Adds an object to either the locals or the globals.
+/// 
+/// In strict mode, returns an ObjectAlreadyDefinedError when an object 
+/// is redefined with an incompatible type, whether local or FLOATed.
+pub fn initialize(
+    id: usize, 
+    obj: Object,
+    float: bool,
+    locals: &mut Objects, 
+    globals: &mut Objects,
+    identifiers: &HashMap<usize, String>,
+    options: &Options
+) -> Result<(), BabaError> {
+    let mut replaced = Vec::new();
+    let extra_float = if float {
+        replaced.extend(locals.remove(&id));
+        true
+    }
+    else {
+        globals.contains_key(&id)
+    };
+    let mut obj = obj;
+    let defined = if extra_float { &*globals } else { &*locals };
+    if let Some(old) = defined.get(&id) {
+        if options.strict && is_incompatible(old, &obj) {
+            return Err(error(
+                ErrorType::ObjectAlreadyDefinedError, 
+                format!("Object {} of type {} cannot be redefined as {}", id, old.obj_type, obj.obj_type),
+                Some((&[id], identifiers))
+            ));
+        }
+    }
+    if extra_float {
+        replaced.extend(globals.remove(&id));
+    }
+    else {
+        replaced.extend(locals.remove(&id));
+    }
+    // References to the identifier outlive the object it held
+    obj.reference_count = replaced.iter().map(|old| old.reference_count).sum();
+    if extra_float {
+        globals.insert(id, obj);
+    }
+    else {
+        locals.insert(id, obj);
+    }
+    for old in replaced {
+        release(&old.obj_type, locals, globals);
+    }
+    Ok(())
+}
+
+/// Checks whether replacing the `old` object with the `new` object
+/// changes its type. EMPTY objects may be redefined as anything.
+fn is_incompatible(old: &Object, new: &Object) -> bool {
+    if let Type::Empty(_) = old.obj_type {
+        false
+    }
+    else {
+        discriminant(&old.obj_type) != discriminant(&new.obj_type)
+    }
+}
+
+/// Blocks the thread for SLEEP. Browsers can't be blocked, so this does nothing 
+/// in WebAssembly.
+fn sleep(duration: Duration) {
+    #[cfg(not(target_arch = "wasm32"))]
+    std::thread::sleep(duration);
+    #[cfg(target_arch = "wasm32")]
+    let _ = duration;
+}
+
+/// Checks whether a returned scope signifies that WIN or DEFEAT stopped the program.
+pub fn is_halted(scope: usize) -> bool {
+    scope == WIN_SCOPE || scope == DEFEAT_SCOPE
+}
+
+/// Reports that an object without a direction was compared with one using FACING.
+fn facing_direction_error(id: usize, obj: &Object, identifiers: &HashMap<usize, String>) -> BabaError {
+    error(
+        ErrorType::TypeError, 
+        format!("Object {} of type {} has no direction to be FACING, only YOU and YOU2 do", id, obj.obj_type),
+        Some((&[id], identifiers))
+    )
+}
+
+/// Returns the identifier and name of the TELE, LEVEL or IMAGE a scope
+/// belongs to, for error messages. Returns None for the program scope.
+pub fn enclosing_scope(scope: usize, identifiers: &HashMap<usize, String>) -> Option<(usize, &str)> {
+    match identifiers.get(&scope) {
+        Some(name) if scope != PRG_SCOPE => Some((scope, name)),
+        _ => None
+    }
+}
+
+/// Searches for an object in the locals and globals provided. 
+/// If found, returns a reference to the object.
+/// If not found, returns an error in strict mode, or EMPTY otherwise.
+fn find_ref<'a>(
+    id: &usize, 
+    locals: &'a Objects, 
+    globals: &'a Objects,
+    identifiers: &HashMap<usize, String>,
+    options: &Options
+) -> Result<Option<&'a Object>, BabaError> {
+    let target = match follow_references(*id, locals, globals, identifiers, true)? {
+        Some(target) => target,
+        None => return Ok(None)
+    };
+    if let Some(obj) = locals.get(&target).or_else(|| globals.get(&target)) {
+        Ok(Some(obj))
+    }
+    else if options.strict_undef {
+        Err(error(
+            ErrorType::ObjectNotDefinedError, 
+            format!("Object {} is not defined in the local or global scopes", target),
+            Some((&[target], identifiers))
+        ))
+    }
+    else {
+        undefined(&target, identifiers);
+        Ok(Some(&UNDEFINED))
+    }
+}
+
+/// Tries to find the object, but doesn't error.
+fn try_find_ref<'a>(
+    id: &usize, 
+    locals: &'a Objects, 
+    globals: &'a Objects,
+    identifiers: &HashMap<usize, String>
+) -> Option<&'a Object> {
+    let target = follow_references(*id, locals, globals, identifiers, false).ok().flatten()?;
+    locals.get(&target).or_else(|| globals.get(&target))
+}
+/// Searches for an object in the locals and globals provided. 
+/// If found, returns the value of the object, which is only cloned once
+/// the caller takes ownership of it (e.g. to assign it). See `find_ref`.
+fn find_value<'a>(
+    id: &usize, 
+    locals: &'a Objects, 
+    globals: &'a Objects,
+    identifiers: &HashMap<usize, String>,
+    options: &Options
+) -> Result<Option<Cow<'a, Object>>, BabaError> {
+    Ok(find_ref(id, locals, globals, identifiers, options)?.map(Cow::Borrowed))
+}
+
+/// Warns that an undefined object is read as EMPTY. 
+/// Only used when `strict_undef` is off.
+fn undefined(id: &usize, identifiers: &HashMap<usize, String>) {
+    warn(
+        ErrorType::ObjectNotDefinedError, 
+        format!("Object {} is not defined in the local or global scopes, and is read as EMPTY", id),
+        Some((&[*id], identifiers))
+    );
+}
+
+/// Searches for an object in the locals and globals provided. 
+/// If found, returns a mutable reference to the object.
+/// If not found, returns an error. 
+fn find_mut_ref<'a>(
+    id: &usize, 
+    locals: &'a mut Objects, 
+    globals: &'a mut Objects,
+    identifiers: &HashMap<usize, String>
+) -> Result<Option<&'a mut Object>, BabaError> {
+    // Returning a mutable borrow from one branch while still using the scopes in
+    // another is rejected by the borrow checker, so the reference chain is followed
+    // using shared lookups and the final object is borrowed mutably only once.
+    let target = match follow_references(*id, locals, globals, identifiers, true)? {
+        Some(target) => target,
+        None => return Ok(None)
+    };
+    if locals.contains_key(&target) {
+        Ok(locals.get_mut(&target))
+    }
+    else if globals.contains_key(&target) {
+        Ok(globals.get_mut(&target))
+    }
+    else {
+        // Not found
+        Err(error(
+            ErrorType::ObjectNotDefinedError, 
+            format!("Object {} is not defined in the local or global scopes", target),
+            Some((&[target], identifiers))
+        ))
+    }
+}
+
+/// Follows the chain of references starting at `id`, returning the identifier
+/// of the object it ends at (which may not be defined).
+/// 
+/// If `check` is set, weak references to objects that no longer exist return an 
+/// error, and so do references that form a cycle. Otherwise, a cycle returns None.
+fn follow_references(
+    id: usize,
+    locals: &Objects, 
+    globals: &Objects,
+    identifiers: &HashMap<usize, String>,
+    check: bool
+) -> Result<Option<usize>, BabaError> {
+    // Only allocated once a reference is followed, as most objects aren't
+    let mut chain = Vec::new();
+    let mut current = id;
+    while let Some(Type::Reference(reference)) = locals.get(&current)
+        .or_else(|| globals.get(&current))
+        .map(|obj| &obj.obj_type) 
+    {
+        if chain.is_empty() {
+            chain.push(id);
+        }
+        if check {
+            check_dangling(&current, reference, locals, globals, identifiers)?;
+        }
+        if let Some(start) = chain.iter().position(|&seen| seen == reference.pointer) {
+            if check {
+                let cycle = &chain[start..];
+                let names = cycle.iter()
+                    .chain(std::iter::once(&reference.pointer))
+                    .map(usize::to_string)
+                    .collect::<Vec<String>>();
+                return Err(error(
+                    ErrorType::RuntimeError, 
+                    format!("Objects {} form a cycle of references", names.join(" -> ")),
+                    Some((cycle, identifiers))
+                ));
+            }
+            return Ok(None);
+        }
+        chain.push(reference.pointer);
+        current = reference.pointer;
+    }
+    Ok(Some(current))
+}
+
+/// Finds the object held by an identifier, without following references.
+fn slot_mut<'a>(
+    id: usize,
+    locals: &'a mut Objects, 
+    globals: &'a mut Objects
+) -> Option<&'a mut Object> {
+    match locals.get_mut(&id) {
+        Some(obj) => Some(obj),
+        None => globals.get_mut(&id)
+    }
+}
+
+// Reference counts follow these ownership rules:
+// 
+// * The reference count of an object is the number of strong references (MIMIC) 
+// pointing to its identifier. Weak references (MIMIC NOT) aren't counted.
+// 
+// * The count belongs to the identifier rather than its value, so it is kept 
+// when the identifier is redefined or made EMPTY.
+// 
+// * A strong reference is released when the identifier holding it is redefined
+// or made EMPTY.
+// 
+// * Once its last strong reference is released, an identifier that has been 
+// made EMPTY is removed from its scope. Weak references to it then dangle.
+
+/// Counts a new strong reference to the identifier `id`.
+fn incref(id: usize, locals: &mut Objects, globals: &mut Objects) {
+    if let Some(obj) = slot_mut(id, locals, globals) {
+        obj.reference_count += 1;
+    }
+}
+
+/// Releases a strong reference to the identifier `id`,
+/// removing it if it is EMPTY and no longer referenced.
+fn decref(id: usize, locals: &mut Objects, globals: &mut Objects) {
+    let unreachable = match slot_mut(id, locals, globals) {
+        Some(obj) => {
+            obj.reference_count = obj.reference_count.saturating_sub(1);
+            obj.reference_count == 0 && matches!(obj.obj_type, Type::Empty(_))
+        },
+        None => false
+    };
+    if unreachable && locals.remove(&id).is_none() {
+        globals.remove(&id);
+    }
+}
+
+/// Releases the reference held by an object that has been replaced, if it is a strong one.
+fn release(old: &Type, locals: &mut Objects, globals: &mut Objects) {
+    if let Type::Reference(reference) = old {
+        if !reference.weak {
+            decref(reference.pointer, locals, globals);
+        }
+    }
+}
+
+/// Returns an ObjectNotDefinedError if a weak reference points to an object
+/// that no longer exists.
+fn check_dangling(
+    id: &usize,
+    reference: &Reference,
+    locals: &Objects, 
+    globals: &Objects,
+    identifiers: &HashMap<usize, String>
+) -> Result<(), BabaError> {
+    if reference.weak && !locals.contains_key(&reference.pointer) && !globals.contains_key(&reference.pointer) {
+        return Err(error(
+            ErrorType::ObjectNotDefinedError, 
+            format!("Object {} is a weak reference to object {}, which no longer exists", id, reference.pointer),
+            Some((&[*id, reference.pointer], identifiers))
+        ));
+    }
+    Ok(())
+}
+
+/// Describes a POWER call given the wrong number of arguments, naming the 
+/// arguments that are expected and those that are missing or extra.
+/// 
+/// For IMAGE objects, `arguments` excludes the instance argument.
+fn argument_error(
+    id: usize,
+    kind: &str,
+    arguments: &[usize],
+    supplied: usize,
+    identifiers: &HashMap<usize, String>
+) -> String {
+    let names = |ids: &[usize]| ids.iter()
+        .map(|arg| identifiers.get(arg).map_or(arg.to_string(), String::clone))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let detail = if supplied < arguments.len() {
+        format!("missing {}", names(&arguments[supplied..]))
+    }
+    else {
+        format!("{} extra", supplied - arguments.len())
+    };
+    format!(
+        "Expected {} arguments ({}) when calling POWER on object {} of type {}, got {} ({})",
+        arguments.len(),
+        names(arguments),
+        id,
+        kind,
+        supplied,
+        detail
+    )
+}
+
+/// Returns the objects of a scope sorted by identifier.
+/// 
+/// Instructions involving ALL visit objects in this order.
+fn sorted(objects: &Objects) -> Vec<(usize, &Object)> {
+    objects.iter().collect()
+}
+
+/// Appends a line read by WORD to a GROUP, one YOU per byte, and moves the 
+/// index to the first byte read (i.e. past the previously last element).
+/// The index is left as is if nothing was read.
+fn append_line(group: &mut Group, line: &[u8]) {
+    if line.is_empty() {
+        return;
+    }
+    group.index = group.data.len();
+    Arc::make_mut(&mut group.data).extend(line.iter().map(|&x| Object {
+        reference_count: 0,
+        obj_type: Type::You(You {
+            x: x as u32,
+            y: 0,
+            dir: 0,
+            sleeping: false
+        })
+    }));
+}
+
+/// Appends a line read by LINE to a GROUP, as one GROUP of YOUs per word, and
+/// moves the index to the first word read. The index is left as is if nothing
+/// was read, i.e. at the end of input.
+/// 
+/// The line break is dropped, and the rest is split on each `delimiter`. Empty 
+/// words are kept, so that an empty line reads as a single empty GROUP, and a 
+/// trailing delimiter is followed by one.
+fn append_words(group: &mut Group, line: &[u8], delimiter: u8) {
+    if line.is_empty() {
+        return;
+    }
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    group.index = group.data.len();
+    Arc::make_mut(&mut group.data).extend(line.split(|&byte| byte == delimiter).map(|word| {
+        let mut inner = Group { index: 0, data: Arc::new(Vec::new()) };
+        append_line(&mut inner, word);
+        Object { reference_count: 0, obj_type: Type::Group(inner) }
+    }));
+}
+
+/// Writes the BEL byte `count` times, ringing the terminal bell.
+fn play(output: &mut impl Write, count: usize) {
+    output.write_all(&vec![BEL; count]).unwrap();
+    output.flush().unwrap();
+}
+
+/// Reads a single byte of input, or 0 at the end of input.
+/// `output` is flushed first, so that any prompt is shown before blocking.
+fn read_byte(output: &mut impl Write, input: &mut impl Read) -> u8 {
+    output.flush().unwrap();
+    let mut buffer: [u8; 1] = [0];
+    match input.read(&mut buffer).unwrap() {
+        0 => 0,
+        _ => buffer[0]
+    }
+}
+
+/// Reads a line of input, including the line break, or at most `max_line` bytes of it.
+/// The rest of a truncated line is left to be read later.
+/// 
+/// Input is read as raw bytes. Only `\n` ends a line, and a preceding `\r` is kept as is.
+/// `output` is flushed first, so that any prompt is shown before blocking.
+fn read_line(output: &mut impl Write, input: &mut impl BufRead, max_line: Option<usize>) -> Vec<u8> {
+    output.flush().unwrap();
+    let mut buffer = Vec::new();
+    match max_line {
+        Some(max) => input.take(max as u64).read_until(b'\n', &mut buffer),
+        None => input.read_until(b'\n', &mut buffer)
+    }.unwrap();
+    buffer
+}
+
+/// Checks whether any YOU or YOU2 object exists in the scope.
+fn any_you(locals: &Objects, globals: &Objects) -> bool {
+    locals.values().chain(globals.values()).any(|obj| matches!(
+        obj.obj_type, 
+        Type::You(_) | Type::You2(_)
+    ))
+}
+
+/// Checks whether an object is a YOU or YOU2 that has been put to SLEEP.
+fn is_asleep(
+    id: &usize, 
+    locals: &Objects, 
+    globals: &Objects,
+    identifiers: &HashMap<usize, String>
+) -> bool {
+    match try_find_ref(id, locals, globals, identifiers).map(|obj| &obj.obj_type) {
+        Some(Type::You(you)) => you.sleeping,
+        Some(Type::You2(you)) => you.sleeping,
+        _ => false
+    }
+}
+
+/// Checks whether an object of the given type satisfies the LONELY prefix.
+fn is_lonely(obj_type: &Type) -> bool {
+    match obj_type {
+        Type::You(you) => you.x == 0 && you.y == 0,
+        Type::You2(you) => you.x == 0 && you.y == 0,
+        Type::Group(group) => group.data.is_empty(),
+        Type::Empty(_) => true,
+        Type::Level(_) => false,
+        Type::Image(img) => img.attributes.values().all(|attr| attr.is_none()),
+        Type::ImageInstance(img) => img.attributes.values().all(|attr| attr.is_none()),
+        // References are resolved before conditions are checked, so this 
+        // is a reference that couldn't be resolved. Like an undefined 
+        // object, it's treated as EMPTY.
+        Type::Reference(_) => true,
+    }
+}
+
+/// Returns the tag identifying the variant of a type, as used by FEEL.
+/// 
+/// 0 = EMPTY, 1 = YOU, 2 = GROUP, 3 = LEVEL, 4 = IMAGE, 5 = IMAGE instance,
+/// 6 = reference, 7 = YOU2
+fn type_tag(obj_type: &Type) -> u8 {
+    match obj_type {
+        Type::Empty(_) => 0,
+        Type::You(_) => 1,
+        Type::Group(_) => 2,
+        Type::Level(_) => 3,
+        Type::Image(_) => 4,
+        Type::ImageInstance(_) => 5,
+        Type::Reference(_) => 6,
+        Type::You2(_) => 7,
+    }
+}
+
+/// Checks if the two objects are of the same variant.
+fn is_same_type(first: &Object, other: &Object) -> bool {
+    if let Type::Empty(_) = first.obj_type {
+        matches!(other.obj_type, Type::Empty(_))
+    }
+    else if let Type::You(_) = first.obj_type {
+        matches!(other.obj_type, Type::You(_))
+    }
+    else if let Type::You2(_) = first.obj_type {
+        matches!(other.obj_type, Type::You2(_))
+    }
+    else if let Type::Group(_) = first.obj_type {
+        matches!(other.obj_type, Type::Group(_))
     }
-    // println!("LOCALS {:#?}\nGLOBALS {:#?}", locals, globals);
-    (return_scope, return_value)
-}
-
-/// Searches for an object in the locals and globals provided. 
-/// If found, returns a reference to the object.
-/// If not found, throws an error and returns None. 
-fn find_ref<'a>(
-    id: &usize, 
-    locals: &'a HashMap<usize, Object>, 
-    globals: &'a HashMap<usize, Object>,
-    identifiers: &HashMap<usize, String>
-) -> Option<&'a Object> {
-    if let Some(obj) = locals.get(&id) {
-        if let Type::Reference(reference) = obj.obj_type {
-            find_ref(&reference.pointer, locals, globals, identifiers)
-        }
-        else {
-            Some(obj)
-        }
+    else if let Type::Level(_) = first.obj_type {
+        matches!(other.obj_type, Type::Level(_))
+    }
+    else if let Type::Image(_) = first.obj_type {
+        matches!(other.obj_type, Type::Image(_))
     }
-    else if let Some(obj) = globals.get(&id) {
-        if let Type::Reference(reference) = obj.obj_type {
-            find_ref(&reference.pointer, locals, globals, identifiers)
+    else if let Type::ImageInstance(img) = &first.obj_type {
+        if let Type::ImageInstance(other_img) = &other.obj_type {
+            img.class == other_img.class
         }
         else {
-            Some(obj)
+            false
         }
     }
     else {
-        throw_error(
-            ErrorType::ObjectNotDefinedError, 
-            format!("Object {} is not defined in the local or global scopes", id),
-            Some((&[*id], identifiers))
-        );
-        None
+        false
     }
 }
 
-/// Tries to find the object, but doesn't error.
-fn try_find_ref<'a>(
-    id: &usize, 
-    locals: &'a HashMap<usize, Object>, 
-    globals: &'a HashMap<usize, Object>,
-    identifiers: &HashMap<usize, String>
-) -> Option<&'a Object> {
-    if let Some(obj) = locals.get(&id) {
-        if let Type::Reference(reference) = obj.obj_type {
-            try_find_ref(&reference.pointer, locals, globals, identifiers)
-        }
-        else {
-            Some(obj)
-        }
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::{Interpreter, dump_state, argument_error, enclosing_scope, sorted, play, append_line, follow_references, try_find_ref, read_byte, read_line, is_incompatible, is_lonely, any_you, Scope, Objects, Options, PRG_SCOPE};
+    use crate::instruction::{Instruction, Simple};
+    use crate::error_handler::{ErrorType, warning_count};
+    use crate::object::{Object, Type, You, You2, Group, Reference, EMPTY};
+    use crate::compile;
+    use std::collections::HashMap;
+    use std::io::{empty, sink, BufWriter, Cursor, Write};
+    use std::sync::Arc;
+
+    /// Runs a program from source, returning its final scope and identifiers.
+    fn run(source: &str) -> (Scope, HashMap<usize, String>) {
+        run_with(source, &Options::default())
     }
-    else if let Some(obj) = globals.get(&id) {
-        if let Type::Reference(reference) = obj.obj_type {
-            try_find_ref(&reference.pointer, locals, globals, identifiers)
-        }
-        else {
-            Some(obj)
-        }
+
+    /// Runs a program from source with the given options.
+    fn run_with(source: &str, options: &Options) -> (Scope, HashMap<usize, String>) {
+        let (instructions, identifiers) = compile(source.as_bytes()).unwrap();
+        (Interpreter::new(empty(), sink()).exec(&instructions, &identifiers, options).unwrap(), identifiers)
     }
-    else {
-        None
+
+    /// Finds the ID associated with an identifier name.
+    fn id(name: &str, identifiers: &HashMap<usize, String>) -> usize {
+        *identifiers.iter().find(|(_, v)| v.as_str() == name).unwrap().0
     }
-}
-/// Searches for an object in the locals and globals provided. 
-/// If found, returns the cloned value of the object.
-/// If not found, throws an error and returns None. 
-/// 
-/// 
-fn find_value<'a>(
-    id: &usize, 
-    locals: &'a HashMap<usize, Object>, 
-    globals: &'a HashMap<usize, Object>,
-    identifiers: &HashMap<usize, String>
-) -> Option<Object> {
-    if let Some(obj) = locals.get(&id) {
-        if let Type::Reference(reference) = obj.obj_type {
-            find_value(&reference.pointer, locals, globals, identifiers)
+
+    #[test]
+    fn exec_returns_locals() {
+        let (scope, ids) = run("baba is you and move and move keke is group keke has baba");
+        if let Type::You(you) = scope.locals[&id("baba", &ids)].obj_type {
+            assert_eq!((you.x, you.y), (2, 0));
         }
         else {
-            Some(obj.clone())
+            panic!("baba should be YOU");
         }
-    }
-    else if let Some(obj) = globals.get(&id) {
-        if let Type::Reference(reference) = obj.obj_type {
-            find_value(&reference.pointer, locals, globals, identifiers)
+        if let Type::Group(group) = &scope.locals[&id("keke", &ids)].obj_type {
+            assert_eq!(group.data.len(), 1);
         }
         else {
-            Some(obj.clone())
+            panic!("keke should be GROUP");
         }
     }
-    else {
-        throw_error(
-            ErrorType::ObjectNotDefinedError, 
-            format!("Object {} is not defined in the local or global scopes", id),
-            Some((&[*id], identifiers))
+
+    #[test]
+    fn exec_returns_globals() {
+        let (scope, ids) = run("baba is float baba is you and move");
+        assert!(scope.globals.contains_key(&id("baba", &ids)));
+        assert!(!scope.locals.contains_key(&id("baba", &ids)));
+    }
+
+    #[test]
+    fn strict_same_type_reinit() {
+        let strict = Options { strict: true, ..Options::default() };
+        let (scope, ids) = run_with("baba is you and move baba is you keke is group keke is group", &strict);
+        assert!(matches!(scope.locals[&id("baba", &ids)].obj_type, Type::You(You { x: 0, .. })));
+        assert!(matches!(scope.locals[&id("keke", &ids)].obj_type, Type::Group(_)));
+    }
+
+    #[test]
+    fn strict_cross_type_reinit() {
+        let you = Object {
+            reference_count: 0,
+            obj_type: Type::You(You { x: 0, y: 0, dir: 0, sleeping: false })
+        };
+        let group = Object {
+            reference_count: 0,
+            obj_type: Type::Group(Group { index: 0, data: Arc::new(Vec::new()) })
+        };
+        assert!(!is_incompatible(&you, &you));
+        assert!(is_incompatible(&you, &group));
+        assert!(is_incompatible(&group, &you));
+        // EMPTY objects can always be redefined
+        assert!(!is_incompatible(&EMPTY, &group));
+    }
+
+    #[test]
+    fn lenient_cross_type_reinit() {
+        let (scope, ids) = run("baba is you baba is group");
+        assert!(matches!(scope.locals[&id("baba", &ids)].obj_type, Type::Group(_)));
+    }
+
+    #[test]
+    fn strict_redefinition() {
+        let source = "baba is you baba is move baba is group keke is you baba has keke";
+        let strict = Options { strict: true, ..Options::default() };
+        let (instructions, identifiers) = compile(source.as_bytes()).unwrap();
+        let err = Interpreter::new(empty(), sink()).exec(&instructions, &identifiers, &strict).unwrap_err();
+        assert_eq!(err.kind, ErrorType::ObjectAlreadyDefinedError);
+        // FLOATed objects are checked just the same
+        let (instructions, identifiers) = compile(b"baba is float baba is you baba is move baba is group").unwrap();
+        let err = Interpreter::new(empty(), sink()).exec(&instructions, &identifiers, &strict).unwrap_err();
+        assert_eq!(err.kind, ErrorType::ObjectAlreadyDefinedError);
+        // Without strict mode, the object is silently replaced
+        let (scope, ids) = run(source);
+        assert!(matches!(&scope.locals[&id("baba", &ids)].obj_type, Type::Group(group) if group.data.len() == 1));
+        assert_eq!(warning_count(), 0);
+    }
+
+    #[test]
+    fn mimic_strong_reference() {
+        let (scope, ids) = run("baba is you keke mimic baba keke is move");
+        assert_eq!(scope.locals[&id("baba", &ids)].reference_count, 1);
+        assert!(matches!(scope.locals[&id("baba", &ids)].obj_type, Type::You(You { x: 1, .. })));
+        assert!(matches!(
+            scope.locals[&id("keke", &ids)].obj_type, 
+            Type::Reference(Reference { weak: false, .. })
+        ));
+    }
+
+    #[test]
+    fn release_references() {
+        // Still held by baba
+        let (scope, ids) = run("baba is you keke mimic baba me mimic baba keke is empty me is you");
+        assert_eq!(scope.locals[&id("baba", &ids)].reference_count, 0);
+        // Kept alive by keke once emptied, until keke is redefined
+        let (scope, ids) = run("baba is you keke mimic baba me mimic not baba baba is empty");
+        assert_eq!(scope.locals[&id("baba", &ids)].reference_count, 1);
+        let (scope, ids) = run("baba is you keke mimic baba me mimic not baba baba is empty keke is empty");
+        assert!(!scope.locals.contains_key(&id("baba", &ids)));
+        // Redefining an identifier keeps its references
+        let (scope, ids) = run("baba is you keke mimic baba baba is group");
+        assert_eq!(scope.locals[&id("baba", &ids)].reference_count, 1);
+    }
+
+    #[test]
+    fn mimic_weak_reference() {
+        let (scope, ids) = run("baba is you keke mimic not baba keke is move");
+        assert_eq!(scope.locals[&id("baba", &ids)].reference_count, 0);
+        assert!(matches!(scope.locals[&id("baba", &ids)].obj_type, Type::You(You { x: 1, .. })));
+        assert!(matches!(
+            scope.locals[&id("keke", &ids)].obj_type, 
+            Type::Reference(Reference { weak: true, .. })
+        ));
+    }
+
+    #[test]
+    fn mimic_weak_reference_dangles() {
+        // baba is removed once it is EMPTY and a no longer refers to it
+        let (instructions, identifiers) = compile(
+            b"baba is you a mimic baba keke mimic not baba baba is empty a is you keke is move"
+        ).unwrap();
+        let err = Interpreter::new(empty(), sink()).exec(&instructions, &identifiers, &Options::default()).unwrap_err();
+        assert_eq!(err.kind, ErrorType::ObjectNotDefinedError);
+        // While a still refers to it, keke reaches the EMPTY baba instead
+        let (instructions, identifiers) = compile(
+            b"baba is you a mimic baba keke mimic not baba baba is empty keke is move"
+        ).unwrap();
+        let err = Interpreter::new(empty(), sink()).exec(&instructions, &identifiers, &Options::default()).unwrap_err();
+        assert_eq!(err.kind, ErrorType::TypeError);
+    }
+
+    #[test]
+    fn group_copies_share_data() {
+        let data = |scope: &Scope, name: &str, ids: &HashMap<usize, String>| match &scope.locals[&id(name, ids)].obj_type {
+            Type::Group(group) => group.data.clone(),
+            _ => panic!("{} should be GROUP", name)
+        };
+        // Copied by IS without cloning the elements
+        let (scope, ids) = run("baba is you baba is move g is group g has baba g has baba h is g");
+        assert!(Arc::ptr_eq(&data(&scope, "g", &ids), &data(&scope, "h", &ids)));
+        // Until either copy changes
+        let (scope, ids) = run("baba is you g is group g has baba h is g h has baba");
+        assert_eq!((data(&scope, "g", &ids).len(), data(&scope, "h", &ids).len()), (1, 2));
+        // While MIMIC aliases the GROUP itself
+        let (scope, ids) = run("baba is you g is group h mimic g h has baba");
+        assert_eq!(data(&scope, "g", &ids).len(), 1);
+    }
+
+    #[test]
+    fn mimic_chained_reference() {
+        let (scope, ids) = run("baba is you keke mimic baba jiji mimic keke jiji is move jiji is move");
+        assert!(matches!(scope.locals[&id("baba", &ids)].obj_type, Type::You(You { x: 2, .. })));
+        assert!(mimicked(&scope, "keke", &ids));
+        assert!(mimicked(&scope, "jiji", &ids));
+    }
+
+    #[test]
+    fn feel_type_tags() {
+        let (scope, ids) = run("
+            baba is you keke is you
+            me is group it is you2
+            a is you a feel empty
+            b is you b feel baba
+            c is you c feel me
+            d is you d feel it
+            e is you e feel keke e feel e
+        ");
+        let tag = |name| match scope.locals[&id(name, &ids)].obj_type {
+            Type::You(you) => you.x,
+            _ => panic!("{} is not YOU", name)
+        };
+        assert_eq!(tag("a"), 0);
+        assert_eq!(tag("b"), 1);
+        assert_eq!(tag("c"), 2);
+        assert_eq!(tag("d"), 7);
+        assert_eq!(tag("e"), 1);
+    }
+
+    #[test]
+    fn equal_values() {
+        let (scope, ids) = run("
+            baba is you keke is you me is group
+            a is you a equal baba
+            b is you b is move b equal keke
+            c is you c equal me
+            d is you d is turn e is you e is turn d equal e
+        ");
+        let value = |name| match scope.locals[&id(name, &ids)].obj_type {
+            Type::You(you) => you.x,
+            _ => panic!("{} is not YOU", name)
+        };
+        assert_eq!(value("a"), 255);
+        assert_eq!(value("b"), 0);
+        assert_eq!(value("c"), 0);
+        // The result is written to the axis the subject is facing
+        assert!(matches!(scope.locals[&id("d", &ids)].obj_type, Type::You(You { x: 0, y: 255, .. })));
+        // and is the largest value of its width
+        let options = Options { width: Some(16), ..Options::default() };
+        let (scope, ids) = run_with("baba is you a is you a equal baba", &options);
+        assert!(matches!(scope.locals[&id("a", &ids)].obj_type, Type::You(You { x: 65535, .. })));
+        let (scope, ids) = run("baba is you2 a is you2 a equal baba");
+        assert!(matches!(scope.locals[&id("a", &ids)].obj_type, Type::You2(You2 { x: 65535, .. })));
+    }
+
+    #[test]
+    fn write_active_axis() {
+        let (scope, ids) = run("
+            baba is you baba is move and move and move
+            keke is you keke is move keke write baba
+            me is you me is turn me is move me write baba
+            tall is you tall is turn tall is move and move
+            flat is you flat write tall
+        ");
+        assert!(matches!(scope.locals[&id("keke", &ids)].obj_type, Type::You(You { x: 3, y: 0, .. })));
+        assert!(matches!(scope.locals[&id("me", &ids)].obj_type, Type::You(You { x: 0, y: 3, .. })));
+        // The value written is read from the axis the target is facing
+        assert!(matches!(scope.locals[&id("flat", &ids)].obj_type, Type::You(You { x: 2, y: 0, .. })));
+    }
+
+    #[test]
+    fn less_rounds_half_up() {
+        let (scope, ids) = run("
+            odd is you odd is move and move and move and move and move
+            floor is you floor is odd floor is not more
+            round is you round is odd round is less
+            even is you even is move and move and move and move
+            half is you half is even half is less
+            double is you double is odd double is not less
+            top is you top is not fall top is less
+        ");
+        let value = |name| match scope.locals[&id(name, &ids)].obj_type {
+            Type::You(you) => you.x,
+            _ => panic!("{} is not YOU", name)
+        };
+        assert_eq!(value("floor"), 2);
+        assert_eq!(value("round"), 3);
+        assert_eq!(value("half"), 2);
+        assert_eq!(value("double"), 10);
+        assert_eq!(value("top"), 128);
+    }
+
+    /// Checks whether an object became a reference, i.e. whether the
+    /// conditional MIMIC instruction targeting it was executed.
+    fn mimicked(scope: &Scope, name: &str, ids: &HashMap<usize, String>) -> bool {
+        matches!(
+            scope.locals.get(&id(name, ids)), 
+            Some(Object { reference_count: _, obj_type: Type::Reference(_) })
+        )
+    }
+
+    #[test]
+    fn conditions_empty() {
+        let (scope, ids) = run("
+            flag is you
+            a is empty a near empty mimic flag
+            b is empty b on empty mimic flag
+            c is empty lonely c mimic flag
+            d is empty other is empty d near other mimic flag
+            e is you e near empty mimic flag
+            f is you f on empty mimic flag
+        ");
+        assert!(mimicked(&scope, "a", &ids));
+        assert!(mimicked(&scope, "b", &ids));
+        assert!(mimicked(&scope, "c", &ids));
+        assert!(mimicked(&scope, "d", &ids));
+        assert!(!mimicked(&scope, "e", &ids));
+        assert!(!mimicked(&scope, "f", &ids));
+    }
+
+    #[test]
+    fn conditions_undefined() {
+        let (scope, ids) = run("
+            flag is you
+            a near empty mimic flag
+            b on empty mimic flag
+            lonely c mimic flag
+            d near ghost mimic flag
+            e is empty e on ghost mimic flag
+            f is you f near ghost mimic flag
+            g not near empty mimic flag
+        ");
+        assert!(mimicked(&scope, "a", &ids));
+        assert!(mimicked(&scope, "b", &ids));
+        assert!(mimicked(&scope, "c", &ids));
+        assert!(mimicked(&scope, "d", &ids));
+        assert!(mimicked(&scope, "e", &ids));
+        assert!(!mimicked(&scope, "f", &ids));
+        assert!(!mimicked(&scope, "g", &ids));
+    }
+
+    #[test]
+    fn lonely_types() {
+        assert!(is_lonely(&EMPTY.obj_type));
+        assert!(is_lonely(&Type::You(You { x: 0, y: 0, dir: 1, sleeping: false })));
+        assert!(!is_lonely(&Type::You(You { x: 0, y: 1, dir: 0, sleeping: false })));
+        assert!(is_lonely(&Type::Group(Group { index: 0, data: Arc::new(Vec::new()) })));
+        assert!(!is_lonely(&Type::Group(Group { index: 0, data: Arc::new(vec![EMPTY]) })));
+        assert!(is_lonely(&Type::Reference(Reference { pointer: 3, weak: true })));
+    }
+
+    #[test]
+    fn spell_digits() {
+        let (scope, ids) = run("
+            n is you n is move and more and more and more and move and more and more and move
+            digits is group digits spell n
+            zero is you none is group none spell zero
+        ");
+        let digits = |name| match &scope.locals[&id(name, &ids)].obj_type {
+            Type::Group(group) => group.data.iter().map(|obj| match obj.obj_type {
+                Type::You(you) => you.x as u8,
+                _ => panic!("{} contains a non-YOU object", name)
+            }).collect::<Vec<u8>>(),
+            _ => panic!("{} is not GROUP", name)
+        };
+        assert_eq!(digits("digits"), b"37".to_vec());
+        assert_eq!(digits("none"), b"0".to_vec());
+    }
+
+    #[test]
+    fn above_group_length() {
+        let (scope, ids) = run(r#"
+            s is "ab" a is "abc" b is "a" c is "a" d is "abc" e is "x"
+            a above s is sink
+            b above s is sink
+            c not above s is sink
+            d above s and empty is sink
+            e above empty is sink
+            s above d is sink
+        "#);
+        let len = |name| match &scope.locals[&id(name, &ids)].obj_type {
+            Type::Group(group) => group.data.len(),
+            _ => panic!("{} is not GROUP", name)
+        };
+        assert_eq!(
+            ["a", "b", "c", "d", "e", "s"].map(len),
+            [2, 1, 0, 2, 0, 2]
         );
-        None
     }
-}
 
-/// Searches for an object in the locals and globals provided. 
-/// If found, returns a mutable reference to the object.
-/// If not found, throws an error and returns None. 
-fn find_mut_ref<'a>(
-    id: &usize, 
-    locals: &'a mut HashMap<usize, Object>, 
-    globals: &'a mut HashMap<usize, Object>,
-    identifiers: &HashMap<usize, String>
-) -> Option<&'a mut Object> {
-    // This is rearranged to avoid borrowing locals/globals as mutable twice.
-    // Instead of doing that, we check for a Reference, and overwrite the 
-    // current object ID to the ID being pointed to and call this function again appropriately.
-    let (referenced, glob, ref_id) = if let Some(obj) = locals.get_mut(id) {
-        if let Type::Reference(reference) = obj.obj_type {
-            (true, false, reference.pointer)
+    #[test]
+    fn reset_in_place() {
+        let (scope, ids) = run("
+            a is you and move and up and move and turn and reset
+            b is you2 and down and move and reset
+            r mimic a
+            r is reset and move
+        ");
+        let you = |name| match scope.locals[&id(name, &ids)].obj_type {
+            Type::You(you) => (you.x as u16, you.y as u16, you.dir),
+            Type::You2(you) => (you.x, you.y, you.dir),
+            _ => panic!("{} is not YOU or YOU2", name)
+        };
+        assert_eq!(you("a"), (1, 0, 0));
+        assert_eq!(you("b"), (0, 0, 0));
+        assert!(matches!(scope.locals[&id("r", &ids)].obj_type, Type::Reference(_)));
+    }
+
+    #[test]
+    fn both_axes() {
+        let (scope, ids) = run("
+            a is you and move and move and both
+            b is you and move and move and move and up and not both
+            c is you2 and up and move and both
+            d is you2 and up and move and not both
+        ");
+        let axes = |name| match scope.locals[&id(name, &ids)].obj_type {
+            Type::You(you) => (you.x as u16, you.y as u16),
+            Type::You2(you) => (you.x, you.y),
+            _ => panic!("{} is not YOU or YOU2", name)
+        };
+        assert_eq!(["a", "b", "c", "d"].map(axes), [(2, 2), (3, 3), (1, 1), (0, 0)]);
+    }
+
+    #[test]
+    fn powered_prefix() {
+        let (scope, ids) = run(r#"
+            lit is you lit is move dark is you
+            full is "ab" none is group
+            powered lit is move
+            powered dark is move
+            not powered dark is move
+            powered full is sink
+            powered none is sink
+        "#);
+        let x = |name| match scope.locals[&id(name, &ids)].obj_type {
+            Type::You(you) => you.x,
+            _ => panic!("{} is not YOU", name)
+        };
+        let len = |name| match &scope.locals[&id(name, &ids)].obj_type {
+            Type::Group(group) => group.data.len(),
+            _ => panic!("{} is not GROUP", name)
+        };
+        assert_eq!((x("lit"), x("dark")), (2, 1));
+        assert_eq!((len("full"), len("none")), (1, 0));
+    }
+
+    #[test]
+    fn prefix_or_condition() {
+        let (scope, ids) = run("
+            baba is you keke is you
+            lonely baba or on keke is move
+            lonely baba or on keke is move
+            not lonely baba or not on keke is move
+            lonely keke on baba is move
+            lonely keke or on baba is move
+        ");
+        let x = |name| match scope.locals[&id(name, &ids)].obj_type {
+            Type::You(you) => you.x,
+            _ => panic!("{} is not YOU", name)
+        };
+        assert_eq!(x("baba"), 2);
+        assert_eq!(x("keke"), 1);
+    }
+
+    #[test]
+    fn chained_conditionals() {
+        let (scope, ids) = run("
+            baba is you keke is you me is you and move
+            baba on keke and facing me is move
+            baba on keke and not facing me is move
+            keke on baba and not on me is move
+            keke on baba and near me and not on me is move
+            lonely me or on baba and on keke is move
+        ");
+        let x = |name| match scope.locals[&id(name, &ids)].obj_type {
+            Type::You(you) => you.x,
+            _ => panic!("{} is not YOU", name)
+        };
+        // Only the first and fourth conditions hold
+        assert_eq!(x("baba"), 1);
+        assert_eq!(x("keke"), 0);
+        assert_eq!(x("me"), 1);
+    }
+
+    #[test]
+    fn on_groups_and_images() {
+        let (scope, ids) = run(r#"
+            d is you a is "AB" b is "AB" c is "BA" e is "AB"
+            a on b has d
+            c on b has d
+            c not on b has d
+            e not on b has d
+            b on a has d
+            point is image point has px point is level point has self px is you self has px point is done point is done
+            seg is image seg has lx seg is level seg has self lx is you self has lx seg is done seg is done
+            p is point p is power q is point q is power r is seg r is power
+            m is you m is move p follow px p eat m
+            p on q is empty
+            q on r is empty
+        "#);
+        let len = |name| match &scope.locals[&id(name, &ids)].obj_type {
+            Type::Group(group) => group.data.len(),
+            _ => panic!("{} is not a GROUP", name)
+        };
+        // Equal GROUPs are on each other, unlike GROUPs holding the same elements in another order
+        assert_eq!((len("a"), len("b"), len("c"), len("e")), (3, 2, 3, 2));
+        // Instances of the same IMAGE are on each other, even with different attributes
+        assert!(matches!(scope.locals[&id("p", &ids)].obj_type, Type::Empty(_)));
+        assert!(matches!(scope.locals[&id("q", &ids)].obj_type, Type::ImageInstance(_)));
+    }
+
+    #[test]
+    fn without_all_types() {
+        let (scope, ids) = run("
+            d is you e is you e is move
+            g is group g has d
+            g without e has e
+            g without d has d
+            point is image point has px point is level point has self px is you self has px point is done point is done
+            p is point p is power q is point q is power
+            p follow px p eat e q follow px q eat d
+            p without d is empty
+            q without d is empty
+            a is 3 b is 3 c is 4
+            a without b is move
+            c without b is move
+            c not without b is move
+            b not without a is move
+        ");
+        let obj = |name| &scope.locals[&id(name, &ids)].obj_type;
+        let x = |name| match obj(name) {
+            Type::You(you) => you.x,
+            _ => panic!("{} is not YOU", name)
+        };
+        assert!(matches!(obj("g"), Type::Group(group) if group.data.len() == 2));
+        // Only the attribute of q holds d
+        assert!(matches!(obj("p"), Type::Empty(_)));
+        assert!(matches!(obj("q"), Type::ImageInstance(_)));
+        assert_eq!((x("a"), x("b"), x("c")), (3, 4, 5));
+        // YOU can't be compared with a GROUP
+        let (instructions, identifiers) = compile(b"d is you g is group d without g is move").unwrap();
+        let err = Interpreter::new(empty(), sink()).exec(&instructions, &identifiers, &Options::default()).unwrap_err();
+        assert_eq!(err.kind, ErrorType::TypeError);
+    }
+
+    #[test]
+    fn over_conditional() {
+        let (scope, ids) = run("
+            x is 5 y is 5 z is 7
+            z over y is move
+            x over y is move
+            y over z is move
+            y not over z is move
+            x not over y is move
+            w is 9 w is turn
+            w over x is move
+        ");
+        let x = |name| match scope.locals[&id(name, &ids)].obj_type {
+            Type::You(you) => you.x,
+            _ => panic!("{} is not YOU", name)
+        };
+        // Equal and lesser values aren't over
+        assert_eq!((x("x"), x("y"), x("z")), (6, 6, 8));
+        // Facing up, w compares its y coordinate, and so isn't moved
+        assert!(matches!(scope.locals[&id("w", &ids)].obj_type, Type::You(You { x: 9, y: 0, .. })));
+        for source in ["d is you g is group d over g is move", "d is you g is group g over d has d"] {
+            let (instructions, identifiers) = compile(source.as_bytes()).unwrap();
+            let err = Interpreter::new(empty(), sink()).exec(&instructions, &identifiers, &Options::default()).unwrap_err();
+            assert_eq!(err.kind, ErrorType::TypeError);
         }
-        else {
-            (false, false, *id)
+    }
+
+    #[test]
+    fn negated_targets() {
+        let (scope, ids) = run("
+            baba is you keke is you g is group
+            baba near keke and not g is move
+            baba near keke and not keke is move
+            baba near g and not keke is move
+            baba not near g and not keke is move
+            baba near keke and not not keke is move
+            baba facing right and not up is move
+        ");
+        match scope.locals[&id("baba", &ids)].obj_type {
+            // All but the second and third conditions hold
+            Type::You(you) => assert_eq!(you.x, 4),
+            _ => panic!("baba is not YOU")
         }
     }
-    else if let Some(obj) = globals.get_mut(id) {
-        if let Type::Reference(reference) = obj.obj_type {
-            (true, true, reference.pointer)
+
+    #[test]
+    fn literal_group() {
+        let (scope, ids) = run(r#"
+            x is "AB\n" z is "" y is you y is "\x00\t\\\""
+        "#);
+        let bytes = |name| match &scope.locals[&id(name, &ids)].obj_type {
+            Type::Group(group) => group.data.iter().map(|obj| match obj.obj_type {
+                Type::You(you) => you.x as u8,
+                _ => panic!("{} contains a non-YOU object", name)
+            }).collect::<Vec<u8>>(),
+            _ => panic!("{} is not GROUP", name)
+        };
+        assert_eq!(bytes("x"), vec![65, 66, 10]);
+        assert_eq!(bytes("y"), vec![0, 9, 92, 34]);
+        assert_eq!(bytes("z"), vec![]);
+    }
+
+    #[test]
+    fn level_alias() {
+        let (scope, ids) = run("
+            f is level f has x f make x f is done
+            v is you v is move
+            g is f g has v g is power
+            f has v h is f
+        ");
+        // Calling the alias returns the argument
+        assert!(matches!(scope.locals[&id("g", &ids)].obj_type, Type::You(You { x: 1, .. })));
+        // Pending arguments are not copied
+        match (&scope.locals[&id("f", &ids)].obj_type, &scope.locals[&id("h", &ids)].obj_type) {
+            (Type::Level(f), Type::Level(h)) => {
+                assert_eq!(f.parameters.len(), 1);
+                assert_eq!(h.parameters.len(), 0);
+                assert_eq!(h.arguments, f.arguments);
+            },
+            _ => panic!("f and h are not LEVEL")
         }
-        else {
-            (false, true, *id)
+    }
+
+    #[test]
+    fn shared_callbacks() {
+        let (instructions, identifiers) = compile(b"f is level f has x f make x f is done g is f").unwrap();
+        let scope = Interpreter::new(empty(), sink()).exec(&instructions, &identifiers, &Options::default()).unwrap();
+        let body = match &instructions[0] {
+            Instruction::Level(level) => &level.instructions,
+            _ => panic!("f is not defined first")
+        };
+        // Neither defining nor copying the LEVEL copies its body
+        for name in ["f", "g"] {
+            match &scope.locals[&id(name, &identifiers)].obj_type {
+                Type::Level(level) => assert!(Arc::ptr_eq(&level.callback, body)),
+                _ => panic!("{} is not LEVEL", name)
+            }
         }
     }
-    else {
-        // Not found
-        throw_error(
-            ErrorType::ObjectNotDefinedError, 
-            format!("Object {} is not defined in the local or global scopes", id),
-            Some((&[*id], identifiers))
+
+    #[test]
+    fn dump_sorted() {
+        let (scope, ids) = run("keke is you keke is move baba is you baba is float baba is group me is empty");
+        assert_eq!(dump_state(&scope, &ids), "3\tkeke\tYOU (1, 0) facing right\n4\tbaba\tGROUP []\n5\tme\tEMPTY");
+    }
+
+    #[test]
+    fn read_line_limit() {
+        let mut input = Cursor::new(b"hello\nbaba\xff\n".to_vec());
+        assert_eq!(read_line(&mut sink(), &mut input, Some(3)), b"hel".to_vec());
+        assert_eq!(read_line(&mut sink(), &mut input, Some(10)), b"lo\n".to_vec());
+        assert_eq!(read_line(&mut sink(), &mut input, None), b"baba\xff\n".to_vec());
+        assert_eq!(read_line(&mut sink(), &mut input, None), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn read_raw_bytes() {
+        let mut input = Cursor::new(b"ab\r\ncd\r\n\r".to_vec());
+        assert_eq!(read_line(&mut sink(), &mut input, None), b"ab\r\n".to_vec());
+        assert_eq!(read_byte(&mut sink(), &mut input), b'c');
+        assert_eq!(read_line(&mut sink(), &mut input, Some(2)), b"d\r".to_vec());
+        assert_eq!(read_byte(&mut sink(), &mut input), b'\n');
+        assert_eq!(read_byte(&mut sink(), &mut input), b'\r');
+        assert_eq!(read_byte(&mut sink(), &mut input), 0);
+    }
+
+    #[test]
+    fn argument_names() {
+        let ids = vec![(5, String::from("x")), (6, String::from("y"))].into_iter().collect::<HashMap<usize, String>>();
+        assert_eq!(
+            argument_error(4, "LEVEL", &[5, 6], 1, &ids),
+            "Expected 2 arguments (x, y) when calling POWER on object 4 of type LEVEL, got 1 (missing y)"
+        );
+        assert_eq!(
+            argument_error(4, "IMAGE", &[6], 3, &ids),
+            "Expected 1 arguments (y) when calling POWER on object 4 of type IMAGE, got 3 (2 extra)"
         );
-        return None;
-    };
-    // Evaluate references
-    if referenced {
-        find_mut_ref(&ref_id, locals, globals, identifiers)
     }
-    else {
-        // Get the object normally
-        if glob {
-            globals.get_mut(&ref_id)
+
+    #[test]
+    fn undefined_read_as_empty() {
+        let (scope, ids) = run("baba is keke me is group me has keke");
+        assert_eq!(scope.locals[&id("baba", &ids)], EMPTY);
+        match &scope.locals[&id("me", &ids)].obj_type {
+            Type::Group(group) => assert_eq!(*group.data, vec![EMPTY]),
+            _ => panic!("me is not GROUP")
         }
-        else {
-            locals.get_mut(&ref_id)
+        // Undefined objects are only read as EMPTY, not defined
+        assert!(!scope.locals.contains_key(&id("keke", &ids)));
+    }
+
+    #[test]
+    fn facing_all_skips_unordered() {
+        // EMPTY, LEVEL and the GROUP can't be compared with a YOU, and are skipped
+        let (scope, ids) = run("baba is you keke is you keke is move me is group keke not facing all is more");
+        assert!(matches!(scope.locals[&id("keke", &ids)].obj_type, Type::You(You { x: 2, .. })));
+    }
+
+    #[test]
+    fn all_chill() {
+        let program = "baba is you baba is move keke is you keke is up keke is move all is ";
+        let (scope, ids) = run(&format!("{}not chill", program));
+        assert!(matches!(scope.locals[&id("baba", &ids)].obj_type, Type::You(You { x: 1, y: 0, .. })));
+        assert!(matches!(scope.locals[&id("keke", &ids)].obj_type, Type::You(You { x: 0, y: 1, .. })));
+        // Only the axis each object faces is randomized
+        let (scope, ids) = run(&format!("{}chill", program));
+        assert!(matches!(scope.locals[&id("baba", &ids)].obj_type, Type::You(You { y: 0, dir: 0, .. })));
+        assert!(matches!(scope.locals[&id("keke", &ids)].obj_type, Type::You(You { x: 0, dir: 1, .. })));
+    }
+
+    #[test]
+    fn sleeping_objects() {
+        let (scope, ids) = run(
+            "baba is you baba is sleep baba is move baba is up baba is turn all is move \
+            keke is you keke is sleep keke is reset keke is move me is you2 me is sleep me is fall \
+            bab is you bab is move bab is move bab is move bab is more bab is sleep bab is less bab is both bab is chill all is less"
+        );
+        assert!(matches!(
+            scope.locals[&id("baba", &ids)].obj_type, 
+            Type::You(You { x: 0, y: 0, dir: 0, sleeping: true })
+        ));
+        assert!(matches!(scope.locals[&id("keke", &ids)].obj_type, Type::You(You { x: 1, sleeping: false, .. })));
+        assert!(matches!(scope.locals[&id("me", &ids)].obj_type, Type::You2(You2 { y: 0, sleeping: true, .. })));
+        assert!(matches!(
+            scope.locals[&id("bab", &ids)].obj_type, 
+            Type::You(You { x: 6, y: 0, dir: 0, sleeping: true })
+        ));
+    }
+
+    #[test]
+    fn has_literal_bytes() {
+        let (scope, ids) = run("g is group g has \"\\x05\\x06\" f is level f has x and y f make y f is done f has \"\\x07\\x08\" f is power");
+        match &scope.locals[&id("g", &ids)].obj_type {
+            Type::Group(group) => assert_eq!(*group.data, vec![
+                Object { reference_count: 0, obj_type: Type::You(You { x: 5, y: 0, dir: 0, sleeping: false }) },
+                Object { reference_count: 0, obj_type: Type::You(You { x: 6, y: 0, dir: 0, sleeping: false }) }
+            ]),
+            _ => panic!("g is not GROUP")
         }
+        // Each byte is a separate argument
+        assert!(matches!(scope.locals[&id("f", &ids)].obj_type, Type::You(You { x: 8, .. })));
     }
-}
 
-/// Executes a simple YOU instruction for every YOU object in the current scope.
-fn exec_all(
-    simple_factory: &dyn Fn(usize, bool) -> Simple,
-    not: bool,
-    locals: &mut HashMap<usize, Object>,
-    globals: &mut HashMap<usize, Object>,
-    identifiers: &HashMap<usize, String>
-) {
-    // Get all YOU keys in the current scope
-    let all_loc: Vec<usize> = locals.iter()
-        .filter(|(_, v)| matches!(
-            v, Object { reference_count: _, obj_type: Type::You(_)}
-        ) || matches!(
-            v, Object { reference_count: _, obj_type: Type::You2(_)}
-        ))
-        .map(|(&k, _)| k)
-        .collect();
-    let all_glob: Vec<usize> = globals.iter()
-        .filter(|(_, v)| matches!(
-            v, Object { reference_count: _, obj_type: Type::You(_)}
-        ) || matches!(
-            v, Object { reference_count: _, obj_type: Type::You2(_)}
-        ))
-        .map(|(&k, _)| k)
-        .collect();
-    for id in all_loc {
-        exec_simple(&simple_factory(id, not), locals, globals, identifiers);
+    #[test]
+    fn scope_names() {
+        let ids = vec![(0, String::from("empty")), (5, String::from("loop"))].into_iter().collect::<HashMap<usize, String>>();
+        assert_eq!(enclosing_scope(PRG_SCOPE, &ids), None);
+        assert_eq!(enclosing_scope(5, &ids), Some((5, "loop")));
     }
-    for id in all_glob {
-        exec_simple(&simple_factory(id, not), locals, globals, identifiers);
+
+    #[test]
+    fn sorted_by_id() {
+        let objects = (0..100).rev().map(|id| (id, EMPTY)).collect::<Objects>();
+        let ids = sorted(&objects).into_iter().map(|(id, _)| id).collect::<Vec<usize>>();
+        assert_eq!(ids, (0..100).collect::<Vec<usize>>());
     }
-}
 
-/// Prints an object's data (`X IS TEXT`) to stdout.
-/// Throws an error if the object type doesn't support TEXT.
-/// 
-/// For YOU objects, prints the 8-bit character associated
-/// with the object's active axis (active axis === right-left VS up-down?).
-/// 
-/// For GROUP objects, recursively calls `print_object` on each
-/// element of the group.
-/// 
-/// For EMPTY objects, does nothing.
-/// 
-/// For LEVEL / IMAGE objects, throws a TypeError.
-fn print_object(obj: &Object, id: Option<usize>) {
-    match &obj.obj_type {
-        Type::You(you) => {
-            if you.dir & 1 == 0 {
-                // Unwrap will catch syscall errors
-                let mut out = stdout();
-                out.write(&[you.x]).unwrap();
-                out.flush().unwrap();
-            }
-            else {
-                let mut out = stdout();
-                out.write(&[you.y]).unwrap();
-                out.flush().unwrap();
-            }
-        },
-        Type::You2(you) => {
-            if you.dir & 1 == 0 {
-                // Unwrap will catch syscall errors
-                let mut out = stdout();
-                let top = (you.x >> 8) as u8;
-                if top == 0 {
-                    out.write(&[you.x as u8]).unwrap();
-                }
-                else {
-                    out.write(&[(you.x >> 8) as u8, you.x as u8]).unwrap();
-                }
-                out.flush().unwrap();
-            }
-            else {
-                let mut out = stdout();
-                out.write(&[(you.y >> 8) as u8, you.y as u8]).unwrap();
-                out.flush().unwrap();
+    #[test]
+    fn reference_cycles() {
+        let reference = |pointer| Object {
+            reference_count: 0,
+            obj_type: Type::Reference(Reference { pointer, weak: false })
+        };
+        let (mut locals, globals, ids) = (Objects::new(), Objects::new(), HashMap::new());
+        locals.insert(3, reference(4));
+        locals.insert(4, reference(5));
+        locals.insert(5, EMPTY);
+        assert_eq!(follow_references(3, &locals, &globals, &ids, false).unwrap(), Some(5));
+        // 3 -> 4 -> 5 -> 4
+        locals.insert(5, reference(4));
+        assert_eq!(follow_references(3, &locals, &globals, &ids, false).unwrap(), None);
+        assert!(try_find_ref(&5, &locals, &globals, &ids).is_none());
+        // A reference to itself
+        locals.insert(6, reference(6));
+        assert_eq!(follow_references(6, &locals, &globals, &ids, false).unwrap(), None);
+    }
+
+    #[test]
+    fn power_keeps_globals() {
+        let (scope, ids) = run("
+            count is float count is you n is you
+            step is level step has x count is move step is done
+            a is step a has n a is power b is step b has n b is power
+            g is group g has n g feed step n play step
+        ");
+        assert!(matches!(scope.globals[&id("count", &ids)].obj_type, Type::You(You { x: 4, .. })));
+    }
+
+    #[test]
+    fn power_fresh_locals() {
+        let (instructions, identifiers) = compile(b"
+            f is level f has n
+                body is tele
+                    lonely n fear body
+                    n is not move
+                    g is f g has n g is power
+                    f make g
+                body is done
+                f make n
+            f is done
+            a is you b is you c is group
+            n is you and move and move and move and move and move and move and move and move
+            r is f r has n r is power
+        ").unwrap();
+        // The most objects held by the locals of a call, at any depth
+        let mut most = 0;
+        let mut interpreter = Interpreter::new(empty(), sink());
+        let mut execution = interpreter.start(&instructions, &identifiers, &Options::default());
+        while execution.instruction().is_some() {
+            if execution.scope() != PRG_SCOPE {
+                most = most.max(execution.locals().iter().count());
             }
-        },
-        Type::Group(group) => {
-            for object in &group.data {
-                print_object(&object, None);
+            interpreter.step(&mut execution).unwrap();
+        }
+        let scope = interpreter.finish(execution).unwrap();
+        assert!(matches!(scope.locals[&id("r", &identifiers)].obj_type, Type::You(You { x: 0, .. })));
+        // Only n, f and g, however deep the recursion
+        assert_eq!(most, 3);
+    }
+
+    #[test]
+    fn step_like_exec() {
+        let programs = [
+            include_str!("../examples/hello_world.baba"),
+            include_str!("../examples/fibonacci.baba"),
+            // Breaking from nested loops
+            "baba is you outer is tele inner is tele baba is move and turn lonely baba fear outer \
+            inner is done outer is done",
+            // Values returned from inside a loop, and WIN inside a call
+            "f is level f has x body is tele lonely x fear body x is not move f make x body is done f is done \
+            baba is you and move and move g is f g has baba g is power \
+            h is level b is you b is win h is done h is power baba is move",
+            // IMAGE constructors, and calls made by FEED and PLAY
+            "point is image point has px point is level point has self px is you self has px point is done \
+            point is done p is point p is power f is level f has x x is move f make x f is done \
+            a is you g is group g has a and a g feed f a play f",
+            "baba is you baba is shift"
+        ];
+        for program in programs {
+            let (instructions, identifiers) = compile(program.as_bytes()).unwrap();
+            let mut interpreter = Interpreter::new(empty(), Vec::new());
+            let expected = interpreter.exec(&instructions, &identifiers, &Options::default());
+            let mut stepper = Interpreter::new(empty(), Vec::new());
+            let execution = stepper.start(&instructions, &identifiers, &Options::default());
+            let stepped = stepper.finish(execution);
+            match (expected, stepped) {
+                (Ok(expected), Ok(stepped)) => {
+                    assert_eq!(dump_state(&stepped, &identifiers), dump_state(&expected, &identifiers), "{}", program);
+                    assert_eq!(stepped.exit_code, expected.exit_code, "{}", program);
+                },
+                (Err(expected), Err(stepped)) => assert_eq!(stepped, expected, "{}", program),
+                (expected, stepped) => panic!("{}: {:?} but {:?}", program, expected, stepped)
             }
-        },
-        x => {
-            if let Some(i) = id {
-                throw_error(
-                    ErrorType::TypeError, 
-                    format!("Object {} of type {} cannot be TEXT", i, x),
-                    None
-                );
+            assert_eq!(stepper.into_output(), interpreter.into_output(), "{}", program);
+        }
+    }
+
+    #[test]
+    fn idle_levels() {
+        let parameters = |source: &str| {
+            let (scope, ids) = run(source);
+            match &scope.locals[&id("f", &ids)].obj_type {
+                Type::Level(level) => level.parameters.len(),
+                _ => panic!("f is not LEVEL")
             }
-            else {
-                throw_error(
-                    ErrorType::TypeError, 
-                    format!("[Unnamed Object] (element of GROUP) of type {} cannot be TEXT", x),
-                    None
-                );
+        };
+        let program = "f is level f has x f is done a is you";
+        // Partially applied
+        assert_eq!(parameters(&format!("{} idle f has a", program)), 0);
+        assert_eq!(parameters(&format!("{} not idle f has a", program)), 1);
+        // Fully applied
+        assert_eq!(parameters(&format!("{} f has a idle f has a", program)), 2);
+        assert_eq!(parameters(&format!("{} f has a not idle f has a", program)), 1);
+    }
+
+    #[test]
+    fn word_group_index() {
+        let (scope, ids) = run("a is you g is group g has a g has a");
+        let mut group = match &scope.locals[&id("g", &ids)].obj_type {
+            Type::Group(group) => group.clone(),
+            _ => panic!("g is not GROUP")
+        };
+        append_line(&mut group, b"xy");
+        assert_eq!(group.data.len(), 4);
+        assert_eq!(group.index, 2);
+        assert!(matches!(group.data[group.index].obj_type, Type::You(you) if you.x == b'x' as u32));
+        // Nothing read
+        append_line(&mut group, b"");
+        assert_eq!(group.index, 2);
+    }
+
+    #[test]
+    fn line_words() {
+        // Each word as a string, along with the index of the GROUP
+        let words = |input: &[u8], delimiter: Option<u8>| {
+            let (instructions, identifiers) = compile(b"g is group g is line g is line").unwrap();
+            let options = Options { delimiter, ..Options::default() };
+            let scope = Interpreter::new(input, sink()).exec(&instructions, &identifiers, &options).unwrap();
+            let group = match &scope.locals[&id("g", &identifiers)].obj_type {
+                Type::Group(group) => group.clone(),
+                _ => panic!("g is not GROUP")
+            };
+            let words = group.data.iter().map(|word| match &word.obj_type {
+                Type::Group(word) => word.data.iter().map(|byte| match byte.obj_type {
+                    Type::You(you) => you.x as u8 as char,
+                    _ => panic!("{} is not YOU", byte)
+                }).collect::<String>(),
+                _ => panic!("{} is not GROUP", word)
+            }).collect::<Vec<String>>();
+            (words, group.index)
+        };
+        let (read, index) = words(b"baba keke\nis you\n", None);
+        assert_eq!(read, ["baba", "keke", "is", "you"]);
+        assert_eq!(index, 2);
+        // Empty lines and trailing delimiters give empty words
+        let (read, index) = words(b"\nbaba \n", None);
+        assert_eq!(read, ["", "baba", ""]);
+        assert_eq!(index, 1);
+        // Nothing is read at the end of input
+        assert_eq!(words(b"", None), (Vec::new(), 0));
+        assert_eq!(words(b"a b,c", Some(b',')).0, ["a b", "c"]);
+        let (instructions, identifiers) = compile(b"baba is you baba is line").unwrap();
+        let err = Interpreter::new(empty(), sink()).exec(&instructions, &identifiers, &Options::default()).unwrap_err();
+        assert_eq!(err.kind, ErrorType::TypeError);
+    }
+
+    #[test]
+    fn number_literals() {
+        let (instructions, identifiers) = compile(b"out is 65 and text g is group g has 66 and out g is text").unwrap();
+        let mut interpreter = Interpreter::new(empty(), Vec::new());
+        interpreter.exec(&instructions, &identifiers, &Options::default()).unwrap();
+        assert_eq!(interpreter.into_output(), b"ABA");
+        let (scope, ids) = run("
+            a is you and right b is 300 c is not 1 a is 7
+            sum is 250 and 10 and not b
+            g is group g has 300
+        ");
+        let value = |name| match scope.locals[&id(name, &ids)].obj_type {
+            Type::You(you) => (you.x, you.y, you.dir),
+            Type::You2(you) => (you.x as u32, you.y as u32, you.dir),
+            _ => panic!("{} is not YOU", name)
+        };
+        // Numbers wrap around, and IS keeps the direction
+        assert_eq!(value("b"), (44, 0, 0));
+        assert_eq!(value("c"), (254, 255, 0));
+        assert_eq!(value("a"), (7, 0, 0));
+        assert_eq!(value("sum"), (216, 0, 0));
+        assert!(matches!(&scope.locals[&id("g", &ids)].obj_type, Type::Group(group) if group.data[0] == Object {
+            reference_count: 0,
+            obj_type: Type::You(You { x: 44, y: 0, dir: 0, sleeping: false })
+        }));
+    }
+
+    #[test]
+    fn wide_you() {
+        let source = "
+            a is 200 and more
+            b is you and not move
+            c is not 1
+            d is you d is 70000 and 1000 g is group g has 70000
+            out is you and move and more and more and more and more and more and more and more and more and move
+        ";
+        let values = |width| {
+            let (scope, ids) = run_with(source, &Options { width, ..Options::default() });
+            let you = |obj_type: &Type| match obj_type {
+                Type::You(you) => (you.x, you.y),
+                other => panic!("{} is not YOU", other)
+            };
+            let mut out = ["a", "b", "c", "d", "out"].iter()
+                .map(|name| you(&scope.locals[&id(name, &ids)].obj_type))
+                .collect::<Vec<(u32, u32)>>();
+            match &scope.locals[&id("g", &ids)].obj_type {
+                Type::Group(group) => out.push(you(&group.data[0].obj_type)),
+                _ => panic!("g is not a GROUP")
             }
-        }
+            out
+        };
+        // Number literals are wrapped to the width as well
+        assert_eq!(values(None), [(144, 0), (255, 0), (254, 255), (88, 0), (1, 0), (112, 0)]);
+        assert_eq!(values(Some(16)), [(400, 0), (65535, 0), (65534, 65535), (5464, 0), (257, 0), (4464, 0)]);
+        assert_eq!(
+            values(Some(32)), 
+            [(400, 0), (u32::MAX, 0), (u32::MAX - 1, u32::MAX), (71000, 0), (257, 0), (70000, 0)]
+        );
+        // TEXT writes the lowest byte
+        let (instructions, identifiers) = compile(b"a is 128 and more out is you out is a and 65 out is text").unwrap();
+        let options = Options { width: Some(16), ..Options::default() };
+        let mut interpreter = Interpreter::new(empty(), Vec::new());
+        let scope = interpreter.exec(&instructions, &identifiers, &options).unwrap();
+        assert!(matches!(scope.locals[&id("out", &identifiers)].obj_type, Type::You(you) if you.x == 321));
+        assert_eq!(interpreter.into_output(), b"A");
     }
-}
 
-/// Checks if the two objects are of the same variant.
-fn is_same_type(first: &Object, other: &Object) -> bool {
-    if let Type::You(_) = first.obj_type {
-        if let Type::You(_) = other.obj_type {
-            true
-        }
-        else {
-            false
-        }
+    #[test]
+    fn empty_group_operations() {
+        let (scope, ids) = run("g is group g is shift g is not shift g is swap g is sink g is not fall");
+        assert!(matches!(&scope.locals[&id("g", &ids)].obj_type, Type::Group(group) if group.data.is_empty() && group.index == 0));
+        // SINK moves the index back onto the remaining elements
+        let (scope, ids) = run("a is you g is group g has a g has a g is not fall g is sink g is swap");
+        assert!(matches!(&scope.locals[&id("g", &ids)].obj_type, Type::Group(group) if group.data.len() == 1 && group.index == 0));
     }
-    else if let Type::You2(_) = first.obj_type {
-        if let Type::You2(_) = other.obj_type {
-            true
-        }
-        else {
-            false
-        }
+
+    #[test]
+    fn sum_all_sign() {
+        let (scope, ids) = run("
+            a is you a is move b is you b is move b is move 
+            c is a and all d is a and not all
+        ");
+        // ALL is a + b when c is summed, and a + b + c when d is
+        assert!(matches!(scope.locals[&id("c", &ids)].obj_type, Type::You2(You2 { x: 4, .. })));
+        assert!(matches!(scope.locals[&id("d", &ids)].obj_type, Type::You2(You2 { x: 65530, .. })));
     }
-    else if let Type::Group(_) = first.obj_type {
-        if let Type::Group(_) = other.obj_type {
-            true
-        }
-        else {
-            false
-        }
+
+    #[test]
+    fn is_not_both_axes() {
+        let (scope, ids) = run("
+            a is you a is move a is up a is move a is move b is not a
+            c is you2 c is move c is up c is move c is move d is not c
+        ");
+        assert!(matches!(scope.locals[&id("b", &ids)].obj_type, Type::You(You { x: 254, y: 253, .. })));
+        assert!(matches!(scope.locals[&id("d", &ids)].obj_type, Type::You2(You2 { x: 65534, y: 65533, .. })));
     }
-    else if let Type::Level(_) = first.obj_type {
-        if let Type::Level(_) = other.obj_type {
-            true
-        }
-        else {
-            false
-        }
+
+    #[test]
+    fn play_level() {
+        let (scope, ids) = run("
+            double is level double has n n is more double make n double is done
+            a is you a is move a play double a play double b is you b play empty
+        ");
+        assert!(matches!(scope.locals[&id("a", &ids)].obj_type, Type::You(You { x: 4, .. })));
+        // The LEVEL itself is unchanged
+        assert!(matches!(scope.locals[&id("double", &ids)].obj_type, Type::Level(_)));
     }
-    else if let Type::Image(_) = first.obj_type {
-        if let Type::Image(_) = other.obj_type {
-            true
-        }
-        else {
-            false
+
+    #[test]
+    fn feed_group() {
+        let (scope, ids) = run("
+            double is level double has n n is more double make n double is done
+            a is you a is move g is group g has a a is move g has a a is move g has a
+            g feed double
+        ");
+        match &scope.locals[&id("g", &ids)].obj_type {
+            Type::Group(group) => {
+                let values = group.data.iter().map(|obj| match obj.obj_type {
+                    Type::You(you) => you.x as u8,
+                    _ => panic!("{} is not YOU", obj)
+                }).collect::<Vec<u8>>();
+                assert_eq!(values, vec![2, 4, 6]);
+            },
+            _ => panic!("g is not GROUP")
         }
+        // The LEVEL itself is left intact
+        assert!(matches!(scope.locals[&id("double", &ids)].obj_type, Type::Level(_)));
     }
-    else if let Type::ImageInstance(img) = &first.obj_type {
-        if let Type::ImageInstance(other_img) = &other.obj_type {
-            img.class == other_img.class
-        }
-        else {
-            false
+
+    #[test]
+    fn play_bells() {
+        let mut output = Vec::new();
+        play(&mut output, 3);
+        play(&mut output, 0);
+        assert_eq!(output, vec![7, 7, 7]);
+    }
+
+    #[test]
+    fn flush_before_read() {
+        let mut output = BufWriter::new(Vec::new());
+        let mut input = Cursor::new(b"baba\n".to_vec());
+        write!(output, "name? ").unwrap();
+        assert!(output.get_ref().is_empty());
+        assert_eq!(read_line(&mut output, &mut input, None), b"baba\n".to_vec());
+        assert_eq!(output.get_ref(), b"name? ");
+        write!(output, "ok").unwrap();
+        assert_eq!(read_byte(&mut output, &mut input), 0);
+        assert_eq!(output.get_ref(), b"name? ok");
+    }
+
+    #[test]
+    fn all_win_defeat_parse() {
+        let (instructions, _) = compile(b"all is win all is defeat all is not win").unwrap();
+        assert_eq!(instructions, vec![
+            Instruction::Simple(Simple::AllWin),
+            Instruction::Simple(Simple::AllDefeat),
+        ]);
+    }
+
+    #[test]
+    fn all_win_trigger() {
+        let (scope, _) = run("keke is group");
+        assert!(!any_you(&scope.locals, &scope.globals));
+        let (scope, _) = run("keke is group baba is you2");
+        assert!(any_you(&scope.locals, &scope.globals));
+    }
+
+    #[test]
+    fn profile_counts() {
+        let (instructions, identifiers) = compile(b"
+            baba is you keke is you keke is move and move and move
+            loop is tele baba is move baba on keke fear loop loop is done
+        ").unwrap();
+        let mut interpreter = Interpreter::new(empty(), sink());
+        assert_eq!(interpreter.profile(), None);
+        interpreter.enable_profile();
+        interpreter.exec(&instructions, &identifiers, &Options::default()).unwrap();
+        assert_eq!(interpreter.profile(), Some(vec![("MOVE", 6), ("TELE", 3), ("IS YOU", 2), ("FEAR", 1)]));
+    }
+
+    #[test]
+    fn tracing_keeps_results() {
+        let source = "baba is you loop is tele baba is move and turn lonely baba fear loop loop is done";
+        let trace = Options { trace: true, ..Options::default() };
+        let (traced, ids) = run_with(source, &trace);
+        let (untraced, _) = run_with(source, &Options::default());
+        assert_eq!(traced.locals[&id("baba", &ids)], untraced.locals[&id("baba", &ids)]);
+    }
+
+    #[test]
+    fn hosted_exit_codes() {
+        // The interpreter never exits itself, leaving that to its host
+        let (scope, ids) = run("baba is you baba is win baba is move");
+        assert_eq!(scope.exit_code, Some(0));
+        // Nothing runs after WIN
+        assert!(matches!(scope.locals[&id("baba", &ids)].obj_type, Type::You(You { x: 0, .. })));
+        // DEFEAT stops the program from within loops and LEVELs
+        let (scope, _) = run("
+            f is level baba is you baba is defeat f is done
+            loop is tele f is power loop is done
+        ");
+        assert_eq!(scope.exit_code, Some(1));
+        let (scope, _) = run("keke is group all is win");
+        assert_eq!(scope.exit_code, None);
+    }
+
+    #[test]
+    fn reserved_objects_intact() {
+        // Built-ins may still be read from
+        let (scope, ids) = run("
+            g is group g has empty
+            f is level f make empty f is done
+            out is f and is power
+        ");
+        assert_eq!(scope.globals[&0], EMPTY);
+        assert!(!scope.locals.contains_key(&0) && !scope.locals.contains_key(&1));
+        assert!(matches!(&scope.locals[&id("g", &ids)].obj_type, Type::Group(group) if *group.data == vec![EMPTY]));
+        assert_eq!(scope.locals[&id("out", &ids)], EMPTY);
+    }
+
+    #[test]
+    fn runtime_errors() {
+        let run_err = |source: &str| {
+            let (instructions, identifiers) = compile(source.as_bytes()).unwrap();
+            Interpreter::new(empty(), sink()).exec(&instructions, &identifiers, &Options::default()).unwrap_err()
+        };
+        let err = run_err("baba is you baba is shift");
+        assert_eq!(err.kind, ErrorType::TypeError);
+        assert_eq!(err.message, "Object 3 of type YOU cannot be SHIFT");
+        let err = run_err("loop is tele a is you b is you a mimic b b mimic a a is move loop is done");
+        assert_eq!(err.kind, ErrorType::RuntimeError);
+        assert_eq!(err.identifiers.len(), 2);
+        // WIN stops the program without exiting, even when not hosted
+        let (scope, ids) = run("baba is you baba is win baba is move");
+        assert_eq!(scope.exit_code, Some(0));
+        assert!(matches!(scope.locals[&id("baba", &ids)].obj_type, Type::You(You { x: 0, .. })));
+    }
+
+    #[test]
+    fn track_group_index() {
+        let (scope, ids) = run("
+            a is you b is you c is you
+            g is group g has a and b and c
+            start is you start track g
+            g is shift and shift
+            twice is you twice track g
+            g is shift
+            wrapped is you wrapped track g
+        ");
+        let value = |name| match scope.locals[&id(name, &ids)].obj_type {
+            Type::You(you) => you.x,
+            _ => panic!("{} is not YOU", name)
+        };
+        assert_eq!(value("start"), 0);
+        assert_eq!(value("twice"), 2);
+        assert_eq!(value("wrapped"), 0);
+    }
+
+    #[test]
+    fn peek_group_index() {
+        let (scope, ids) = run("
+            a is you b is you and move c is you and move and move
+            g is group g has a and b and c
+            g peek first
+            g is shift and shift
+            g peek last
+            g is shift and shift
+            g peek again
+        ");
+        let value = |name| match scope.locals[&id(name, &ids)].obj_type {
+            Type::You(you) => you.x,
+            _ => panic!("{} is not YOU", name)
+        };
+        assert_eq!([value("first"), value("last"), value("again")], [0, 2, 1]);
+        // The elements are left in place
+        match &scope.locals[&id("g", &ids)].obj_type {
+            Type::Group(group) => assert_eq!(group.data.len(), 3),
+            _ => panic!("g is not GROUP")
         }
+        let run_err = |source: &str| {
+            let (instructions, identifiers) = compile(source.as_bytes()).unwrap();
+            Interpreter::new(empty(), sink()).exec(&instructions, &identifiers, &Options::default()).unwrap_err()
+        };
+        let err = run_err("g is group g peek x");
+        assert_eq!(err.kind, ErrorType::RuntimeError);
+        assert_eq!(err.message, "Object 3 of type GROUP has no element at index 0, as it holds 0");
+        // Removing the last element leaves the index past the end
+        let err = run_err("a is you g is group g has a and a g is shift g make b g peek x");
+        assert_eq!(err.kind, ErrorType::RuntimeError);
+        assert_eq!(run_err("a is you a peek x").kind, ErrorType::TypeError);
     }
-    else {
-        false
+
+    #[test]
+    fn injected_io() {
+        let (instructions, identifiers) = compile(b"g is group g is word g is text b is you b is word b is text b is word b is text").unwrap();
+        let mut interpreter = Interpreter::new(Cursor::new(b"Hi\nX"), Vec::new());
+        interpreter.exec(&instructions, &identifiers, &Options::default()).unwrap();
+        // The end of input reads as 0
+        assert_eq!(interpreter.into_output(), b"Hi\nX\0");
+    }
+
+    #[test]
+    fn flushed_on_win() {
+        let source = include_str!("../examples/hello_world.baba").to_owned() + "\nbaba is you baba is win baba is text";
+        let (instructions, identifiers) = compile(source.as_bytes()).unwrap();
+        let mut interpreter = Interpreter::new(empty(), Vec::new());
+        let scope = interpreter.exec(&instructions, &identifiers, &Options::default()).unwrap();
+        assert_eq!(scope.exit_code, Some(0));
+        // Written out before returning, rather than when the interpreter is consumed
+        assert!(interpreter.output.buffer().is_empty());
+        assert_eq!(interpreter.output.get_ref(), b"Hello, world!\n");
     }
 }
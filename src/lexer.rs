@@ -1,9 +1,10 @@
 use std::fs::File;
 use std::io::Read;
 use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
 
-use crate::token::{Token, parse};
-use crate::error_handler::{ErrorType, throw_error};
+use crate::token::{Token, Noun, parse, spelling};
+use crate::error_handler::{ErrorType, BabaError, error, set_source};
 
 /// The simple internal state of the lexer.
 /// 
@@ -12,38 +13,69 @@ enum State {
     Word,
     Separator,
     MaybeComment,
-    Comment
+    Comment,
+    BlockComment,
+    MaybeBlockEnd,
+    Literal,
+    Escape
 }
 
+/// Start (inclusive) and end (exclusive) byte offsets of each token.
+pub type Spans = Vec<(usize, usize)>;
+
+/// Mapping between identifier IDs and their names.
+pub type Identifiers = HashMap<usize, String>;
+
 /// Tokenizes a Baba source file from the given path.
 /// Returns a vector of tokens if tokenization is successful,
-/// as well as a hashmap of the identifiers encountered.
+/// along with the byte span of each token in the source
+/// and a hashmap of the identifiers encountered.
 /// 
 /// # Arguments
 /// 
 /// * `path` - Source path for the program being tokenized.
 /// 
+/// * `source` - Source code to tokenize, if no path is provided.
+/// 
+/// * `lenient` - Whether to treat unexpected control bytes as whitespace
+///   instead of returning a LexerError.
+/// 
 /// # Return
 /// 
 /// Returns a tuple containing:
 /// 
 /// * `Vec<Token>` - The tokens parsed from the file.
 /// 
+/// * `Vec<(usize, usize)>` - The start (inclusive) and end (exclusive) byte offsets of each token.
+/// 
 /// * `HashMap<String, usize>` - A mapping between identifiers (e.g. "baba")
-/// and their corresponding IDs.
-pub fn tokenize(path: Option<String>, source: Option<&mut Vec<u8>>) -> (Vec<Token>, HashMap<usize, String>) {
+///   and their corresponding IDs.
+pub fn tokenize(
+    path: Option<String>, 
+    source: Option<&mut Vec<u8>>,
+    lenient: bool
+) -> Result<(Vec<Token>, Spans, Identifiers), BabaError> {
+    let buffer = read_source(path, source)?;
+    // Spans are reported by line
+    set_source(&buffer);
+    let (tokens, spans, _comments, identifiers) = lex(&buffer, lenient)?;
+    Ok((tokens, spans, identifiers))
+}
+
+/// Reads Baba source code from the given path, or takes it from `source`
+/// if no path is given.
+pub fn read_source(path: Option<String>, source: Option<&mut Vec<u8>>) -> Result<Vec<u8>, BabaError> {
     let mut buffer = Vec::new();
     
     if let Some(p) = path {
         let mut file = match File::open(&p) {
             Ok(f) => f,
             Err(_) => {
-                throw_error(
+                return Err(error(
                     ErrorType::FileError, 
                     format!("Could not open file at `{}`", p),
                     None
-                );
-                panic!() // necessary for match arms to match
+                ));
             }
         };
         file.read_to_end(&mut buffer).unwrap();
@@ -53,8 +85,27 @@ pub fn tokenize(path: Option<String>, source: Option<&mut Vec<u8>>) -> (Vec<Toke
             buffer.append(bytes);
         }
     }
+    Ok(buffer)
+}
 
+/// Tokenizes a buffer of Baba source code.
+/// 
+/// Returns the tokens, their spans, the spans of comments 
+/// and the identifiers encountered.
+/// 
+/// Control bytes other than tabs and line breaks raise a LexerError
+/// outside of comments, unless `lenient` is set.
+/// 
+/// Comments are either line comments (`// ...`) or block comments (`/* ... */`).
+/// Block comments don't nest: the first `*/` ends the comment.
+pub fn lex(
+    buffer: &[u8], 
+    lenient: bool
+) -> Result<(Vec<Token>, Spans, Spans, Identifiers), BabaError> {
     let mut out: Vec<Token> = Vec::new();
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    let mut comments: Vec<(usize, usize)> = Vec::new();
+    let mut comment_start = 0;
     let mut identifiers: HashMap<usize, String> = HashMap::new();
     identifiers.insert(0, "empty".to_string());
     identifiers.insert(1, "level".to_string());
@@ -63,6 +114,15 @@ pub fn tokenize(path: Option<String>, source: Option<&mut Vec<u8>>) -> (Vec<Toke
     let mut word_start = 0;
 
     for (i, &byte) in buffer.iter().enumerate() {
+        if !lenient && is_unexpected_control(byte) {
+            if let State::Comment | State::BlockComment | State::MaybeBlockEnd = state {} else {
+                return Err(error(
+                    ErrorType::LexerError,
+                    format!("Unexpected control byte 0x{:02x} at offset {}", byte, i),
+                    None
+                ));
+            }
+        }
         let c = char::from(byte)
             .to_lowercase().next()
             .unwrap();
@@ -76,8 +136,12 @@ pub fn tokenize(path: Option<String>, source: Option<&mut Vec<u8>>) -> (Vec<Toke
                 }
                 else if c == '/' {
                     state = State::MaybeComment;
+                    comment_start = i;
                     word_start += 1;
                 }
+                else if c == '"' {
+                    state = State::Literal;
+                }
                 else {
                     // The current word won't start here yet
                     word_start += 1;
@@ -96,15 +160,23 @@ pub fn tokenize(path: Option<String>, source: Option<&mut Vec<u8>>) -> (Vec<Toke
                     // Empty strings aren't tokens (we should never encounter any)
                     if let Some(token) = parse(word, &mut identifiers) {
                         out.push(token);
+                        spans.push((word_start, i));
                     }
                     else {
-                        throw_error(
-                            ErrorType::LexerError,
-                            format!("Failed to parse input: {:?}", &word),
-                            None
-                        );
+                        return Err(word_error(word, word_start));
                     };
-                    word_start = i + 1;
+                    if c == '"' {
+                        state = State::Literal;
+                        word_start = i;
+                    }
+                    else {
+                        // A comment may directly follow the word
+                        if c == '/' {
+                            state = State::MaybeComment;
+                            comment_start = i;
+                        }
+                        word_start = i + 1;
+                    }
                 }
             },
             // This might be a comment? ("//")
@@ -116,6 +188,13 @@ pub fn tokenize(path: Option<String>, source: Option<&mut Vec<u8>>) -> (Vec<Toke
                     state = State::Comment;
                     word_start += 1;
                 }
+                else if c == '*' {
+                    state = State::BlockComment;
+                    word_start += 1;
+                }
+                else if c == '"' {
+                    state = State::Literal;
+                }
                 else {
                     // The current word won't start here yet
                     word_start += 1;
@@ -124,44 +203,313 @@ pub fn tokenize(path: Option<String>, source: Option<&mut Vec<u8>>) -> (Vec<Toke
             // This certainly is a comment. ("//")
             State::Comment => {
                 if c == '\n' || c == '\r' {
+                    comments.push((comment_start, i));
                     state = State::Separator;
                     word_start += 1;
                 }
                 else {
                     word_start += 1;
                 }
+            },
+            // In a block comment ("/*")
+            State::BlockComment => {
+                if c == '*' {
+                    state = State::MaybeBlockEnd;
+                }
+                word_start += 1;
+            },
+            // This might be the end of a block comment ("*/")
+            State::MaybeBlockEnd => {
+                if c == '/' {
+                    comments.push((comment_start, i + 1));
+                    state = State::Separator;
+                }
+                else if c != '*' {
+                    state = State::BlockComment;
+                }
+                word_start += 1;
+            },
+            // In a string literal ("...")
+            State::Literal => {
+                if c == '\\' {
+                    state = State::Escape;
+                }
+                else if c == '"' {
+                    state = State::Separator;
+                    if let Err(message) = decode_literal(&buffer[word_start + 1..i]) {
+                        return Err(error(
+                            ErrorType::LexerError,
+                            format!("{} in string literal at offset {}", message, word_start),
+                            None
+                        ));
+                    }
+                    // Literals are never empty, since they include the quotes,
+                    // but their raw bytes may not be valid UTF-8
+                    if let Some(token) = parse(&buffer[word_start..=i], &mut identifiers) {
+                        out.push(token);
+                        spans.push((word_start, i + 1));
+                    }
+                    else {
+                        return Err(error(
+                            ErrorType::LexerError,
+                            format!("Invalid UTF-8 in string literal at offset {}", word_start),
+                            None
+                        ));
+                    }
+                    word_start = i + 1;
+                }
+            },
+            // The byte after a backslash in a string literal
+            State::Escape => {
+                state = State::Literal;
             }
         }
     }
     // Account for EOF
-    if let State::Word = state {
+    if let State::Comment = state {
+        comments.push((comment_start, buffer.len()));
+    }
+    else if let State::BlockComment | State::MaybeBlockEnd = state {
+        return Err(error(
+            ErrorType::LexerError,
+            format!("Unterminated block comment at offset {}", comment_start),
+            None
+        ));
+    }
+    else if let State::Literal | State::Escape = state {
+        return Err(error(
+            ErrorType::LexerError,
+            format!("Unterminated string literal at offset {}", word_start),
+            None
+        ));
+    }
+    else if let State::Word = state {
         let word = &buffer[word_start..];
         if let Some(token) = parse(word, &mut identifiers) {
             out.push(token);
+            spans.push((word_start, buffer.len()));
         }
         else {
-            throw_error(
-                ErrorType::LexerError,
-                format!("Failed to parse input: {:?}", &word),
-                None
-            );
+            return Err(word_error(word, word_start));
         };
     }
-    let output = out.to_owned();
-    let id = identifiers.to_owned();
+    Ok((out, spans, comments, identifiers))
+}
+
+/// The LexerError raised for a word at `offset` that isn't a token, 
+/// e.g. a number that doesn't fit in 32 bits.
+fn word_error(word: &[u8], offset: usize) -> BabaError {
+    let message = if word.iter().all(u8::is_ascii_digit) {
+        format!(
+            "Number {} at offset {} is larger than {}, the largest 32-bit number", 
+            String::from_utf8_lossy(word), offset, u32::MAX
+        )
+    }
+    else {
+        format!("Failed to parse input: {:?}", word)
+    };
+    error(ErrorType::LexerError, message, None)
+}
+
+/// Decodes the contents of a string literal (without the surrounding quotes) 
+/// into the bytes it represents.
+/// 
+/// The escape sequences `\n`, `\t`, `\\`, `\"` and `\xNN` (a byte in hexadecimal)
+/// are supported. Returns a description of the first invalid escape sequence, if any.
+pub fn decode_literal(contents: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut bytes = contents.iter();
+    while let Some(&byte) = bytes.next() {
+        if byte != b'\\' {
+            out.push(byte);
+            continue;
+        }
+        match bytes.next() {
+            Some(b'n') => out.push(b'\n'),
+            Some(b't') => out.push(b'\t'),
+            Some(b'\\') => out.push(b'\\'),
+            Some(b'"') => out.push(b'"'),
+            Some(b'x') => {
+                let digits = [bytes.next(), bytes.next()];
+                let value = match digits {
+                    [Some(&high), Some(&low)] if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() => {
+                        std::str::from_utf8(&[high, low]).ok()
+                            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    },
+                    _ => None
+                };
+                match value {
+                    Some(value) => out.push(value),
+                    None => return Err(String::from("Invalid escape sequence \\x"))
+                }
+            },
+            Some(&other) => return Err(format!("Invalid escape sequence \\{}", char::from(other))),
+            None => return Err(String::from("Unterminated escape sequence"))
+        }
+    }
+    Ok(out)
+}
+
+/// Serializes a token stream as JSON.
+/// 
+/// The schema (version 1) is an object of the form 
+/// `{"version": 1, "tokens": [...]}`, where each token is an object with the keys:
+/// 
+/// * `kind` - One of `"noun"`, `"verb"`, `"property"`, `"prefix"`, `"conditional"`,
+///   `"not"`, `"and"`, `"or"`, `"identifier"` or `"literal"`.
+/// 
+/// * `text` - The canonical spelling of the token.
+/// 
+/// * `id` - The identifier ID of the token (or of the literal), or `null` for keywords.
+/// 
+/// * `start`, `end` - The start (inclusive) and end (exclusive) byte offsets of the token.
+pub fn tokens_json(
+    tokens: &[Token], 
+    spans: &[(usize, usize)], 
+    identifiers: &HashMap<usize, String>
+) -> String {
+    let entries = tokens.iter()
+        .zip(spans.iter())
+        .map(|(token, (start, end))| {
+            let (kind, id) = match token {
+                Token::Noun(Noun::Identifier(id)) => ("identifier", id.to_string()),
+                Token::Noun(Noun::Literal(id)) => ("literal", id.to_string()),
+                Token::Noun(Noun::Number(_)) => ("number", String::from("null")),
+                Token::Noun(_) => ("noun", String::from("null")),
+                Token::Verb(_) => ("verb", String::from("null")),
+                Token::Property(_) => ("property", String::from("null")),
+                Token::Prefix(_) => ("prefix", String::from("null")),
+                Token::Conditional(_) => ("conditional", String::from("null")),
+                Token::Not => ("not", String::from("null")),
+                Token::And => ("and", String::from("null")),
+                Token::Or => ("or", String::from("null")),
+            };
+            format!(
+                "{{\"kind\": \"{}\", \"text\": \"{}\", \"id\": {}, \"start\": {}, \"end\": {}}}",
+                kind, escape_json(&spelling(token, identifiers)), id, start, end
+            )
+        })
+        .collect::<Vec<String>>();
+    format!("{{\"version\": 1, \"tokens\": [{}]}}", entries.join(", "))
+}
+
+/// Escapes a string for use inside a JSON string literal.
+pub fn escape_json(string: &str) -> String {
+    let mut out = String::new();
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c)
+        }
+    }
+    out
+}
+
+/// The syntactic class of a piece of source code, for highlighting.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokenClass {
+    Noun,
+    Verb,
+    Property,
+    Prefix,
+    Conditional,
+    Operator,
+    Identifier,
+    Literal,
+    Number,
+    Comment
+}
+
+impl Display for TokenClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenClass::Noun => write!(f, "keyword-noun"),
+            TokenClass::Verb => write!(f, "keyword-verb"),
+            TokenClass::Property => write!(f, "keyword-property"),
+            TokenClass::Prefix => write!(f, "prefix"),
+            TokenClass::Conditional => write!(f, "conditional"),
+            TokenClass::Operator => write!(f, "operator"),
+            TokenClass::Identifier => write!(f, "identifier"),
+            TokenClass::Literal => write!(f, "literal"),
+            TokenClass::Number => write!(f, "number"),
+            TokenClass::Comment => write!(f, "comment"),
+        }
+    }
+}
+
+/// Classifies each token and comment in a buffer of Baba source code.
+/// Returns the class of each along with its start (inclusive) and end (exclusive) 
+/// byte offsets, in source order.
+pub fn classify(buffer: &[u8]) -> Result<Vec<(TokenClass, usize, usize)>, BabaError> {
+    let (tokens, spans, comments, _identifiers) = lex(buffer, true)?;
+    let mut out: Vec<(TokenClass, usize, usize)> = tokens.iter()
+        .zip(spans.iter())
+        .map(|(token, &(start, end))| {
+            let class = match token {
+                Token::Noun(Noun::Identifier(_)) => TokenClass::Identifier,
+                Token::Noun(Noun::Literal(_)) => TokenClass::Literal,
+                Token::Noun(Noun::Number(_)) => TokenClass::Number,
+                Token::Noun(_) => TokenClass::Noun,
+                Token::Verb(_) => TokenClass::Verb,
+                Token::Property(_) => TokenClass::Property,
+                Token::Prefix(_) => TokenClass::Prefix,
+                Token::Conditional(_) => TokenClass::Conditional,
+                Token::Not | Token::And | Token::Or => TokenClass::Operator,
+            };
+            (class, start, end)
+        })
+        .chain(comments.iter().map(|&(start, end)| (TokenClass::Comment, start, end)))
+        .collect();
+    out.sort_by_key(|&(_, start, _)| start);
+    Ok(out)
+}
 
-    (output, id)
+/// Checks whether a byte is a control character other than tab, 
+/// line feed or carriage return.
+fn is_unexpected_control(byte: u8) -> bool {
+    (byte < 0x20 || byte == 0x7f) && !matches!(byte, b'\t' | b'\n' | b'\r')
+}
+
+/// Returns the canonical spelling of a token stream, with comments removed 
+/// and each token separated by a single space.
+/// 
+/// Tokenizing the output yields the same tokens and identifiers as the original source.
+pub fn minify(tokens: &[Token], identifiers: &HashMap<usize, String>) -> String {
+    tokens.iter()
+        .map(|token| spelling(token, identifiers))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Lists a token stream one token per line, for debugging the lexer.
+/// 
+/// Identifiers and literals are followed by the name they resolve to, 
+/// e.g. `Identifier(3) "baba"`.
+pub fn dump_tokens(tokens: &[Token], identifiers: &HashMap<usize, String>) -> String {
+    tokens.iter()
+        .map(|token| match token {
+            Token::Noun(noun @ Noun::Identifier(id)) | Token::Noun(noun @ Noun::Literal(id)) => {
+                format!("{:?} {:?}", noun, identifiers[id])
+            },
+            _ => format!("{:?}", token)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::lexer::tokenize;
-    use crate::token::{Token, Noun, Verb};
+    use crate::lexer::{tokenize, minify, dump_tokens, classify, tokens_json, escape_json, is_unexpected_control, decode_literal, TokenClass};
+    use crate::token::{Token, Noun, Verb, Property};
+    use crate::instruction::is_reserved;
+    use crate::error_handler::ErrorType;
 
     #[test]
     fn tokenize_alnum() {
         let path = String::from("tests/tokenize_alnum.baba");
-        let (tokens, _identifiers) = tokenize(Some(path), None);
+        let (tokens, _spans, _identifiers) = tokenize(Some(path), None, false).unwrap();
 
         assert_eq!(
             tokens,
@@ -188,4 +536,190 @@ mod tests {
             ]
         )
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn reserved_identifiers() {
+        // Keywords never alias user identifiers, regardless of case
+        let mut bytes = b"Empty LEVEL image baba".to_vec();
+        let (tokens, _, _) = tokenize(None, Some(&mut bytes), false).unwrap();
+        assert_eq!(tokens, vec![
+            Token::Noun(Noun::Empty),
+            Token::Noun(Noun::Level),
+            Token::Noun(Noun::Image),
+            Token::Noun(Noun::Identifier(3)),
+        ]);
+        assert!(is_reserved(0) && is_reserved(1) && is_reserved(2));
+        assert!(!is_reserved(3));
+    }
+
+    #[test]
+    fn tokenize_spans() {
+        let mut source = b"baba is you // comment\n  keke\tis\r\ngroup".to_vec();
+        let (_tokens, spans, _identifiers) = tokenize(None, Some(&mut source), false).unwrap();
+
+        assert_eq!(spans, vec![(0, 4), (5, 7), (8, 11), (25, 29), (30, 32), (34, 39)]);
+    }
+
+    #[test]
+    fn minify_round_trip() {
+        let mut source = b"// comment\nBaba IS you   and\tMOVE\n\nkeke/is not/baba // trailing".to_vec();
+        let (tokens, _spans, identifiers) = tokenize(None, Some(&mut source), false).unwrap();
+        let minified = minify(&tokens, &identifiers);
+        assert_eq!(minified, "baba is you and move keke is not baba");
+
+        let mut bytes = minified.into_bytes();
+        let (new_tokens, _new_spans, new_identifiers) = tokenize(None, Some(&mut bytes), false).unwrap();
+        assert_eq!(tokens, new_tokens);
+        assert_eq!(identifiers, new_identifiers);
+    }
+
+    #[test]
+    fn dump_token_stream() {
+        let mut source = b"baba is \"hi\" and not empty".to_vec();
+        let (tokens, _spans, identifiers) = tokenize(None, Some(&mut source), false).unwrap();
+        assert_eq!(
+            dump_tokens(&tokens, &identifiers),
+            "Identifier(3) \"baba\"\nVerb(Is)\nLiteral(4) \"\\\"hi\\\"\"\nAnd\nNot\nNoun(Empty)"
+        );
+    }
+
+    #[test]
+    fn block_comments() {
+        let mut source = b"/* header\n   baba is you\n*/keke is you keke/* inline */is/**/move /* * / ** */".to_vec();
+        let (tokens, spans, identifiers) = tokenize(None, Some(&mut source), false).unwrap();
+        assert_eq!(identifiers[&3], "keke");
+        assert_eq!(tokens, vec![
+            Token::Noun(Noun::Identifier(3)),
+            Token::Verb(Verb::Is),
+            Token::Property(Property::You),
+            Token::Noun(Noun::Identifier(3)),
+            Token::Verb(Verb::Is),
+            Token::Property(Property::Move),
+        ]);
+        assert_eq!(spans, vec![(27, 31), (32, 34), (35, 38), (39, 43), (55, 57), (61, 65)]);
+        // Block comments don't nest, and line comments can follow words directly
+        let source = b"/* a /* b */ baba// c\nis /*/ you */ you";
+        assert_eq!(classify(source).unwrap(), vec![
+            (TokenClass::Comment, 0, 12),
+            (TokenClass::Identifier, 13, 17),
+            (TokenClass::Comment, 17, 21),
+            (TokenClass::Verb, 22, 24),
+            (TokenClass::Comment, 25, 35),
+            (TokenClass::Property, 36, 39),
+        ]);
+    }
+
+    #[test]
+    fn lexer_errors() {
+        // Non-UTF-8 bytes in a literal must be escaped, e.g. as \xFF
+        for source in [&b"baba /* is you"[..], b"baba is \"you", b"baba\x00is you", b"baba is \"\xff\""] {
+            let err = tokenize(None, Some(&mut source.to_vec()), false).unwrap_err();
+            assert_eq!(err.kind, ErrorType::LexerError);
+        }
+        let err = tokenize(None, Some(&mut b"out is 99999999999 and text".to_vec()), false).unwrap_err();
+        assert_eq!(err.message, "Number 99999999999 at offset 7 is larger than 4294967295, the largest 32-bit number");
+        let err = tokenize(Some(String::from("tests/missing.baba")), None, false).unwrap_err();
+        assert_eq!(err.message, "Could not open file at `tests/missing.baba`");
+    }
+
+    #[test]
+    fn classify_with_comments() {
+        let source = b"// header\nnot lonely baba is you and empty\nkeke on baba has keke // end";
+        assert_eq!(classify(source).unwrap(), vec![
+            (TokenClass::Comment, 0, 9),
+            (TokenClass::Operator, 10, 13),
+            (TokenClass::Prefix, 14, 20),
+            (TokenClass::Identifier, 21, 25),
+            (TokenClass::Verb, 26, 28),
+            (TokenClass::Property, 29, 32),
+            (TokenClass::Operator, 33, 36),
+            (TokenClass::Noun, 37, 42),
+            (TokenClass::Identifier, 43, 47),
+            (TokenClass::Conditional, 48, 50),
+            (TokenClass::Identifier, 51, 55),
+            (TokenClass::Verb, 56, 59),
+            (TokenClass::Identifier, 60, 64),
+            (TokenClass::Comment, 65, 71),
+        ]);
+    }
+
+    #[test]
+    fn tokens_as_json() {
+        let mut source = b"baba IS not you".to_vec();
+        let (tokens, spans, identifiers) = tokenize(None, Some(&mut source), false).unwrap();
+        assert_eq!(
+            tokens_json(&tokens, &spans, &identifiers),
+            concat!(
+                "{\"version\": 1, \"tokens\": [",
+                "{\"kind\": \"identifier\", \"text\": \"baba\", \"id\": 3, \"start\": 0, \"end\": 4}, ",
+                "{\"kind\": \"verb\", \"text\": \"is\", \"id\": null, \"start\": 5, \"end\": 7}, ",
+                "{\"kind\": \"not\", \"text\": \"not\", \"id\": null, \"start\": 8, \"end\": 11}, ",
+                "{\"kind\": \"property\", \"text\": \"you\", \"id\": null, \"start\": 12, \"end\": 15}",
+                "]}"
+            )
+        );
+    }
+
+    #[test]
+    fn json_escapes() {
+        assert_eq!(escape_json("a\"b\\c\n"), "a\\\"b\\\\c\\u000a");
+    }
+
+    #[test]
+    fn control_bytes() {
+        assert!(is_unexpected_control(0x00));
+        assert!(is_unexpected_control(0x0b));
+        assert!(is_unexpected_control(0x0c));
+        assert!(is_unexpected_control(0x7f));
+        assert!(!is_unexpected_control(b'\t'));
+        assert!(!is_unexpected_control(b'\n'));
+        assert!(!is_unexpected_control(b'\r'));
+        assert!(!is_unexpected_control(b' '));
+        assert!(!is_unexpected_control(b'a'));
+    }
+
+    #[test]
+    fn control_bytes_lenient() {
+        let mut source = b"baba\x0cis\x00you // \x0b comment".to_vec();
+        let (tokens, _spans, _identifiers) = tokenize(None, Some(&mut source), true).unwrap();
+        assert_eq!(tokens.len(), 3);
+    }
+
+    #[test]
+    fn tokenize_literal() {
+        let mut source = b"x is \"A b\\\"\"// \"comment\"\ny is\"x\"keke".to_vec();
+        let (tokens, spans, identifiers) = tokenize(None, Some(&mut source), false).unwrap();
+        assert_eq!(tokens, vec![
+            Token::Noun(Noun::Identifier(3)),
+            Token::Verb(Verb::Is),
+            Token::Noun(Noun::Literal(4)),
+            Token::Noun(Noun::Identifier(5)),
+            Token::Verb(Verb::Is),
+            Token::Noun(Noun::Literal(6)),
+            Token::Noun(Noun::Identifier(7)),
+        ]);
+        assert_eq!(spans, vec![(0, 1), (2, 4), (5, 12), (25, 26), (27, 29), (29, 32), (32, 36)]);
+        assert_eq!(identifiers[&4], "\"A b\\\"\"");
+    }
+
+    #[test]
+    fn literal_escapes() {
+        assert_eq!(decode_literal(b"AB"), Ok(b"AB".to_vec()));
+        assert_eq!(decode_literal(b"a\\nb"), Ok(b"a\nb".to_vec()));
+        assert_eq!(decode_literal(b"\\t"), Ok(b"\t".to_vec()));
+        assert_eq!(decode_literal(b"\\\\"), Ok(b"\\".to_vec()));
+        assert_eq!(decode_literal(b"\\\""), Ok(b"\"".to_vec()));
+        assert_eq!(decode_literal(b"\\x00\\x7F\\xff"), Ok(vec![0x00, 0x7f, 0xff]));
+    }
+
+    #[test]
+    fn literal_invalid_escapes() {
+        assert!(decode_literal(b"\\q").is_err());
+        assert!(decode_literal(b"\\0").is_err());
+        assert!(decode_literal(b"\\x").is_err());
+        assert!(decode_literal(b"\\x4").is_err());
+        assert!(decode_literal(b"\\xg0").is_err());
+        assert!(decode_literal(b"\\x+f").is_err());
+        assert!(decode_literal(b"a\\").is_err());
+    }
+}
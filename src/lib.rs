@@ -0,0 +1,131 @@
+//! Babalang interpreter, as a library.
+//!
+//! `run` executes a program in one call, and `run_capture` does the same
+//! with in-memory input and output. The stages of the interpreter
+//! (lexing, statement parsing, AST parsing and execution) are also
+//! available separately through their modules.
+
+pub mod error_handler;
+pub mod token;
+pub mod lexer;
+pub mod statement;
+pub mod statement_parser;
+pub mod instruction;
+pub mod ast;
+pub mod interpreter;
+pub mod bytecode;
+pub mod debugger;
+pub mod object;
+pub mod symbols;
+pub mod stats;
+pub mod bast;
+pub mod format;
+pub mod lint;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+pub use error_handler::BabaError;
+pub use format::format_source;
+
+use std::collections::HashMap;
+use std::io::{stdin, stdout};
+
+use instruction::Instruction;
+use statement::Statement;
+
+/// Executes a Babalang program given as source code.
+///
+/// Returns the first error raised by any stage of the interpreter.
+/// WIN and DEFEAT stop the program without erroring.
+pub fn run(source: &str) -> Result<(), BabaError> {
+    run_bytes(source.as_bytes())
+}
+
+/// Executes a Babalang program given as raw bytes. See `run`.
+pub fn run_bytes(source: &[u8]) -> Result<(), BabaError> {
+    let (ast, identifiers) = compile(source)?;
+    interpreter::Interpreter::new(stdin(), stdout()).exec(&ast, &identifiers, &interpreter::Options::default())?;
+    Ok(())
+}
+
+/// Executes a Babalang program given as source code, reading the input of WORD
+/// from `input` instead of stdin.
+///
+/// Returns everything the program wrote with TEXT and PLAY. See `run`.
+pub fn run_capture(source: &str, input: &[u8]) -> Result<Vec<u8>, BabaError> {
+    let (output, result) = run_in_memory(source.as_bytes(), input);
+    result?;
+    Ok(output)
+}
+
+/// Executes a Babalang program given as source code, reading the input of WORD
+/// from `input`. This is the entry point of WebAssembly builds.
+///
+/// Returns everything the program wrote with TEXT and PLAY, followed by the
+/// diagnostic of the error that stopped it, if any.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen::prelude::wasm_bindgen]
+pub fn run_string(source: &str, input: &str) -> String {
+    let (output, result) = run_in_memory(source.as_bytes(), input.as_bytes());
+    let mut out = String::from_utf8_lossy(&output).into_owned();
+    if let Err(e) = result {
+        out.push_str(&e.report);
+    }
+    out
+}
+
+/// Executes a program with in-memory input and output, returning everything
+/// it wrote along with its result, even if it was stopped by an error.
+fn run_in_memory(source: &[u8], input: &[u8]) -> (Vec<u8>, Result<interpreter::Scope, BabaError>) {
+    let mut interpreter = interpreter::Interpreter::new(input, Vec::new());
+    let result = compile(source)
+        .and_then(|(ast, identifiers)| interpreter.exec(&ast, &identifiers, &interpreter::Options::default()));
+    (interpreter.into_output(), result)
+}
+
+/// Lexes and parses a program into its AST and identifiers.
+pub(crate) fn compile(source: &[u8]) -> Result<(Vec<Instruction>, HashMap<usize, String>), BabaError> {
+    let (statements, identifiers) = parse_statements(source)?;
+    Ok((ast::parse(&statements, &identifiers)?, identifiers))
+}
+
+/// Lexes and parses a program into its statements and identifiers. See `compile`.
+pub(crate) fn parse_statements(source: &[u8]) -> Result<(Vec<Statement>, HashMap<usize, String>), BabaError> {
+    let (tokens, spans, identifiers) = lexer::tokenize(None, Some(&mut source.to_vec()), false)?;
+    Ok((statement_parser::parse(&tokens, &spans, &identifiers)?, identifiers))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{run, run_bytes, run_capture};
+    use crate::error_handler::ErrorType;
+
+    #[test]
+    fn run_programs() {
+        assert_eq!(run("baba is you baba is move and win"), Ok(()));
+        assert_eq!(run_bytes(b"baba is you // comment\n"), Ok(()));
+        assert_eq!(run("baba is").unwrap_err().kind, ErrorType::StatementParserError);
+        assert_eq!(run("baba is you baba is shift").unwrap_err().kind, ErrorType::TypeError);
+    }
+
+    #[test]
+    fn capture_output() {
+        let hello = include_str!("../examples/hello_world.baba");
+        assert_eq!(run_capture(hello, b""), Ok(b"Hello, world!\n".to_vec()));
+        // Stops after WIN, like the interpreter
+        let echo = "baba is group baba is word baba is text keke is you keke is win baba is text";
+        assert_eq!(run_capture(echo, b"hi\nthere\n"), Ok(b"hi\n".to_vec()));
+        assert_eq!(run_capture("baba is", b"").unwrap_err().kind, ErrorType::StatementParserError);
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn run_string_output() {
+        use crate::run_string;
+        let echo = "baba is group baba is word baba is text";
+        assert_eq!(run_string(echo, "hi\n"), "hi\n");
+        // Output is kept when an error stops the program
+        let failed = run_string("baba is you baba is move baba is text baba is shift", "");
+        assert!(failed.starts_with("\u{1}TypeError"), "{:?}", failed);
+    }
+}
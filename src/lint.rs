@@ -0,0 +1,161 @@
+use crate::instruction::{Instruction, Simple, Level, is_reserved};
+use crate::statement::Target;
+use crate::token::Noun;
+use crate::error_handler::{ErrorType, warn};
+
+use std::collections::HashMap;
+
+/// Finds the objects that are defined but never used, in order of definition.
+///
+/// An object is defined by IS YOU, IS YOU2, IS GROUP, IS EMPTY, by being
+/// assigned a value or string literal, by MIMIC, or as a LEVEL or IMAGE. It is
+/// used if any other instruction, at any depth, refers to it, including as
+/// a condition. TELE loops and LEVEL arguments aren't checked.
+pub fn unused(ast: &[Instruction]) -> Vec<usize> {
+    let mut definitions = Vec::new();
+    let mut mentions = HashMap::new();
+    walk(ast, &mut definitions, &mut mentions);
+    let mut out = Vec::new();
+    for id in definitions {
+        // Each definition mentions the object once
+        if !is_reserved(id) && mentions.get(&id) == Some(&1) && !out.contains(&id) {
+            out.push(id);
+        }
+    }
+    out
+}
+
+/// Warns about every object that is defined but never used. See `unused`.
+pub fn lint(ast: &[Instruction], identifiers: &HashMap<usize, String>) {
+    for id in unused(ast) {
+        warn(
+            ErrorType::InstructionParserError,
+            format!("Object {} is defined but never used. Is its name misspelled?", id),
+            Some((&[id], identifiers))
+        );
+    }
+}
+
+/// Records the definitions in the instructions, along with the number of
+/// times each identifier is mentioned.
+fn walk(instructions: &[Instruction], definitions: &mut Vec<usize>, mentions: &mut HashMap<usize, usize>) {
+    for instruction in instructions {
+        match instruction {
+            Instruction::Simple(simple) => walk_simple(simple, definitions, mentions),
+            Instruction::Complex(complex) => {
+                walk_simple(&complex.instruction, definitions, mentions);
+                for conditions in &complex.conditions {
+                    for (target, _) in &conditions.targets {
+                        if let Target::Noun(Noun::Identifier(id)) = target {
+                            mention(*id, mentions);
+                        }
+                    }
+                }
+            },
+            Instruction::Tele(tele) => {
+                mention(tele.identifier, mentions);
+                walk(&tele.instructions, definitions, mentions);
+            },
+            Instruction::Level(level) => {
+                definitions.push(level.identifier);
+                mention(level.identifier, mentions);
+                walk_level(level, definitions, mentions);
+            },
+            Instruction::Image(image) => {
+                definitions.push(image.identifier);
+                mention(image.identifier, mentions);
+                for attribute in &image.attributes {
+                    mention(*attribute, mentions);
+                }
+                walk_level(&image.constructor, definitions, mentions);
+            },
+            _ => ()
+        }
+    }
+}
+
+/// Records the arguments and body of a LEVEL. See `walk`.
+fn walk_level(level: &Level, definitions: &mut Vec<usize>, mentions: &mut HashMap<usize, usize>) {
+    for argument in &level.arguments {
+        mention(*argument, mentions);
+    }
+    walk(&level.instructions, definitions, mentions);
+}
+
+/// Records the definition made by a simple instruction, if any, along with
+/// the identifiers it mentions. See `walk`.
+fn walk_simple(simple: &Simple, definitions: &mut Vec<usize>, mentions: &mut HashMap<usize, usize>) {
+    match simple {
+        Simple::InitYou(id, _) | Simple::InitYou2(id, _) | Simple::InitGroup(id, _)
+        | Simple::IsEmpty(id) | Simple::IsLiteral(id, _) | Simple::IsNumber(id, _, _) => definitions.push(*id),
+        Simple::IsValue(id, _, _) | Simple::MimicReference(id, _, _) => definitions.push(*id),
+        _ => ()
+    }
+    match simple {
+        Simple::AllWin | Simple::AllDefeat
+        | Simple::AllMove(_) | Simple::AllTurn(_) | Simple::AllFall(_)
+        | Simple::AllMore(_) | Simple::AllLess(_) | Simple::AllRight(_)
+        | Simple::AllUp(_) | Simple::AllLeft(_) | Simple::AllDown(_)
+        | Simple::AllChill(_) => (),
+        Simple::InitYou(id, _) | Simple::InitYou2(id, _) | Simple::InitGroup(id, _)
+        | Simple::Win(id) | Simple::Defeat(id) | Simple::Sleep(id)
+        | Simple::Text(id) | Simple::Word(id) | Simple::Line(id) | Simple::IsEmpty(id)
+        | Simple::Move(id, _) | Simple::Turn(id, _) | Simple::Fall(id, _)
+        | Simple::More(id, _) | Simple::Less(id, _) | Simple::Right(id, _)
+        | Simple::Up(id, _) | Simple::Left(id, _) | Simple::Down(id, _)
+        | Simple::Chill(id, _) | Simple::Both(id, _) | Simple::Reset(id) | Simple::Shift(id, _) | Simple::Sink(id)
+        | Simple::Swap(id) | Simple::Power(id, _)
+        | Simple::IsLiteral(id, _) | Simple::IsNumber(id, _, _) | Simple::HasLiteral(id, _)
+        | Simple::HasNumber(id, _) => mention(*id, mentions),
+        Simple::IsValue(id, target, _) | Simple::MimicReference(id, target, _)
+        | Simple::HasValue(id, target) | Simple::MakeValue(id, target)
+        | Simple::FearTele(id, target) | Simple::FollowAttribute(id, target)
+        | Simple::EatValue(id, target) | Simple::FeelType(id, target)
+        | Simple::EqualValue(id, target) | Simple::WriteValue(id, target)
+        | Simple::TrackIndex(id, target) | Simple::SpellDigits(id, target)
+        | Simple::Play(id, target) | Simple::FeedLevel(id, target) | Simple::PeekValue(id, target) => {
+            mention(*id, mentions);
+            mention(*target, mentions);
+        },
+        Simple::IsSum(id, nouns, _) => {
+            mention(*id, mentions);
+            for noun in nouns {
+                if let Noun::Identifier(target) = noun {
+                    mention(*target, mentions);
+                }
+            }
+        }
+    }
+}
+
+/// Counts a mention of an identifier.
+fn mention(id: usize, mentions: &mut HashMap<usize, usize>) {
+    *mentions.entry(id).or_insert(0) += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lint::unused;
+    use crate::compile;
+
+    /// Returns the names of the unused objects of a program.
+    fn unused_names(source: &str) -> Vec<String> {
+        let (instructions, identifiers) = compile(source.as_bytes()).unwrap();
+        unused(&instructions).into_iter().map(|id| identifiers[&id].clone()).collect()
+    }
+
+    #[test]
+    fn unused_objects() {
+        assert_eq!(unused_names("baba is you keke is you baba is move kek is group"), vec!["keke", "kek"]);
+        // Uses in conditions and nested scopes count
+        assert_eq!(unused_names("
+            baba is you keke is you g is group
+            loop is tele
+                f is level f has x baba on keke is move f is done
+                g is f
+            loop is done
+        "), Vec::<String>::new());
+        // Loops and arguments aren't checked, unlike values and LEVELs
+        assert_eq!(unused_names("loop is tele f is level f has x f is done loop is done copy is loop"), vec!["f", "copy"]);
+    }
+}
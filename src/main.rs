@@ -1,61 +1,423 @@
-mod error_handler;
-mod token;
-mod lexer;
-mod statement;
-mod statement_parser;
-mod instruction;
-mod ast;
-mod interpreter;
-mod object;
-
 use std::env;
+use std::fs;
+use std::collections::HashMap;
+use std::io::{stdin, stdout, BufRead, BufReader, IsTerminal, Read, Stdin, Stdout, Write};
+use std::process::exit;
+use std::time::{Duration, Instant};
+
+use babalang::{error_handler, lexer, statement_parser, ast, interpreter, debugger, symbols, stats, bast, lint, bytecode, BabaError};
+use babalang::instruction::Instruction;
 
 /// Babalang interpreter
 fn main() -> std::io::Result<()> {
-    // Get path of source file
+    // Get path of source file, along with any flags
     let mut raw_content = None;
-    let file_path = match env::args().skip(1).next() {
-        Some(x) => {
-            let option = String::from("-c");
-            if x == option {
-                raw_content = env::args().skip(2).next();
-                None
-            }
-            else {
-                Some(x)
-            }
+    let mut file_path = None;
+    let mut options = interpreter::Options::default();
+    let mut time = false;
+    let mut hosted = false;
+    let mut print_symbols = false;
+    let mut minify = false;
+    let mut classify = false;
+    let mut tokens_json = false;
+    let mut dump_tokens = false;
+    let mut dump_ast = false;
+    let mut dump_statements = false;
+    let mut debug = false;
+    let mut debug_input = None;
+    let mut profile = false;
+    let mut fmt = false;
+    let mut lint = false;
+    let mut vm = false;
+    let mut lenient = false;
+    let mut ast_stats = false;
+    let mut dump_state = false;
+    let mut compile = false;
+    let mut output_path = None;
+    let mut run_path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-c" => raw_content = args.next().map(String::into_bytes),
+            "--strict" => options.strict = true,
+            "--strict-undef" => options.strict_undef = true,
+            "--no-exit-on-win" => hosted = true,
+            "--trace" => options.trace = true,
+            "--time" => time = true,
+            "--symbols" => print_symbols = true,
+            "--minify" => minify = true,
+            "--classify" => classify = true,
+            "--emit-tokens-json" => tokens_json = true,
+            "--dump-tokens" => dump_tokens = true,
+            "--dump-ast" => dump_ast = true,
+            "--dump-statements" => dump_statements = true,
+            "--debug" => debug = true,
+            "--debug-input" => debug_input = args.next(),
+            "--profile" => profile = true,
+            "--fmt" => fmt = true,
+            "--lint" => lint = true,
+            "--bytecode" => vm = true,
+            "--lenient" => lenient = true,
+            "--ast-stats" => ast_stats = true,
+            "--dump-state" => dump_state = true,
+            "--max-line" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(max) => options.max_line = Some(max),
+                None => fail(error_handler::error_str(
+                    error_handler::ErrorType::ArgumentError,
+                    "--max-line must be followed by a number of bytes"
+                ))
+            },
+            "--delimiter" => match args.next().map(String::into_bytes).as_deref() {
+                Some(&[byte]) => options.delimiter = Some(byte),
+                _ => fail(error_handler::error_str(
+                    error_handler::ErrorType::ArgumentError,
+                    "--delimiter must be followed by a single byte"
+                ))
+            },
+            "--width" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(width @ 1..=32) => options.width = Some(width),
+                _ => fail(error_handler::error_str(
+                    error_handler::ErrorType::ArgumentError,
+                    "--width must be followed by a number of bits between 1 and 32"
+                ))
+            },
+            "-q" | "--quiet" => error_handler::set_quiet(true),
+            "--error-format=human" => error_handler::set_json(false),
+            "--error-format=json" => error_handler::set_json(true),
+            "--backtrace" => error_handler::set_backtrace(true),
+            "--compile" => compile = true,
+            "-o" => output_path = args.next(),
+            "--run" => run_path = args.next(),
+            "--explain" => {
+                // Explain an error type instead of executing anything
+                let name = args.next().unwrap_or_default();
+                match error_handler::find_error_type(&name) {
+                    Some(error_type) => {
+                        println!("{:?} ({})\n", error_type, error_handler::code(&error_type));
+                        println!("{}", error_handler::explain(&error_type));
+                    },
+                    None => fail(error_handler::error(
+                        error_handler::ErrorType::ArgumentError,
+                        format!("Unknown error type `{}`", name),
+                        None
+                    ))
+                }
+                return Ok(())
+            },
+            _ => file_path = Some(arg)
         }
-        None => {
-            error_handler::throw_error_str(
+    }
+    if let Some(path) = run_path {
+        // Execute a precompiled AST, skipping the parsing stages
+        let start = Instant::now();
+        let bytes = fs::read(&path).unwrap_or_else(|_| fail(error_handler::error_str(
+            error_handler::ErrorType::FileError,
+            &format!("Could not read file {}", path)
+        )));
+        let (ast, identifiers) = bast::decode(&bytes).unwrap_or_else(|e| fail(error_handler::error_str(
+            error_handler::ErrorType::FileError,
+            &format!("Invalid AST file {}: {}", path, e)
+        )));
+        let loaded = Instant::now();
+        let mut interpreter = interpreter::Interpreter::new(stdin(), stdout());
+        if profile {
+            interpreter.enable_profile();
+        }
+        let scope = or_exit(interpreter.exec(&ast, &identifiers, &options));
+        let executed = Instant::now();
+        report_profile(&interpreter);
+        exit_unless_hosted(&scope, hosted);
+        if dump_state {
+            println!("{}", interpreter::dump_state(&scope, &identifiers));
+        }
+        if time {
+            report_times(&[
+                ("load", loaded - start),
+                ("exec", executed - loaded),
+                ("total", executed - start),
+            ]);
+        }
+        if let Some(code) = scope.exit_code {
+            exit(code);
+        }
+        return Ok(())
+    }
+
+    // Read the program from stdin given `-`, or given nothing while stdin is piped
+    let piped = raw_content.is_none() && file_path.is_none() && !stdin().is_terminal();
+    if piped || file_path.as_deref() == Some("-") {
+        let mut bytes = Vec::new();
+        if stdin().read_to_end(&mut bytes).is_err() {
+            fail(error_handler::error_str(
                 error_handler::ErrorType::FileError,
-                "File not provided"
-            );
-            panic!() // necessary for the match arms to match 
+                "Could not read program from stdin"
+            ));
         }
-    };
+        raw_content = Some(bytes);
+        file_path = None;
+    }
+
+    if let (None, None) = (&raw_content, &file_path) {
+        fail(error_handler::error_str(
+            error_handler::ErrorType::FileError,
+            "File not provided"
+        ));
+    }
 
-    let (tokens, identifiers) = if let Some(content) = raw_content {
-        let mut raw_bytes = content.bytes().collect::<Vec<u8>>();
-        lexer::tokenize(None, Some(&mut raw_bytes))
+    if fmt {
+        // Format the program instead of executing it, in place if it is a file
+        let source = or_exit(lexer::read_source(file_path.clone(), raw_content.as_mut()));
+        let formatted = or_exit(babalang::format_source(&String::from_utf8_lossy(&source)));
+        match file_path {
+            Some(path) => if fs::write(&path, formatted).is_err() {
+                fail(error_handler::error_str(
+                    error_handler::ErrorType::FileError,
+                    &format!("Could not write file {}", path)
+                ));
+            },
+            None => print!("{}", formatted)
+        }
+        return Ok(())
+    }
+
+    if classify {
+        // Print the syntactic class of each token and comment instead of executing
+        let source = or_exit(lexer::read_source(file_path, raw_content.as_mut()));
+        for (class, start, end) in or_exit(lexer::classify(&source)) {
+            println!("{}\t{}..{}", class, start, end);
+        }
+        return Ok(())
+    }
+
+    let start = Instant::now();
+    let (tokens, spans, identifiers) = or_exit(if let Some(mut raw_bytes) = raw_content {
+        lexer::tokenize(None, Some(&mut raw_bytes), lenient)
     } 
     else {
-        lexer::tokenize(file_path, None)
-    };
+        lexer::tokenize(file_path, None, lenient)
+    });
     // Tokenize the source file and return a vector of tokens
     // println!("Successfully tokenized program at `{}`", file_path);
+    let lexed = Instant::now();
+
+    if tokens_json {
+        // Print the tokens as JSON instead of executing the program
+        println!("{}", lexer::tokens_json(&tokens, &spans, &identifiers));
+        return Ok(())
+    }
 
+    if dump_tokens {
+        // Print each token instead of executing the program
+        println!("{}", lexer::dump_tokens(&tokens, &identifiers));
+        return Ok(())
+    }
+
+    if minify {
+        // Print the minified program instead of executing it
+        println!("{}", lexer::minify(&tokens, &identifiers));
+        return Ok(())
+    }
 
     // A vector of Statements (e.g. BABA IS YOU)
-    let statements = statement_parser::parse(&tokens, &identifiers);
+    let statements = or_exit(statement_parser::parse(&tokens, &spans, &identifiers));
     // println!("Successfully parsed program into statements");
+    let parsed_statements = Instant::now();
+
+    if dump_statements {
+        // Print each statement instead of executing the program
+        println!("{}", statement_parser::dump(&statements, &identifiers));
+        return Ok(())
+    }
 
     // A vector of Instructions (e.g. [initialize BABA as YOU])
-    let ast = ast::parse(&statements, &identifiers);
+    let ast = or_exit(ast::parse(&statements, &identifiers));
     // println!("Successfully parsed statements into an AST");
+    let parsed_ast = Instant::now();
+
+    if lint {
+        // Warn about unused objects instead of executing the program
+        lint::lint(&ast, &identifiers);
+        return Ok(())
+    }
+
+    if dump_ast {
+        // Print the instruction tree instead of executing the program
+        println!("{}", ast::dump(&ast, &identifiers));
+        return Ok(())
+    }
+
+    if print_symbols {
+        // Print the symbol table instead of executing the program
+        for symbol in symbols::symbols(&ast, &statements, &identifiers) {
+            match symbol.location {
+                Some((start, end)) => println!("{}\t{}\t{}..{}", symbol.name, symbol.kind, start, end),
+                None => println!("{}\t{}\t-", symbol.name, symbol.kind)
+            }
+        }
+        return Ok(())
+    }
+
+    if compile {
+        // Write the AST to a file instead of executing the program
+        let path = output_path.unwrap_or_else(|| String::from("out.bast"));
+        if fs::write(&path, bast::encode(&ast, &identifiers)).is_err() {
+            fail(error_handler::error_str(
+                error_handler::ErrorType::FileError,
+                &format!("Could not write file {}", path)
+            ));
+        }
+        return Ok(())
+    }
+
+    if ast_stats {
+        // Print static metrics of the program instead of executing it
+        println!("{}", stats::stats(&ast));
+        return Ok(())
+    }
     
-    interpreter::exec(&ast, &identifiers);
+    let mut interpreter = interpreter::Interpreter::new(stdin(), stdout());
+    if profile {
+        interpreter.enable_profile();
+    }
+    let scope = if debug {
+        let commands = debug_commands(debug_input, &ast);
+        let scope = or_exit(run_debugger(&mut interpreter, commands, &ast, &identifiers, &options));
+        report_profile(&interpreter);
+        scope
+    }
+    else if vm {
+        let ops = bytecode::compile(&ast);
+        let scope = or_exit(bytecode::run_bytecode(&mut interpreter, &ops, &identifiers, &options));
+        report_profile(&interpreter);
+        scope
+    }
+    else {
+        let scope = or_exit(interpreter.exec(&ast, &identifiers, &options));
+        report_profile(&interpreter);
+        scope
+    };
     // println!("Successfully executed AST");
+    let executed = Instant::now();
+    exit_unless_hosted(&scope, hosted);
+
+    if dump_state {
+        // Only reached if the program wasn't exited via WIN or DEFEAT,
+        // unless --no-exit-on-win is given
+        println!("{}", interpreter::dump_state(&scope, &identifiers));
+    }
+
+    if time {
+        report_times(&[
+            ("lex", lexed - start),
+            ("parse-statements", parsed_statements - lexed),
+            ("parse-ast", parsed_ast - parsed_statements),
+            ("exec", executed - parsed_ast),
+            ("total", executed - start),
+        ]);
+    }
+
+    // Exit with the code of WIN or DEFEAT, now that reports are done
+    if let Some(code) = scope.exit_code {
+        exit(code);
+    }
 
     // Done
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Reports an error and exits.
+fn fail(error: BabaError) -> ! {
+    error_handler::report(&error);
+    exit(1)
+}
+
+/// Returns the output of a stage of the interpreter, 
+/// or reports its error and exits if it failed.
+fn or_exit<T>(result: Result<T, BabaError>) -> T {
+    result.unwrap_or_else(|error| fail(error))
+}
+
+/// Exits right away with the code of WIN or DEFEAT, if the program was stopped
+/// by one. When `hosted` (`--no-exit-on-win`), the program is still reported 
+/// on before exiting. The interpreter itself never exits: see `Scope::exit_code`.
+fn exit_unless_hosted(scope: &interpreter::Scope, hosted: bool) {
+    if let (Some(code), false) = (scope.exit_code, hosted) {
+        exit(code);
+    }
+}
+
+/// Opens the source of the commands of `--debug`: the file given with 
+/// `--debug-input` (e.g. `/dev/tty`), or else stdin. 
+/// 
+/// Exits with an error if the commands would be read from stdin while the 
+/// program reads its own input from it with WORD or LINE.
+fn debug_commands(path: Option<String>, ast: &[Instruction]) -> Box<dyn BufRead> {
+    match path {
+        Some(path) => match fs::File::open(&path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(_) => fail(error_handler::error_str(
+                error_handler::ErrorType::FileError,
+                &format!("Could not read file {}", path)
+            ))
+        },
+        None => {
+            let kinds = stats::stats(ast).kinds;
+            if kinds.contains_key("Word") || kinds.contains_key("Line") {
+                fail(error_handler::error_str(
+                    error_handler::ErrorType::ArgumentError,
+                    "--debug reads commands from stdin, which the program reads with WORD or LINE. \
+                    Give another source of commands with --debug-input"
+                ));
+            }
+            Box::new(stdin().lock())
+        }
+    }
+}
+
+/// Executes a program one instruction at a time, prompting for commands on stderr.
+/// 
+/// `step` (or an empty line) executes the next instruction, `continue` runs the
+/// program to completion and `print NAME` shows the value of an object.
+fn run_debugger(
+    interpreter: &mut interpreter::Interpreter<Stdin, Stdout>,
+    mut commands: Box<dyn BufRead>,
+    ast: &[Instruction], 
+    identifiers: &HashMap<usize, String>, 
+    options: &interpreter::Options
+) -> Result<interpreter::Scope, BabaError> {
+    let mut debugger = debugger::Debugger::new(interpreter, ast, identifiers, options);
+    let mut paused = debugger.step();
+    while let Some(snapshot) = &paused {
+        let scope = snapshot.scope.as_ref().map_or("program", |(_, name)| name.as_str());
+        let instruction = ast::dump(std::slice::from_ref(&snapshot.instruction), identifiers);
+        // Only the first line of a TELE, LEVEL or IMAGE
+        eprint!("[{}] {}\n(debug) ", scope, instruction.lines().next().unwrap_or_default());
+        let mut command = String::new();
+        if commands.read_line(&mut command).unwrap_or(0) == 0 {
+            // The end of input continues the program
+            break
+        }
+        match command.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            [] | ["step"] | ["s"] => paused = debugger.step(),
+            ["continue"] | ["c"] => break,
+            ["print", name] | ["p", name] => eprintln!("{}", snapshot.find(name).unwrap_or("not defined")),
+            _ => eprintln!("Commands: step, continue, print NAME")
+        }
+    }
+    debugger.finish()
+}
+
+/// Prints the number of times each kind of instruction was executed to stderr,
+/// most executed first, if profiling was enabled.
+fn report_profile<R: Read, W: Write>(interpreter: &interpreter::Interpreter<R, W>) {
+    for (kind, count) in interpreter.profile().unwrap_or_default() {
+        error_handler::log(&format!("{:>16}: {}\n", kind, count));
+    }
+}
+
+/// Prints the duration of each pipeline stage to stderr, unless silenced.
+fn report_times(stages: &[(&str, Duration)]) {
+    for (stage, duration) in stages {
+        error_handler::log(&format!("{:>16}: {:?}\n", stage, duration));
+    }
+}
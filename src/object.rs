@@ -3,16 +3,17 @@ use crate::instruction::Instruction;
 use std::fmt::{Display, Formatter, Result};
 use std::collections::HashMap;
 use std::cmp::Ordering;
+use std::sync::Arc;
 
 /// The base object for all Babalang objects
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, Debug)]
 pub struct Object {
     pub reference_count: usize,
     pub obj_type: Type
 }
 
 /// The type of a Babalang object
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, Debug)]
 pub enum Type {
     Empty(Empty),
     Reference(Reference),
@@ -24,27 +25,111 @@ pub enum Type {
     ImageInstance(ImageInstance)
 }
 
+impl PartialEq for Object {
+    fn eq(&self, other: &Object) -> bool {
+        self.reference_count == other.reference_count && self.obj_type == other.obj_type
+    }
+}
+
+/// Compares nested objects (GROUP elements and IMAGE attributes) with
+/// an explicit stack rather than recursion, so that deeply nested
+/// objects can't overflow the call stack.
+impl PartialEq for Type {
+    fn eq(&self, other: &Type) -> bool {
+        let mut pending = vec![(self, other)];
+        while let Some(pair) = pending.pop() {
+            let equal = match pair {
+                (Type::Empty(_), Type::Empty(_)) => true,
+                (Type::Reference(a), Type::Reference(b)) => a == b,
+                (Type::You(a), Type::You(b)) => a == b,
+                (Type::You2(a), Type::You2(b)) => a == b,
+                (Type::Level(a), Type::Level(b)) => a == b,
+                (Type::Group(a), Type::Group(b)) => {
+                    a.data.len() == b.data.len()
+                    && push_objects(&mut pending, a.data.iter().zip(b.data.iter()))
+                },
+                (Type::Image(a), Type::Image(b)) => {
+                    a.constructor == b.constructor
+                    && push_attributes(&mut pending, &a.attributes, &b.attributes)
+                },
+                (Type::ImageInstance(a), Type::ImageInstance(b)) => {
+                    a.class == b.class
+                    && push_attributes(&mut pending, &a.attributes, &b.attributes)
+                },
+                _ => false
+            };
+            if !equal {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Queues the types of pairs of objects to be compared, 
+/// returning false if their reference counts already differ.
+fn push_objects<'a>(
+    pending: &mut Vec<(&'a Type, &'a Type)>, 
+    pairs: impl Iterator<Item = (&'a Object, &'a Object)>
+) -> bool {
+    for (a, b) in pairs {
+        if a.reference_count != b.reference_count {
+            return false;
+        }
+        pending.push((&a.obj_type, &b.obj_type));
+    }
+    true
+}
+
+/// Queues the values of two sets of attributes to be compared,
+/// returning false if their names or initialization differ.
+fn push_attributes<'a>(
+    pending: &mut Vec<(&'a Type, &'a Type)>, 
+    a: &'a HashMap<usize, Option<Object>>, 
+    b: &'a HashMap<usize, Option<Object>>
+) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut pairs = Vec::new();
+    for (name, value) in a {
+        match (value, b.get(name)) {
+            (Some(value), Some(Some(other))) => pairs.push((value, other)),
+            (None, Some(None)) => (),
+            _ => return false
+        }
+    }
+    push_objects(pending, pairs.into_iter())
+}
+
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Empty {}
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Reference {
     // virtual pointer
-    pub pointer: usize
+    pub pointer: usize,
+    // weak references don't keep their target alive
+    pub weak: bool
 }
 
 #[derive(Clone, Copy, Debug)]
 pub struct You {
-    pub x: u8,
-    pub y: u8,
-    pub dir: u8 // only lowest 2 bits are used
+    // wrapped to the width of the interpreter, 8 bits by default
+    pub x: u32,
+    pub y: u32,
+    pub dir: u8, // only lowest 2 bits are used
+    // sleeping objects don't move or turn
+    pub sleeping: bool
 }
 
 #[derive(Clone, Copy, Debug)]
 pub struct You2 {
     pub x: u16,
     pub y: u16,
-    pub dir: u8 // only lowest 2 bits used
+    pub dir: u8, // only lowest 2 bits used
+    // sleeping objects don't move or turn
+    pub sleeping: bool
 }
 
 impl PartialEq<You> for You {
@@ -55,13 +140,13 @@ impl PartialEq<You> for You {
 
 impl PartialEq<You2> for You {
     fn eq(&self, other: &You2) -> bool {
-        (self.x as u16) == other.x && (self.y as u16) == other.y
+        self.x == other.x as u32 && self.y == other.y as u32
     }
 }
 
 impl PartialEq<You> for You2 {
     fn eq(&self, other: &You) -> bool {
-        self.x == other.x as u16 && self.y == other.y as u16
+        self.x as u32 == other.x && self.y as u32 == other.y
     }
 }
 
@@ -97,16 +182,16 @@ impl PartialOrd<You2> for You {
     fn partial_cmp(&self, other: &You2) -> Option<Ordering> {
         match self.dir {
             0 => {
-                (self.x as u16).partial_cmp(&other.x)
+                self.x.partial_cmp(&(other.x as u32))
             },
             1 => {
-                (self.y as u16).partial_cmp(&other.y)
+                self.y.partial_cmp(&(other.y as u32))
             },
             2 => {
-                other.x.partial_cmp(&(self.x as u16))
+                (other.x as u32).partial_cmp(&self.x)
             },
             3 => {
-                other.y.partial_cmp(&(self.y as u16))
+                (other.y as u32).partial_cmp(&self.y)
             },
             _ => {
                 None
@@ -119,16 +204,16 @@ impl PartialOrd<You> for You2 {
     fn partial_cmp(&self, other: &You) -> Option<Ordering> {
         match self.dir {
             0 => {
-                self.x.partial_cmp(&(other.x as u16))
+                (self.x as u32).partial_cmp(&other.x)
             },
             1 => {
-                self.y.partial_cmp(&(other.y as u16))
+                (self.y as u32).partial_cmp(&other.y)
             },
             2 => {
-                (other.x as u16).partial_cmp(&self.x)
+                other.x.partial_cmp(&(self.x as u32))
             },
             3 => {
-                (other.y as u16).partial_cmp(&self.y)
+                other.y.partial_cmp(&(self.y as u32))
             },
             _ => {
                 None
@@ -159,10 +244,15 @@ impl PartialOrd<You2> for You2 {
     }
 }
 
+/// A GROUP of objects.
+///
+/// Copies of a GROUP (e.g. made by IS) share their elements, which are only
+/// copied once one of them is modified, with `Arc::make_mut`. MIMIC aliases
+/// the GROUP itself instead, through a reference.
 #[derive(Clone, Debug)]
 pub struct Group {
     pub index: usize,
-    pub data: Vec<Object>
+    pub data: Arc<Vec<Object>>
 }
 
 impl PartialEq for Group {
@@ -182,12 +272,18 @@ pub struct Level {
     pub identifier: usize,
     pub arguments: Vec<usize>,
     pub parameters: Vec<Object>,
-    pub callback: Vec<Instruction>
+    // The body of the LEVEL, shared with its definition and every copy
+    pub callback: Arc<[Instruction]>,
+    // What the callback returns once it runs to the end:
+    // EMPTY for a LEVEL, or the instance for the constructor of an IMAGE
+    pub returns: usize,
+    // Where the callback starts in a compiled program, if it was defined by one
+    pub entry: Option<usize>
 }
 
 impl PartialEq for Level {
     fn eq(&self, other: &Level) -> bool {
-        self.arguments == other.arguments && self.callback == other.callback
+        self.arguments == other.arguments && self.callback == other.callback && self.returns == other.returns
     }
 }
 
@@ -233,17 +329,278 @@ impl Display for Type {
     }
 }
 
+/// Displays the value of an object in a readable form, 
+/// e.g. `YOU (3, 0) facing right` or `GROUP [YOU (1, 0) facing right]`.
+impl Display for Object {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match &self.obj_type {
+            Type::You(you) => write!(f, "YOU ({}, {}) facing {}", you.x, you.y, direction(you.dir)),
+            Type::You2(you) => write!(f, "YOU2 ({}, {}) facing {}", you.x, you.y, direction(you.dir)),
+            Type::Group(group) => {
+                write!(f, "GROUP [")?;
+                for (i, obj) in group.data.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", obj)?;
+                }
+                write!(f, "]")
+            },
+            Type::Reference(reference) if reference.weak => write!(f, "{} to {} (weak)", self.obj_type, reference.pointer),
+            Type::Reference(reference) => write!(f, "{} to {}", self.obj_type, reference.pointer),
+            Type::Level(level) => write!(f, "LEVEL taking {} arguments", level.arguments.len()),
+            // The instance argument isn't passed explicitly
+            Type::Image(image) => write!(
+                f, "IMAGE with {} attributes, taking {} arguments", 
+                image.attributes.len(), 
+                image.constructor.arguments.len().saturating_sub(1)
+            ),
+            Type::ImageInstance(instance) => write!(f, "{} of {}", self.obj_type, instance.class),
+            Type::Empty(_) => write!(f, "{}", self.obj_type),
+        }
+    }
+}
+
+/// Returns the name of a direction (the lowest 2 bits of `dir`).
+fn direction(dir: u8) -> &'static str {
+    match dir & 3 {
+        0 => "right",
+        1 => "up",
+        2 => "left",
+        _ => "down"
+    }
+}
+
+/// Whether an object counts as true, e.g. for the POWERED prefix:
+///
+/// * YOU and YOU2 are true if the axis they are facing is nonzero.
+///
+/// * GROUP is true if it holds any elements.
+///
+/// * LEVEL, IMAGE and image instances are always true.
+///
+/// * EMPTY, and references whose target doesn't exist, are false.
+pub fn is_truthy(obj: &Object) -> bool {
+    match &obj.obj_type {
+        Type::You(you) => if you.dir & 1 == 0 { you.x != 0 } else { you.y != 0 },
+        Type::You2(you) => if you.dir & 1 == 0 { you.x != 0 } else { you.y != 0 },
+        Type::Group(group) => !group.data.is_empty(),
+        Type::Level(_) | Type::Image(_) | Type::ImageInstance(_) => true,
+        Type::Empty(_) | Type::Reference(_) => false
+    }
+}
+
+/// Returns the value of a YOU or YOU2 along the axis it is facing,
+/// i.e. its x coordinate facing left or right, and its y coordinate otherwise.
+pub fn active_value(obj: &Object) -> Option<u32> {
+    match &obj.obj_type {
+        Type::You(you) => Some(if you.dir & 1 == 0 { you.x } else { you.y }),
+        Type::You2(you) => Some(if you.dir & 1 == 0 { you.x as u32 } else { you.y as u32 }),
+        _ => None
+    }
+}
+
+/// Checks whether `source` holds `target`, i.e. whether `source` is not WITHOUT `target`:
+///
+/// * A GROUP holds its elements.
+///
+/// * An IMAGE, or an instance of one, holds the values of its filled attributes.
+///
+/// * YOU and YOU2 hold a YOU or YOU2 with the same active value (see `active_value`).
+///
+/// Returns None if the objects can't be compared, i.e. if `source` is of any
+/// other type, or if only one of them is a YOU or YOU2.
+pub fn holds_value(source: &Object, target: &Object) -> Option<bool> {
+    let filled = |attributes: &HashMap<usize, Option<Object>>| attributes.values()
+        .any(|attr| attr.as_ref().is_some_and(|attr| attr.obj_type == target.obj_type));
+    match &source.obj_type {
+        Type::Group(group) => Some(group.data.iter().any(|element| element.obj_type == target.obj_type)),
+        Type::Image(image) => Some(filled(&image.attributes)),
+        Type::ImageInstance(instance) => Some(filled(&instance.attributes)),
+        Type::You(_) | Type::You2(_) => Some(active_value(source)? == active_value(target)?),
+        _ => None
+    }
+}
+
+/// Checks whether objects of this type are ordered, i.e. whether they
+/// can be the subject of FACING with another object as its target.
+/// Only YOU, YOU2 and GROUP objects are ordered.
+pub fn is_ordered(obj: &Object) -> bool {
+    matches!(obj.obj_type, Type::You(_) | Type::You2(_) | Type::Group(_))
+}
+
+/// Checks whether `source` is FACING `target`:
+///
+/// * YOU and YOU2 are facing a YOU or YOU2 further along the direction they are facing.
+///
+/// * A GROUP is facing a GROUP with more elements.
+///
+/// Returns None if the objects can't be compared, i.e. if either is not ordered
+/// or if only one of them is a GROUP. In particular, LEVEL and IMAGE objects 
+/// can be compared for equality (e.g. with ON), but not ordered.
+pub fn is_facing(source: &Object, target: &Object) -> Option<bool> {
+    match (&source.obj_type, &target.obj_type) {
+        (Type::You(you), Type::You(other)) => Some(you < other),
+        (Type::You(you), Type::You2(other)) => Some(you < other),
+        (Type::You2(you), Type::You(other)) => Some(you < other),
+        (Type::You2(you), Type::You2(other)) => Some(you < other),
+        (Type::Group(group), Type::Group(other)) => Some(group < other),
+        _ => None
+    }
+}
+
+/// Checks whether `source` is ON `target`:
+///
+/// * YOU and YOU2 are on a YOU or YOU2 at the same coordinates, whatever their directions.
+///
+/// * A GROUP is on a GROUP holding equal elements, in the same order.
+///
+/// * An IMAGE instance is on an instance of the same IMAGE, whatever its attributes.
+///
+/// * Any other object is on an equal object of the same type, e.g. EMPTY is on EMPTY.
+pub fn is_on(source: &Object, target: &Object) -> bool {
+    match (&source.obj_type, &target.obj_type) {
+        (Type::You(you), Type::You(other)) => you == other,
+        (Type::You(you), Type::You2(other)) => you == other,
+        (Type::You2(you), Type::You(other)) => you == other,
+        (Type::You2(you), Type::You2(other)) => you == other,
+        (Type::Group(group), Type::Group(other)) => group == other,
+        (Type::ImageInstance(instance), Type::ImageInstance(other)) => instance.class == other.class,
+        (obj_type, other) => obj_type == other
+    }
+}
+
 pub const EMPTY: Object = Object {
     reference_count: 0,
     obj_type: Type::Empty(Empty {})
 };
 
-pub const LEVEL: Object = Object {
-    reference_count: 0,
-    obj_type: Type::Level(Level {
-        identifier: 1,
-        arguments: Vec::new(),
-        parameters: Vec::new(),
-        callback: Vec::new()
-    })
-};
\ No newline at end of file
+/// Returns the built-in LEVEL object.
+pub fn builtin_level() -> Object {
+    Object {
+        reference_count: 0,
+        obj_type: Type::Level(Level {
+            identifier: 1,
+            arguments: Vec::new(),
+            parameters: Vec::new(),
+            callback: Arc::new([]),
+            returns: 0,
+            entry: None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::object::{Object, Type, You, You2, Group, Reference, EMPTY, builtin_level, is_truthy, is_ordered, is_facing, is_on, active_value};
+    use std::sync::Arc;
+
+    fn object(obj_type: Type) -> Object {
+        Object { reference_count: 0, obj_type }
+    }
+
+    #[test]
+    fn display_objects() {
+        let you = object(Type::You(You { x: 65, y: 2, dir: 1, sleeping: false }));
+        assert_eq!(you.to_string(), "YOU (65, 2) facing up");
+        assert_eq!(object(Type::You2(You2 { x: 300, y: 0, dir: 6, sleeping: false })).to_string(), "YOU2 (300, 0) facing left");
+        assert_eq!(
+            object(Type::Group(Group { index: 0, data: Arc::new(vec![you, EMPTY]) })).to_string(),
+            "GROUP [YOU (65, 2) facing up, EMPTY]"
+        );
+        assert_eq!(object(Type::Group(Group { index: 0, data: Arc::new(vec![]) })).to_string(), "GROUP []");
+        assert_eq!(
+            object(Type::Reference(Reference { pointer: 4, weak: true })).to_string(), 
+            "[REFERENCE] to 4 (weak)"
+        );
+        assert_eq!(builtin_level().to_string(), "LEVEL taking 0 arguments");
+    }
+
+    #[test]
+    fn truthiness() {
+        // The axis being faced decides
+        assert!(is_truthy(&object(Type::You(You { x: 1, y: 0, dir: 0, sleeping: false }))));
+        assert!(!is_truthy(&object(Type::You(You { x: 1, y: 0, dir: 1, sleeping: false }))));
+        assert!(is_truthy(&object(Type::You(You { x: 0, y: 1, dir: 3, sleeping: false }))));
+        assert!(!is_truthy(&object(Type::You2(You2 { x: 0, y: 256, dir: 2, sleeping: false }))));
+        assert!(is_truthy(&object(Type::You2(You2 { x: 256, y: 0, dir: 2, sleeping: false }))));
+        assert!(is_truthy(&object(Type::Group(Group { index: 0, data: Arc::new(vec![EMPTY]) }))));
+        assert!(!is_truthy(&object(Type::Group(Group { index: 0, data: Arc::new(vec![]) }))));
+        assert!(is_truthy(&builtin_level()));
+        assert_eq!(active_value(&object(Type::You(You { x: 1, y: 2, dir: 3, sleeping: false }))), Some(2));
+        assert_eq!(active_value(&object(Type::You2(You2 { x: 300, y: 0, dir: 2, sleeping: false }))), Some(300));
+        assert_eq!(active_value(&EMPTY), None);
+        assert!(!is_truthy(&EMPTY));
+        assert!(!is_truthy(&object(Type::Reference(Reference { pointer: 4, weak: false }))));
+    }
+
+    #[test]
+    fn facing_all_types() {
+        let you = object(Type::You(You { x: 1, y: 0, dir: 0, sleeping: false }));
+        let you2 = object(Type::You2(You2 { x: 2, y: 0, dir: 2, sleeping: false }));
+        let group = object(Type::Group(Group { index: 0, data: Arc::new(vec![EMPTY]) }));
+        let empty_group = object(Type::Group(Group { index: 0, data: Arc::new(vec![]) }));
+        let reference = object(Type::Reference(Reference { pointer: 4, weak: false }));
+        let level = builtin_level();
+        assert_eq!(is_facing(&you, &you2), Some(true));
+        assert_eq!(is_facing(&you2, &you), Some(true));
+        assert_eq!(is_facing(&empty_group, &group), Some(true));
+        assert_eq!(is_facing(&group, &empty_group), Some(false));
+        // Unordered types, or mixed ordered types
+        for obj in &[&you, &you2, &group, &EMPTY, &level, &reference] {
+            assert_eq!(is_facing(&level, obj), None);
+            assert_eq!(is_facing(&EMPTY, obj), None);
+            assert_eq!(is_facing(&reference, obj), None);
+            assert_eq!(is_facing(obj, &level), None);
+        }
+        assert_eq!(is_facing(&you, &group), None);
+        assert_eq!(is_facing(&group, &you2), None);
+        assert!(is_ordered(&you) && is_ordered(&you2) && is_ordered(&group));
+        assert!(!is_ordered(&level) && !is_ordered(&EMPTY) && !is_ordered(&reference));
+    }
+
+    #[test]
+    fn on_all_types() {
+        let you = object(Type::You(You { x: 1, y: 2, dir: 0, sleeping: false }));
+        let you2 = object(Type::You2(You2 { x: 1, y: 2, dir: 3, sleeping: true }));
+        let group = |data| object(Type::Group(Group { index: 0, data: Arc::new(data) }));
+        assert!(is_on(&you, &you2) && is_on(&you2, &you));
+        assert!(is_on(&group(vec![you.clone(), EMPTY]), &group(vec![you.clone(), EMPTY])));
+        assert!(!is_on(&group(vec![you.clone(), EMPTY]), &group(vec![EMPTY, you.clone()])));
+        assert!(!is_on(&group(vec![]), &EMPTY));
+        assert!(is_on(&EMPTY, &EMPTY) && is_on(&builtin_level(), &builtin_level()));
+    }
+    /// Wraps EMPTY in `depth` nested GROUPs, with `last` alongside the innermost one
+    fn nested(depth: usize, last: Object) -> Object {
+        let mut obj = object(Type::Group(Group { index: 0, data: Arc::new(vec![EMPTY, last]) }));
+        for _ in 0..depth {
+            obj = object(Type::Group(Group { index: 0, data: Arc::new(vec![obj]) }));
+        }
+        obj
+    }
+
+    /// Drops nested GROUPs one level at a time, since dropping is recursive
+    fn flatten(mut obj: Object) {
+        while let Type::Group(group) = &mut obj.obj_type {
+            match Arc::make_mut(&mut group.data).pop() {
+                Some(inner) => obj = inner,
+                None => break
+            }
+        }
+    }
+
+    #[test]
+    fn deep_equality() {
+        let you = object(Type::You(You { x: 1, y: 0, dir: 0, sleeping: false }));
+        let a = nested(200_000, you.clone());
+        let b = nested(200_000, you.clone());
+        let c = nested(200_000, EMPTY);
+        let d = nested(199_999, you);
+        assert!(a == b);
+        assert!(a != c);
+        assert!(a != d);
+        for obj in vec![a, b, c, d].into_iter() {
+            flatten(obj);
+        }
+    }
+}
@@ -1,26 +1,56 @@
-use crate::token::{Noun, Property, Prefix, Verb, Conditional};
+use crate::token::{Noun, Property, Prefix, Verb, Conditional, Token, spelling};
+
+use std::collections::HashMap;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Target {
     Noun(Noun),
     Property(Property)
 }
 
+/// Returns the canonical spelling of a target. See `token::spelling`.
+pub fn target_spelling(target: &Target, identifiers: &HashMap<usize, String>) -> String {
+    match target {
+        Target::Noun(noun) => spelling(&Token::Noun(*noun), identifiers),
+        Target::Property(property) => spelling(&Token::Property(*property), identifiers),
+    }
+}
+
+/// A conditional of a statement, along with its sign and its targets.
+/// Each target has its own sign, as in `BABA NEAR KEKE AND NOT ME`.
+pub type Condition = (Conditional, bool, Vec<(Target, bool)>);
+
+/// How the prefix and the condition of a statement are combined.
+/// `LONELY BABA ON KEKE` requires both to hold, while `LONELY BABA OR ON KEKE` 
+/// requires either. NOT applies to the prefix or the condition it precedes, 
+/// never to the combination as a whole. Chained conditions, as in 
+/// `LONELY BABA OR ON KEKE AND FACING ME`, are combined with the prefix as one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Combinator {
+    And,
+    Or
+}
+
 #[derive(Clone, Debug)]
 pub struct Statement {
     pub prefix: Option<Prefix>,
     pub prefix_sign: Option<bool>,
     pub subject: Noun,
-    pub cond_type: Option<Conditional>,
-    pub cond_sign: Option<bool>,
-    pub cond_targets: Vec<Target>,
+    // All of which must hold
+    pub conditions: Vec<Condition>,
+    // How the prefix and the condition are combined
+    pub combinator: Combinator,
     pub action_type: Verb,
     // These can only be nouns. 
     // Any properties will get converted into separate statements with `action_target`.
     pub action_targets: Option<Vec<Noun>>,
     pub action_target: Option<Target>,
     pub action_signs: Option<Vec<bool>>,
-    pub action_sign: bool
+    pub action_sign: bool,
+    // Byte span in the source, from the subject to the last target
+    pub span: (usize, usize)
 }
 
 // Adds a statement to the stream
@@ -29,12 +59,12 @@ pub fn append_statement(
     prefix: &Option<Prefix>,
     prefix_sign: &Option<bool>,
     subject: &Noun, 
-    cond_type: &Option<Conditional>,
-    cond_sign: &Option<bool>,
-    cond_targets: Option<&[Target]>,
+    conditions: &[Condition],
+    combinator: &Combinator,
     action_type: &Verb,
     action_targets: &[Target],
     action_signs: &[bool],
+    span: (usize, usize),
     ) {
     // [NOUN] IS [NOUN] AND [NOUN] evaluates the AND statement *before* the IS, 
     // which means we can't guarantee that each target is its separate instruction.
@@ -45,7 +75,7 @@ pub fn append_statement(
         let total = action_targets.len();
         for (i, target) in action_targets.iter().enumerate() {
             match target {
-                Target::Noun(n) if matches!(n, Noun::Identifier(_)) | matches!(n, Noun::All) => (),
+                Target::Noun(Noun::Identifier(_) | Noun::All | Noun::Number(_)) => (),
                 _ => {
                     match i - start_index {
                         0 => {
@@ -54,17 +84,14 @@ pub fn append_statement(
                                 prefix: *prefix,
                                 prefix_sign: *prefix_sign,
                                 subject: *subject,
-                                cond_type: *cond_type,
-                                cond_sign: *cond_sign,
-                                cond_targets: match cond_targets {
-                                    Some(v) => v.to_vec(),
-                                    None => Vec::new()
-                                },
+                                conditions: conditions.to_vec(),
+                                combinator: *combinator,
                                 action_type: *action_type,
                                 action_targets: None,
                                 action_target: Some(*target),
                                 action_signs: None,
                                 action_sign: action_signs[i],
+                                span,
                             });
                         },
                         1 => {
@@ -73,34 +100,28 @@ pub fn append_statement(
                                 prefix: *prefix,
                                 prefix_sign: *prefix_sign,
                                 subject: *subject,
-                                cond_type: *cond_type,
-                                cond_sign: *cond_sign,
-                                cond_targets: match cond_targets {
-                                    Some(v) => v.to_vec(),
-                                    None => Vec::new()
-                                },
+                                conditions: conditions.to_vec(),
+                                combinator: *combinator,
                                 action_type: *action_type,
                                 action_targets: None,
                                 action_target: Some(action_targets[i - 1]),
                                 action_signs: None,
                                 action_sign: action_signs[i - 1],
+                                span,
                             });
                             // Current property
                             out.push(Statement {
                                 prefix: *prefix,
                                 prefix_sign: *prefix_sign,
                                 subject: *subject,
-                                cond_type: *cond_type,
-                                cond_sign: *cond_sign,
-                                cond_targets: match cond_targets {
-                                    Some(v) => v.to_vec(),
-                                    None => Vec::new()
-                                },
+                                conditions: conditions.to_vec(),
+                                combinator: *combinator,
                                 action_type: *action_type,
                                 action_targets: None,
                                 action_target: Some(*target),
                                 action_signs: None,
                                 action_sign: action_signs[i],
+                                span,
                             });
                         },
                         k if k > 1 => {
@@ -117,34 +138,28 @@ pub fn append_statement(
                                 prefix: *prefix,
                                 prefix_sign: *prefix_sign,
                                 subject: *subject,
-                                cond_type: *cond_type,
-                                cond_sign: *cond_sign,
-                                cond_targets: match cond_targets {
-                                    Some(v) => v.to_vec(),
-                                    None => Vec::new()
-                                },
+                                conditions: conditions.to_vec(),
+                                combinator: *combinator,
                                 action_type: *action_type,
                                 action_targets: Some(targets),
                                 action_target: None,
                                 action_signs: Some(action_signs[i - k..i].to_vec()),
                                 action_sign: false,
+                                span,
                             });
                             // Current property
                             out.push(Statement {
                                 prefix: *prefix,
                                 prefix_sign: *prefix_sign,
                                 subject: *subject,
-                                cond_type: *cond_type,
-                                cond_sign: *cond_sign,
-                                cond_targets: match cond_targets {
-                                    Some(v) => v.to_vec(),
-                                    None => Vec::new()
-                                },
+                                conditions: conditions.to_vec(),
+                                combinator: *combinator,
                                 action_type: *action_type,
                                 action_targets: None,
                                 action_target: Some(*target),
                                 action_signs: None,
                                 action_sign: action_signs[i],
+                                span,
                             });
                         }
                         _ => ()
@@ -159,17 +174,14 @@ pub fn append_statement(
                     prefix: *prefix,
                     prefix_sign: *prefix_sign,
                     subject: *subject,
-                    cond_type: *cond_type,
-                    cond_sign: *cond_sign,
-                    cond_targets: match cond_targets {
-                        Some(v) => v.to_vec(),
-                        None => Vec::new()
-                    },
+                    conditions: conditions.to_vec(),
+                    combinator: *combinator,
                     action_type: *action_type,
                     action_targets: None,
                     action_target: Some(action_targets[start_index]),
                     action_signs: None,
                     action_sign: action_signs[start_index],
+                    span,
                 });
             },
             k if k > 1 => {
@@ -183,17 +195,14 @@ pub fn append_statement(
                     prefix: *prefix,
                     prefix_sign: *prefix_sign,
                     subject: *subject,
-                    cond_type: *cond_type,
-                    cond_sign: *cond_sign,
-                    cond_targets: match cond_targets {
-                        Some(v) => v.to_vec(),
-                        None => Vec::new()
-                    },
+                    conditions: conditions.to_vec(),
+                    combinator: *combinator,
                     action_type: *action_type,
                     action_targets: Some(targets),
                     action_target: None,
                     action_signs: Some(action_signs[start_index..].to_vec()),
                     action_sign: false,
+                    span,
                 });
             },
             _ => ()
@@ -207,17 +216,14 @@ pub fn append_statement(
                 prefix: *prefix,
                 prefix_sign: *prefix_sign,
                 subject: *subject,
-                cond_type: *cond_type,
-                cond_sign: *cond_sign,
-                cond_targets: match cond_targets {
-                    Some(v) => v.to_vec(),
-                    None => Vec::new()
-                },
+                conditions: conditions.to_vec(),
+                combinator: *combinator,
                 action_type: *action_type,
                 action_targets: None,
                 action_target: Some(*target),
                 action_signs: None,
-                action_sign: action_signs[i]
+                action_sign: action_signs[i],
+                span,
             };
             out.push(statement);
         }
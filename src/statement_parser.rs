@@ -1,6 +1,6 @@
-use crate::token::{Noun, Verb, Property, Prefix, Conditional, Token};
-use crate::statement::{Target, Statement, append_statement};
-use crate::error_handler::{ErrorType, throw_error, throw_error_str};
+use crate::token::{Noun, Verb, Property, Prefix, Conditional, Token, spelling};
+use crate::statement::{Target, Statement, Combinator, Condition, append_statement, target_spelling};
+use crate::error_handler::{ErrorType, BabaError, error, error_str};
 
 use std::collections::HashMap;
 
@@ -13,7 +13,7 @@ enum ParserState {
     Subject,
     // Major conditional 
     ExpectsMajCond, MajCond, MajCondTarget, CondAnd, 
-    MajCondFacing, MajCondFacingTarget, CondFacingAnd,
+    MajCondFacing, MajCondFacingTarget, CondFacingAnd, CondAndNot,
     // Major action: IS
     MajAct, MajActTarget, ActAnd, ExpectsMajActTarget,
     // Major action: other verbs
@@ -30,10 +30,18 @@ enum ParserState {
 /// 
 /// * `tokens` - A slice of tokens to read.
 /// 
+/// * `spans` - The byte spans of each token in the source.
+/// 
+/// * `identifiers` - A HashMap that associates each token identifier with its name.
+/// 
 /// # Return
 /// 
-/// Returns a `Vec` of `Statement` objects.
-pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Statement> {
+/// Returns a `Vec` of `Statement` objects, or the first StatementParserError raised.
+pub fn parse(
+    tokens: &[Token], 
+    spans: &[(usize, usize)], 
+    identifiers: &HashMap<usize, String>
+) -> Result<Vec<Statement>, BabaError> {
     let mut out = Vec::new();
     let mut state = ParserState::Blank;
 
@@ -43,18 +51,27 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
     let mut subject: Option<Noun> = None;
     let mut cond_type: Option<Conditional> = None;
     let mut cond_sign = false;
-    let mut cond_targets: Vec<Target> = Vec::new();
+    let mut combinator = Combinator::And;
+    let mut cond_targets: Vec<(Target, bool)> = Vec::new();
+    // The sign of the condition target following AND NOT
+    let mut target_sign = false;
+    // The conditionals preceding the one being parsed
+    let mut conditions: Vec<Condition> = Vec::new();
     let mut action_type: Option<Verb> = None;
     let mut action_targets: Vec<Target> = Vec::new();
     let mut action_sign = false;
     let mut action_signs: Vec<bool> = Vec::new();
+    // Indices of the first and last tokens of the current statement
+    let mut start = 0;
+    let mut end = 0;
 
-    for token in tokens {
+    for (i, token) in tokens.iter().enumerate() {
         // The compiler is hopefully smart enough to recognize
         // that this is a finite state machine
         match state {
             ParserState::Blank => {
                 // Expect statements to begin with a noun
+                start = i;
                 if let Token::Noun(noun) = token {
                     subject = Some(*noun);
                     state = ParserState::Subject;
@@ -68,11 +85,11 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                     state = ParserState::ExpectsPrefix;
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Noun, Prefix or Not, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::ExpectsPrefix => {
@@ -84,19 +101,21 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                     prefix_sign = !prefix_sign;
                     state = ParserState::ExpectsPrefix;
                 }
-                else if let Token::Noun(Noun::Identifier(id)) = token {
-                    throw_error(
-                        ErrorType::StatementParserError,
-                        format!("Expected Prefix or Not, got {:?}", token),
-                        Some((&[*id], identifiers))
-                    );
+                // NOT directly before the subject negates the action instead,
+                // i.e. NOT BABA IS MOVE is the same as BABA IS NOT MOVE.
+                // NOT directly before a prefix only ever negates the prefix.
+                else if let Token::Noun(noun) = token {
+                    action_sign = prefix_sign;
+                    prefix_sign = false;
+                    subject = Some(*noun);
+                    state = ParserState::Subject;
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
-                        format!("Expected Prefix or Not, got {:?}", token),
+                        format!("Expected Prefix, Noun or Not, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::Prefix => {
@@ -105,11 +124,11 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                     state = ParserState::Subject;
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Noun, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::Subject => {
@@ -137,19 +156,30 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                     cond_sign = !cond_sign;
                     state = ParserState::ExpectsMajCond;
                 }
+                // The prefix OR the condition must hold
+                else if let (Token::Or, Combinator::And) = (token, combinator) {
+                    if prefix.is_none() {
+                        return Err(error_str(
+                            ErrorType::StatementParserError,
+                            "Or must be preceded by a Prefix"
+                        ));
+                    }
+                    combinator = Combinator::Or;
+                    state = ParserState::ExpectsMajCond;
+                }
                 else if let Token::Noun(Noun::Identifier(id)) = token {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Verb, Conditional or Not, got {:?}", token),
                         Some((&[*id], identifiers))
-                    );
+                    ));
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Verb, Conditional or Not, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::ExpectsMajCond => {
@@ -170,69 +200,70 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                     state = ParserState::ExpectsMajCond;
                 }
                 else if let Token::Noun(Noun::Identifier(id)) = token {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Conditional or Not, got {:?}", token),
                         Some((&[*id], identifiers))
-                    );
+                    ));
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Conditional or Not, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::MajCond => {
                 if let Token::Noun(noun) = token {
                     // Nouns and properties are wrapped with an enum due to FACING
-                    cond_targets.push(Target::Noun(*noun));
+                    cond_targets.push((Target::Noun(*noun), false));
                     state = ParserState::MajCondTarget;
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Noun, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::MajCondFacing => {
                 if let Token::Noun(noun) = token {
                     // Nouns and properties are wrapped with an enum due to FACING
-                    cond_targets.push(Target::Noun(*noun));
+                    cond_targets.push((Target::Noun(*noun), false));
                     state = ParserState::MajCondFacingTarget;
                 }
                 else if let Token::Property(prop) = token {
                     // FACING accepts UP, DOWN, LEFT, RIGHT
                     match prop {
                         Property::Up | Property::Down | Property::Left | Property::Right => {
-                            cond_targets.push(Target::Property(*prop))
+                            cond_targets.push((Target::Property(*prop), false))
                         },
                         _ => {
-                            throw_error(
+                            return Err(error(
                                 ErrorType::StatementParserError, 
                                 format!(
                                     "Property words following Facing must be Up, Down, Left or Right, not {:?}",
                                     prop
                                 ),
                                 None
-                            )
+                            ))
                         }
                     }
                     state = ParserState::MajCondFacingTarget;
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Noun or Property, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::MajCondTarget => {
                 if let Token::Verb(verb) = token {
+                    end_condition(&mut conditions, &mut cond_type, &mut cond_sign, &mut cond_targets);
                     if let Verb::Is = verb {
                         state = ParserState::MajIs;
                     }
@@ -245,22 +276,23 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                     state = ParserState::CondAnd;
                 }
                 else if let Token::Noun(Noun::Identifier(id)) = token {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Verb or And, got {:?}", token),
                         Some((&[*id], identifiers))
-                    );
+                    ));
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Verb or And, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::MajCondFacingTarget => {
                 if let Token::Verb(verb) = token {
+                    end_condition(&mut conditions, &mut cond_type, &mut cond_sign, &mut cond_targets);
                     if let Verb::Is = verb {
                         state = ParserState::MajIs;
                     }
@@ -273,68 +305,152 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                     state = ParserState::CondFacingAnd;
                 }
                 else if let Token::Noun(Noun::Identifier(id)) = token {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Verb or And, got {:?}", token),
                         Some((&[*id], identifiers))
-                    );
+                    ));
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Verb or And, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::CondAnd => {
                 if let Token::Noun(noun) = token {
-                    cond_targets.push(Target::Noun(*noun));
+                    cond_targets.push((Target::Noun(*noun), false));
                     state = ParserState::MajCondTarget;
                 }
+                // A new conditional, which must hold along with the previous ones
+                else if let Token::Conditional(cond) = token {
+                    end_condition(&mut conditions, &mut cond_type, &mut cond_sign, &mut cond_targets);
+                    if let Conditional::Facing = cond {
+                        state = ParserState::MajCondFacing;
+                    }
+                    else {
+                        state = ParserState::MajCond;
+                    }
+                    cond_type = Some(*cond);
+                }
+                // Either a negated target or a new negated conditional
+                else if let Token::Not = token {
+                    target_sign = true;
+                    state = ParserState::CondAndNot;
+                }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
-                        format!("Expected Noun, got {:?}", token),
+                        format!("Expected Noun, Conditional or Not, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::CondFacingAnd => {
                 if let Token::Noun(noun) = token {
-                    cond_targets.push(Target::Noun(*noun));
+                    cond_targets.push((Target::Noun(*noun), false));
                     state = ParserState::MajCondTarget;
                 }
                 else if let Token::Property(prop) = token {
                     match prop {
                         Property::Up | Property::Down | Property::Left | Property::Right => {
-                            cond_targets.push(Target::Property(*prop))
+                            cond_targets.push((Target::Property(*prop), false))
                         },
                         _ => {
-                            throw_error(
+                            return Err(error(
                                 ErrorType::StatementParserError, 
                                 format!(
                                     "Property words following Facing must be Up, Down, Left or Right, not {:?}",
                                     prop
                                 ),
                                 None
-                            )
+                            ))
                         }
                     }
                     state = ParserState::MajCondFacingTarget;
                 }
+                // A new conditional, which must hold along with the previous ones
+                else if let Token::Conditional(cond) = token {
+                    end_condition(&mut conditions, &mut cond_type, &mut cond_sign, &mut cond_targets);
+                    if let Conditional::Facing = cond {
+                        state = ParserState::MajCondFacing;
+                    }
+                    else {
+                        state = ParserState::MajCond;
+                    }
+                    cond_type = Some(*cond);
+                }
+                // Either a negated target or a new negated conditional
+                else if let Token::Not = token {
+                    target_sign = true;
+                    state = ParserState::CondAndNot;
+                }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
-                        format!("Expected Noun or Property got {:?}", token),
+                        format!("Expected Noun, Property, Conditional or Not, got {:?}", token),
                         None
-                    );
+                    ));
+                }
+            },
+            ParserState::CondAndNot => {
+                let facing = cond_type == Some(Conditional::Facing);
+                if let Token::Noun(noun) = token {
+                    cond_targets.push((Target::Noun(*noun), target_sign));
+                    target_sign = false;
+                    state = if facing { ParserState::MajCondFacingTarget } else { ParserState::MajCondTarget };
+                }
+                else if let (Token::Property(prop), true) = (token, facing) {
+                    match prop {
+                        Property::Up | Property::Down | Property::Left | Property::Right => {
+                            cond_targets.push((Target::Property(*prop), target_sign))
+                        },
+                        _ => {
+                            return Err(error(
+                                ErrorType::StatementParserError, 
+                                format!(
+                                    "Property words following Facing must be Up, Down, Left or Right, not {:?}",
+                                    prop
+                                ),
+                                None
+                            ))
+                        }
+                    }
+                    target_sign = false;
+                    state = ParserState::MajCondFacingTarget;
+                }
+                // NOT before a conditional negates the conditional as a whole
+                else if let Token::Conditional(cond) = token {
+                    end_condition(&mut conditions, &mut cond_type, &mut cond_sign, &mut cond_targets);
+                    cond_sign = target_sign;
+                    target_sign = false;
+                    if let Conditional::Facing = cond {
+                        state = ParserState::MajCondFacing;
+                    }
+                    else {
+                        state = ParserState::MajCond;
+                    }
+                    cond_type = Some(*cond);
+                }
+                else if let Token::Not = token {
+                    target_sign = !target_sign;
+                    state = ParserState::CondAndNot;
+                }
+                else {
+                    return Err(error(
+                        ErrorType::StatementParserError,
+                        format!("Expected Noun, Conditional or Not, got {:?}", token),
+                        None
+                    ));
                 }
             },
             ParserState::MajAct => {
                 if let Token::Noun(noun) = token {
                     action_signs.push(action_sign);
                     action_targets.push(Target::Noun(*noun));
+                    end = i;
                     state = ParserState::MajActTarget;
                 }
                 else if let Token::Not = token {
@@ -342,22 +458,24 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                     state = ParserState::MajAct;
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Noun or Not, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::MajIs => {
                 if let Token::Property(prop) = token {
                     action_signs.push(action_sign);
                     action_targets.push(Target::Property(*prop));
+                    end = i;
                     state = ParserState::MajIsTarget;
                 }
                 else if let Token::Noun(noun) = token {
                     action_signs.push(action_sign);
                     action_targets.push(Target::Noun(*noun));
+                    end = i;
                     state = ParserState::MajIsTarget;
                 }
                 else if let Token::Not = token {
@@ -365,11 +483,11 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                     state = ParserState::MajIs;
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Property, Noun or Not, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::MajActTarget => {
@@ -380,22 +498,22 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                         &prefix,
                         &Some(prefix_sign),
                         &subject.clone().unwrap(), 
-                        &cond_type, 
-                        &Some(cond_sign), 
-                        Some(&cond_targets),
+                        &conditions,
+                        &combinator,
                         &action_type.unwrap(), 
                         &action_targets, 
-                        &action_signs
+                        &action_signs,
+                        span(spans, start, end)
                     );
                     action_targets.clear();
                     action_signs.clear();
-                    cond_type = None;
-                    cond_targets.clear();
-                    cond_sign = false;
+                    conditions.clear();
+                    combinator = Combinator::And;
                     prefix = None;
                     prefix_sign = false;
                     action_sign = false;
                     subject = Some(*noun);
+                    start = i;
                     state = ParserState::Subject;
                 }
                 // Continue existing statement (not IS)
@@ -409,21 +527,21 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                         &prefix,
                         &Some(prefix_sign),
                         &subject.clone().unwrap(), 
-                        &cond_type, 
-                        &Some(cond_sign), 
-                        Some(&cond_targets),
+                        &conditions,
+                        &combinator,
                         &action_type.unwrap(), 
                         &action_targets, 
-                        &action_signs
+                        &action_signs,
+                        span(spans, start, end)
                     );
                     action_targets.clear();
                     action_signs.clear();
-                    cond_type = None;
-                    cond_targets.clear();
-                    cond_sign = false;
+                    conditions.clear();
+                    combinator = Combinator::And;
                     prefix_sign = false;
                     action_sign = false;
                     prefix = Some(*pref);
+                    start = i;
                     state = ParserState::Prefix;
                 }
                 // New statement (NOT PREFIX)
@@ -433,30 +551,30 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                         &prefix,
                         &Some(prefix_sign),
                         &subject.clone().unwrap(), 
-                        &cond_type, 
-                        &Some(cond_sign), 
-                        Some(&cond_targets),
+                        &conditions,
+                        &combinator,
                         &action_type.unwrap(), 
                         &action_targets, 
-                        &action_signs
+                        &action_signs,
+                        span(spans, start, end)
                     );
                     action_targets.clear();
                     action_signs.clear();
-                    cond_type = None;
-                    cond_targets.clear();
-                    cond_sign = false;
+                    conditions.clear();
+                    combinator = Combinator::And;
                     prefix = None;
                     prefix_sign = false;
                     action_sign = false;
                     prefix_sign = !prefix_sign;
+                    start = i;
                     state = ParserState::ExpectsPrefix;
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Noun, And, Prefix or Not, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::MajIsTarget => {
@@ -467,22 +585,22 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                         &prefix,
                         &Some(prefix_sign),
                         &subject.clone().unwrap(), 
-                        &cond_type, 
-                        &Some(cond_sign), 
-                        Some(&cond_targets),
+                        &conditions,
+                        &combinator,
                         &action_type.unwrap(), 
                         &action_targets, 
-                        &action_signs
+                        &action_signs,
+                        span(spans, start, end)
                     );
                     action_targets.clear();
                     action_signs.clear();
-                    cond_type = None;
-                    cond_targets.clear();
-                    cond_sign = false;
+                    conditions.clear();
+                    combinator = Combinator::And;
                     prefix = None;
                     prefix_sign = false;
                     action_sign = false;
                     subject = Some(*noun);
+                    start = i;
                     state = ParserState::Subject;
                 }
                 // Continue existing statement (IS)
@@ -496,21 +614,21 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                         &prefix,
                         &Some(prefix_sign),
                         &subject.clone().unwrap(), 
-                        &cond_type, 
-                        &Some(cond_sign), 
-                        Some(&cond_targets),
+                        &conditions,
+                        &combinator,
                         &action_type.unwrap(), 
                         &action_targets, 
-                        &action_signs
+                        &action_signs,
+                        span(spans, start, end)
                     );
                     action_targets.clear();
                     action_signs.clear();
-                    cond_type = None;
-                    cond_targets.clear();
-                    cond_sign = false;
+                    conditions.clear();
+                    combinator = Combinator::And;
                     prefix_sign = false;
                     action_sign = false;
                     prefix = Some(*pref);
+                    start = i;
                     state = ParserState::Prefix;
                 }
                 // New statement (NOT PREFIX)
@@ -520,30 +638,30 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                         &prefix,
                         &Some(prefix_sign),
                         &subject.clone().unwrap(), 
-                        &cond_type, 
-                        &Some(cond_sign), 
-                        Some(&cond_targets),
+                        &conditions,
+                        &combinator,
                         &action_type.unwrap(), 
                         &action_targets, 
-                        &action_signs
+                        &action_signs,
+                        span(spans, start, end)
                     );
                     action_targets.clear();
                     action_signs.clear();
-                    cond_type = None;
-                    cond_targets.clear();
-                    cond_sign = false;
+                    conditions.clear();
+                    combinator = Combinator::And;
                     prefix = None;
                     prefix_sign = false;
                     action_sign = false;
                     prefix_sign = !prefix_sign;
+                    start = i;
                     state = ParserState::ExpectsPrefix;
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Noun, And, Prefix, or Not, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::ActAnd => {
@@ -551,6 +669,7 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                 if let Token::Noun(noun) = token {
                     action_signs.push(action_sign);
                     action_targets.push(Target::Noun(*noun));
+                    end = i;
                     state = ParserState::MajActTarget;
                 }
                 else if let Token::Not = token {
@@ -563,12 +682,12 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                         &prefix,
                         &Some(prefix_sign),
                         &subject.clone().unwrap(), 
-                        &cond_type, 
-                        &Some(cond_sign), 
-                        Some(&cond_targets),
+                        &conditions,
+                        &combinator,
                         &action_type.unwrap(), 
                         &action_targets, 
-                        &action_signs
+                        &action_signs,
+                        span(spans, start, end)
                     );
                     // Minor actions come after major actions.
                     // They occupy the same subject and conditionals
@@ -577,15 +696,15 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                     action_type = Some(*verb);
                     action_targets.clear();
                     action_signs.clear();
-                    cond_type = None;
+                    conditions.clear();
                     state = ParserState::ExpectsMinActTarget;
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Noun, Not or Verb, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::IsAnd => {
@@ -593,11 +712,13 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                 if let Token::Noun(noun) = token {
                     action_signs.push(action_sign);
                     action_targets.push(Target::Noun(*noun));
+                    end = i;
                     state = ParserState::MajIsTarget;
                 }
                 else if let Token::Property(prop) = token {
                     action_signs.push(action_sign);
                     action_targets.push(Target::Property(*prop));
+                    end = i;
                     state = ParserState::MajIsTarget;
                 }
                 else if let Token::Not = token {
@@ -610,26 +731,26 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                         &prefix,
                         &Some(prefix_sign),
                         &subject.clone().unwrap(), 
-                        &cond_type, 
-                        &Some(cond_sign), 
-                        Some(&cond_targets),
+                        &conditions,
+                        &combinator,
                         &action_type.unwrap(), 
                         &action_targets, 
-                        &action_signs
+                        &action_signs,
+                        span(spans, start, end)
                     );
                     // Minor actions come after major actions
                     action_type = Some(*verb);
                     action_targets.clear();
                     action_signs.clear();
-                    cond_type = None;
+                    conditions.clear();
                     state = ParserState::ExpectsMinActTarget;
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Noun, Property, Not or Verb, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::ExpectsMajActTarget => {
@@ -637,6 +758,7 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                 if let Token::Noun(noun) = token {
                     action_signs.push(action_sign);
                     action_targets.push(Target::Noun(*noun));
+                    end = i;
                     state = ParserState::MajActTarget;
                 }
                 else if let Token::Not = token {
@@ -644,11 +766,11 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                     state = ParserState::ExpectsMajActTarget;
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Noun or Not, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             ParserState::ExpectsMajIsTarget => {
@@ -656,11 +778,13 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                 if let Token::Noun(noun) = token {
                     action_signs.push(action_sign);
                     action_targets.push(Target::Noun(*noun));
+                    end = i;
                     state = ParserState::MajIsTarget;
                 }
                 else if let Token::Property(prop) = token {
                     action_signs.push(action_sign);
                     action_targets.push(Target::Property(*prop));
+                    end = i;
                     state = ParserState::MajIsTarget;
                 }
                 else if let Token::Not = token {
@@ -668,11 +792,11 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                     state = ParserState::ExpectsMajIsTarget;
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Noun, Propery or Not, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             },
             // Minor actions can only have one target, and thus
@@ -682,26 +806,26 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                 if let Token::Noun(noun) = token {
                     action_signs.push(action_sign);
                     action_targets.push(Target::Noun(*noun));
+                    end = i;
                     append_statement(
                         &mut out,
                         &prefix,
                         &Some(prefix_sign),
                         &subject.clone().unwrap(), 
-                        &cond_type, 
-                        &Some(cond_sign), 
-                        Some(&cond_targets),
+                        &conditions,
+                        &combinator,
                         &action_type.unwrap(), 
                         &action_targets, 
-                        &action_signs
+                        &action_signs,
+                        span(spans, start, end)
                     );
                     // It's not necessary to clear the subject
                     // or action type, as those are necessarily
                     // overriden by new statements.
                     action_signs.clear();
                     action_targets.clear();
-                    cond_type = None;
-                    cond_targets.clear();
-                    cond_sign = false;
+                    conditions.clear();
+                    combinator = Combinator::And;
                     prefix = None;
                     prefix_sign = false;
                     action_sign = false;
@@ -710,23 +834,23 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                 else if let Token::Property(prop) = token {
                     action_signs.push(action_sign);
                     action_targets.push(Target::Property(*prop));
+                    end = i;
                     append_statement(
                         &mut out,
                         &prefix,
                         &Some(prefix_sign),
                         &subject.clone().unwrap(), 
-                        &cond_type, 
-                        &Some(cond_sign), 
-                        Some(&cond_targets),
+                        &conditions,
+                        &combinator,
                         &action_type.unwrap(), 
                         &action_targets, 
-                        &action_signs
+                        &action_signs,
+                        span(spans, start, end)
                     );
                     action_signs.clear();
                     action_targets.clear();
-                    cond_type = None;
-                    cond_targets.clear();
-                    cond_sign = false;
+                    conditions.clear();
+                    combinator = Combinator::And;
                     prefix = None;
                     prefix_sign = false;
                     action_sign = false;
@@ -737,11 +861,11 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                     state = ParserState::ExpectsMinActTarget;
                 }
                 else {
-                    throw_error(
+                    return Err(error(
                         ErrorType::StatementParserError,
                         format!("Expected Noun, Propery or Not, got {:?}", token),
                         None
-                    );
+                    ));
                 }
             }
         }
@@ -760,12 +884,12 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                 &prefix,
                 &Some(prefix_sign),
                 &subject.clone().unwrap(), 
-                &cond_type, 
-                &Some(cond_sign), 
-                Some(&cond_targets),
+                &conditions,
+                &combinator,
                 &action_type.unwrap(), 
                 &action_targets, 
-                &action_signs
+                &action_signs,
+                span(spans, start, end)
             );
         },
         ParserState::MajIsTarget => {
@@ -775,22 +899,234 @@ pub fn parse(tokens: &[Token], identifiers: &HashMap<usize, String>) -> Vec<Stat
                 &prefix,
                 &Some(prefix_sign),
                 &subject.clone().unwrap(), 
-                &cond_type, 
-                &Some(cond_sign), 
-                Some(&cond_targets),
+                &conditions,
+                &combinator,
                 &action_type.unwrap(), 
                 &action_targets, 
-                &action_signs
+                &action_signs,
+                span(spans, start, end)
             );
         },
         _ => {
-            // EOF occurred during some other random state
-            throw_error_str(
+            // EOF occurred in the middle of a statement
+            let (context, expected) = expected_at_eof(&state);
+            return Err(error(
                 ErrorType::StatementParserError,
-                "Unexpected EOF during statement parsing"
-            )
+                format!(
+                    "Unexpected EOF {} (in the statement starting at offset {}), expected {}", 
+                    context, 
+                    spans.get(start).map_or(0, |span| span.0),
+                    expected
+                ),
+                None
+            ))
         }
     }
     
-    out
-}
\ No newline at end of file
+    Ok(out)
+}
+
+/// Describes where a statement was cut off by EOF in the given state,
+/// and which tokens were expected next.
+fn expected_at_eof(state: &ParserState) -> (&'static str, &'static str) {
+    match state {
+        ParserState::ExpectsPrefix => ("after Not", "Prefix, Noun or Not"),
+        ParserState::Prefix => ("after a Prefix", "a subject Noun"),
+        ParserState::Subject => ("after a subject without a Verb", "Verb, Conditional or Not"),
+        ParserState::ExpectsMajCond => ("before a Conditional", "Conditional or Not"),
+        ParserState::MajCond => ("after a Conditional", "Noun"),
+        ParserState::MajCondFacing => ("after Facing", "Noun or Property"),
+        ParserState::MajCondTarget | ParserState::MajCondFacingTarget => ("after a condition without a Verb", "Verb or And"),
+        ParserState::CondAnd => ("after And in a condition", "Noun, Conditional or Not"),
+        ParserState::CondFacingAnd => ("after And in a condition", "Noun, Property, Conditional or Not"),
+        ParserState::CondAndNot => ("after Not in a condition", "Noun, Conditional or Not"),
+        ParserState::MajAct => ("after a Verb", "Noun or Not"),
+        ParserState::MajIs => ("after Is", "Property, Noun or Not"),
+        ParserState::ActAnd => ("after And", "Noun, Not or Verb"),
+        ParserState::IsAnd => ("after And", "Noun, Property, Not or Verb"),
+        ParserState::ExpectsMajActTarget => ("after Not", "Noun or Not"),
+        ParserState::ExpectsMajIsTarget | ParserState::ExpectsMinActTarget => ("after Not", "Noun, Property or Not"),
+        // Complete statements
+        ParserState::Blank | ParserState::MajActTarget | ParserState::MajIsTarget => ("", "nothing")
+    }
+}
+
+/// Ends the conditional being parsed, adding it to the conditions of the statement.
+fn end_condition(
+    conditions: &mut Vec<Condition>,
+    cond_type: &mut Option<Conditional>,
+    cond_sign: &mut bool,
+    cond_targets: &mut Vec<(Target, bool)>
+) {
+    if let Some(cond) = cond_type.take() {
+        conditions.push((cond, *cond_sign, std::mem::take(cond_targets)));
+    }
+    *cond_sign = false;
+}
+
+/// Returns the byte span covering the tokens from `start` to `end` (inclusive).
+fn span(spans: &[(usize, usize)], start: usize, end: usize) -> (usize, usize) {
+    match (spans.get(start), spans.get(end)) {
+        (Some(first), Some(last)) => (first.0, last.1),
+        _ => (0, 0)
+    }
+}
+
+/// Lists statements one per line, for debugging the statement parser.
+/// 
+/// Each line holds the byte span of the statement, its subject along with 
+/// any prefix and condition, its verb and its targets, separated by tabs. 
+/// Targets kept together as a single AND chain are bracketed, e.g. 
+/// `0..24\tkeke\tis\t[baba, not me]`.
+pub fn dump(statements: &[Statement], identifiers: &HashMap<usize, String>) -> String {
+    let negate = |sign: Option<bool>| if sign == Some(true) { "not " } else { "" };
+    statements.iter()
+        .map(|statement| {
+            let mut subject = spelling(&Token::Noun(statement.subject), identifiers);
+            if let Some(prefix) = statement.prefix {
+                subject = format!("{}{} {}", negate(statement.prefix_sign), spelling(&Token::Prefix(prefix), identifiers), subject);
+                if statement.combinator == Combinator::Or {
+                    subject.push_str(" or");
+                }
+            }
+            let conditions = statement.conditions.iter()
+                .map(|(cond_type, cond_sign, cond_targets)| {
+                    let targets = cond_targets.iter()
+                        .map(|(target, sign)| format!("{}{}", negate(Some(*sign)), target_spelling(target, identifiers)))
+                        .collect::<Vec<String>>();
+                    format!(
+                        "{}{} {}", 
+                        negate(Some(*cond_sign)), spelling(&Token::Conditional(*cond_type), identifiers), targets.join(" and ")
+                    )
+                })
+                .collect::<Vec<String>>();
+            if !conditions.is_empty() {
+                subject = format!("{} {}", subject, conditions.join(" and "));
+            }
+            let targets = match (&statement.action_targets, &statement.action_signs, &statement.action_target) {
+                (Some(nouns), Some(signs), _) => {
+                    let targets = nouns.iter()
+                        .zip(signs.iter())
+                        .map(|(noun, &sign)| format!("{}{}", negate(Some(sign)), spelling(&Token::Noun(*noun), identifiers)))
+                        .collect::<Vec<String>>();
+                    format!("[{}]", targets.join(", "))
+                },
+                (_, _, Some(target)) => format!("{}{}", negate(Some(statement.action_sign)), target_spelling(target, identifiers)),
+                _ => String::new()
+            };
+            format!(
+                "{}..{}\t{}\t{}\t{}", 
+                statement.span.0, statement.span.1, subject, spelling(&Token::Verb(statement.action_type), identifiers), targets
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::tokenize;
+    use crate::statement_parser::{parse, dump, expected_at_eof, ParserState};
+    use crate::statement::Combinator;
+    use crate::token::Conditional;
+
+    fn spans_of(source: &str) -> Vec<(usize, usize)> {
+        let mut bytes = source.bytes().collect::<Vec<u8>>();
+        let (tokens, spans, identifiers) = tokenize(None, Some(&mut bytes), false).unwrap();
+        parse(&tokens, &spans, &identifiers).unwrap().iter().map(|s| s.span).collect()
+    }
+
+    #[test]
+    fn statement_spans() {
+        let source = "baba is you  keke is group\nlonely baba is move";
+        let spans = spans_of(source);
+        assert_eq!(spans, vec![(0, 11), (13, 26), (27, 46)]);
+        assert_eq!(&source[spans[2].0..spans[2].1], "lonely baba is move");
+    }
+
+    #[test]
+    fn statement_spans_and() {
+        // Statements split from an AND chain share the span of the chain
+        let source = "baba is you and move keke is group";
+        assert_eq!(spans_of(source), vec![(0, 20), (0, 20), (21, 34)]);
+    }
+
+    #[test]
+    fn statement_combinator() {
+        let mut bytes = b"lonely baba or not on keke is move baba on keke is move".to_vec();
+        let (tokens, spans, identifiers) = tokenize(None, Some(&mut bytes), false).unwrap();
+        let statements = parse(&tokens, &spans, &identifiers).unwrap();
+        assert_eq!(statements[0].combinator, Combinator::Or);
+        assert!(statements[0].conditions[0].1);
+        assert_eq!(statements[1].combinator, Combinator::And);
+    }
+
+    #[test]
+    fn statement_negation() {
+        let mut bytes = b"not baba is move not not keke is you not lonely baba is move not baba is not move".to_vec();
+        let (tokens, spans, identifiers) = tokenize(None, Some(&mut bytes), false).unwrap();
+        let statements = parse(&tokens, &spans, &identifiers).unwrap();
+        // NOT before the subject negates the action
+        assert!(statements[0].action_sign);
+        assert_eq!(statements[0].prefix_sign, Some(false));
+        assert_eq!(statements[0].span, (0, 16));
+        assert!(!statements[1].action_sign);
+        // NOT before a prefix negates the prefix
+        assert!(!statements[2].action_sign);
+        assert_eq!(statements[2].prefix_sign, Some(true));
+        // Both negations cancel out
+        assert!(!statements[3].action_sign);
+    }
+
+    #[test]
+    fn dump_statements() {
+        let mut bytes = b"keke is move and baba and not me not lonely baba or not on keke and me has keke".to_vec();
+        let (tokens, spans, identifiers) = tokenize(None, Some(&mut bytes), false).unwrap();
+        let statements = parse(&tokens, &spans, &identifiers).unwrap();
+        assert_eq!(dump(&statements, &identifiers), "\
+0..32\tkeke\tis\tmove
+0..32\tkeke\tis\t[baba, not me]
+33..79\tnot lonely baba or not on keke and me\thas\tkeke");
+    }
+
+    #[test]
+    fn eof_expectations() {
+        assert_eq!(expected_at_eof(&ParserState::Subject), ("after a subject without a Verb", "Verb, Conditional or Not"));
+        assert_eq!(expected_at_eof(&ParserState::MajIs).1, "Property, Noun or Not");
+        assert_eq!(expected_at_eof(&ParserState::CondAnd).1, "Noun, Conditional or Not");
+    }
+
+    #[test]
+    fn chained_conditions() {
+        let mut bytes = b"baba on keke and me and facing up and not near me is move lonely baba or on keke and facing me is you".to_vec();
+        let (tokens, spans, identifiers) = tokenize(None, Some(&mut bytes), false).unwrap();
+        let statements = parse(&tokens, &spans, &identifiers).unwrap();
+        let conditions = statements[0].conditions.iter()
+            .map(|(cond_type, sign, targets)| (*cond_type, *sign, targets.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(conditions, vec![
+            (Conditional::On, false, 2), 
+            (Conditional::Facing, false, 1), 
+            (Conditional::Near, true, 1)
+        ]);
+        assert_eq!(dump(&statements, &identifiers), "\
+0..57\tbaba on keke and me and facing up and not near me\tis\tmove
+58..101\tlonely baba or on keke and facing me\tis\tyou");
+        // NOT before a noun negates the target, and before a conditional the conditional
+        let mut bytes = b"baba near keke and not me and not not baba and not near keke is move".to_vec();
+        let (tokens, spans, identifiers) = tokenize(None, Some(&mut bytes), false).unwrap();
+        let statements = parse(&tokens, &spans, &identifiers).unwrap();
+        let signs = statements[0].conditions.iter()
+            .map(|(_, sign, targets)| (*sign, targets.iter().map(|(_, sign)| *sign).collect::<Vec<bool>>()))
+            .collect::<Vec<_>>();
+        assert_eq!(signs, vec![(false, vec![false, true, false]), (true, vec![false])]);
+        assert_eq!(
+            dump(&statements, &identifiers), 
+            "0..68\tbaba near keke and not me and baba and not near keke\tis\tmove"
+        );
+        // A conditional can't directly follow another's targets
+        let mut bytes = b"baba on keke facing me is move".to_vec();
+        let (tokens, spans, identifiers) = tokenize(None, Some(&mut bytes), false).unwrap();
+        assert!(parse(&tokens, &spans, &identifiers).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+use crate::instruction::{Instruction, Simple, Level, is_reserved};
+use crate::statement::Target;
+use crate::token::Noun;
+
+use std::fmt::{Display, Formatter, Result};
+use std::collections::{BTreeMap, HashSet};
+
+/// Static metrics describing the structure of a program.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    // Number of instructions of each kind, keyed by name
+    pub kinds: BTreeMap<String, usize>,
+    // Deepest nesting of TELE, LEVEL and IMAGE blocks
+    pub depth: usize,
+    // Number of distinct identifiers referred to
+    pub identifiers: usize,
+    // Most operands joined with AND in a single instruction
+    pub and_chain: usize,
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "instructions\t{}", self.kinds.values().sum::<usize>())?;
+        for (kind, count) in &self.kinds {
+            writeln!(f, "kind\t{}\t{}", kind, count)?;
+        }
+        writeln!(f, "depth\t{}", self.depth)?;
+        writeln!(f, "identifiers\t{}", self.identifiers)?;
+        write!(f, "and-chain\t{}", self.and_chain)
+    }
+}
+
+/// Computes static metrics for a program by walking its instructions.
+///
+/// # Arguments
+///
+/// * `ast` - The instructions of the program.
+pub fn stats(ast: &[Instruction]) -> Stats {
+    let mut out = Stats::default();
+    let mut seen = HashSet::new();
+    walk(ast, 0, &mut out, &mut seen);
+    out.identifiers = seen.len();
+    out
+}
+
+/// Adds the metrics of the instructions, nested at the given depth, to `out`.
+fn walk(instructions: &[Instruction], depth: usize, out: &mut Stats, seen: &mut HashSet<usize>) {
+    for instruction in instructions {
+        match instruction {
+            Instruction::Simple(simple) => walk_simple(simple, out, seen),
+            Instruction::Complex(complex) => {
+                walk_simple(&complex.instruction, out, seen);
+                for conditions in &complex.conditions {
+                    out.and_chain = out.and_chain.max(conditions.targets.len());
+                    for (target, _) in &conditions.targets {
+                        if let Target::Noun(Noun::Identifier(id)) = target {
+                            see(*id, seen);
+                        }
+                    }
+                }
+            },
+            Instruction::Tele(tele) => {
+                count("Tele", out);
+                out.depth = out.depth.max(depth + 1);
+                see(tele.identifier, seen);
+                walk(&tele.instructions, depth + 1, out, seen);
+            },
+            Instruction::Level(level) => {
+                count("Level", out);
+                walk_level(level, depth, out, seen);
+            },
+            Instruction::Image(image) => {
+                count("Image", out);
+                out.and_chain = out.and_chain.max(image.attributes.len());
+                for attribute in &image.attributes {
+                    see(*attribute, seen);
+                }
+                walk_level(&image.constructor, depth, out, seen);
+            },
+            _ => ()
+        }
+    }
+}
+
+/// Adds the metrics of a LEVEL body, defined at the given depth, to `out`.
+fn walk_level(level: &Level, depth: usize, out: &mut Stats, seen: &mut HashSet<usize>) {
+    out.depth = out.depth.max(depth + 1);
+    out.and_chain = out.and_chain.max(level.arguments.len());
+    see(level.identifier, seen);
+    for argument in &level.arguments {
+        see(*argument, seen);
+    }
+    walk(&level.instructions, depth + 1, out, seen);
+}
+
+/// Adds the metrics of a simple instruction to `out`.
+fn walk_simple(simple: &Simple, out: &mut Stats, seen: &mut HashSet<usize>) {
+    // The variant name, without any fields
+    let name = format!("{:?}", simple);
+    count(name.split('(').next().unwrap_or_default(), out);
+    match simple {
+        Simple::AllWin | Simple::AllDefeat
+        | Simple::AllMove(_) | Simple::AllTurn(_) | Simple::AllFall(_)
+        | Simple::AllMore(_) | Simple::AllLess(_) | Simple::AllRight(_)
+        | Simple::AllUp(_) | Simple::AllLeft(_) | Simple::AllDown(_)
+        | Simple::AllChill(_) => (),
+        Simple::InitYou(id, _) | Simple::InitYou2(id, _) | Simple::InitGroup(id, _)
+        | Simple::Win(id) | Simple::Defeat(id) | Simple::Sleep(id)
+        | Simple::Text(id) | Simple::Word(id) | Simple::Line(id) | Simple::IsEmpty(id)
+        | Simple::Move(id, _) | Simple::Turn(id, _) | Simple::Fall(id, _)
+        | Simple::More(id, _) | Simple::Less(id, _) | Simple::Right(id, _)
+        | Simple::Up(id, _) | Simple::Left(id, _) | Simple::Down(id, _)
+        | Simple::Chill(id, _) | Simple::Both(id, _) | Simple::Reset(id) | Simple::Shift(id, _) | Simple::Sink(id)
+        | Simple::Swap(id) | Simple::Power(id, _) => see(*id, seen),
+        Simple::IsValue(id, target, _) | Simple::MimicReference(id, target, _)
+        | Simple::HasValue(id, target) | Simple::MakeValue(id, target)
+        | Simple::FearTele(id, target) | Simple::FollowAttribute(id, target)
+        | Simple::EatValue(id, target) | Simple::FeelType(id, target)
+        | Simple::EqualValue(id, target) | Simple::WriteValue(id, target)
+        | Simple::TrackIndex(id, target) | Simple::SpellDigits(id, target)
+        | Simple::Play(id, target) | Simple::FeedLevel(id, target) | Simple::PeekValue(id, target) => {
+            see(*id, seen);
+            see(*target, seen);
+        },
+        Simple::IsLiteral(id, _) | Simple::IsNumber(id, _, _) | Simple::HasLiteral(id, _)
+        | Simple::HasNumber(id, _) => see(*id, seen),
+        Simple::IsSum(id, nouns, _) => {
+            see(*id, seen);
+            out.and_chain = out.and_chain.max(nouns.len());
+            for noun in nouns {
+                if let Noun::Identifier(target) = noun {
+                    see(*target, seen);
+                }
+            }
+        }
+    }
+}
+
+/// Increments the count of an instruction kind.
+fn count(kind: &str, out: &mut Stats) {
+    *out.kinds.entry(kind.to_string()).or_insert(0) += 1;
+}
+
+/// Records an identifier, ignoring the built-in objects.
+fn see(id: usize, seen: &mut HashSet<usize>) {
+    if !is_reserved(id) {
+        seen.insert(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stats::{stats, Stats};
+    use crate::compile;
+
+    fn stats_of(source: &str) -> Stats {
+        let (instructions, _) = compile(source.as_bytes()).unwrap();
+        stats(&instructions)
+    }
+
+    #[test]
+    fn stats_counts() {
+        let found = stats_of("
+            baba is you keke is you
+            baba is move and move
+            f is level f has x and y
+                loop is tele
+                    x is baba and keke and baba
+                loop is done
+            f is done
+            baba near keke and x is move
+        ");
+        assert_eq!(found.kinds["InitYou"], 2);
+        assert_eq!(found.kinds["Move"], 3);
+        assert_eq!(found.kinds["Level"], 1);
+        assert_eq!(found.kinds["Tele"], 1);
+        assert_eq!(found.kinds["IsSum"], 1);
+        assert_eq!(found.depth, 2);
+        // baba, keke, f, x, y, loop
+        assert_eq!(found.identifiers, 6);
+        assert_eq!(found.and_chain, 3);
+    }
+
+    #[test]
+    fn stats_output() {
+        let found = stats_of("baba is you baba is move");
+        assert_eq!(
+            found.to_string(),
+            "instructions\t2\nkind\tInitYou\t1\nkind\tMove\t1\ndepth\t0\nidentifiers\t1\nand-chain\t0"
+        );
+    }
+}
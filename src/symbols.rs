@@ -0,0 +1,218 @@
+use crate::instruction::{Instruction, Simple, Level};
+use crate::statement::{Statement, Target};
+use crate::token::{Noun, Verb};
+
+use std::fmt::{Display, Formatter, Result};
+use std::collections::{HashMap, HashSet};
+
+/// The kind of object an identifier is defined as.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SymbolKind {
+    You,
+    You2,
+    Group,
+    Tele,
+    Level,
+    Image,
+    Reference,
+    Empty,
+    // Copied from another object
+    Value,
+    // LEVEL parameter
+    Argument,
+    // IMAGE attribute
+    Attribute,
+}
+
+/// Describes an identifier defined in a program.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Symbol {
+    pub identifier: usize,
+    pub name: String,
+    pub kind: SymbolKind,
+    // Byte span of the statement defining the symbol, if known
+    pub location: Option<(usize, usize)>
+}
+
+impl Display for SymbolKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            SymbolKind::You => write!(f, "YOU"),
+            SymbolKind::You2 => write!(f, "YOU2"),
+            SymbolKind::Group => write!(f, "GROUP"),
+            SymbolKind::Tele => write!(f, "TELE"),
+            SymbolKind::Level => write!(f, "LEVEL"),
+            SymbolKind::Image => write!(f, "IMAGE"),
+            SymbolKind::Reference => write!(f, "[REFERENCE]"),
+            SymbolKind::Empty => write!(f, "EMPTY"),
+            SymbolKind::Value => write!(f, "[VALUE]"),
+            SymbolKind::Argument => write!(f, "[ARGUMENT]"),
+            SymbolKind::Attribute => write!(f, "[ATTRIBUTE]"),
+        }
+    }
+}
+
+/// Collects the symbols defined in a program, in order of first definition.
+///
+/// The kind of each symbol is inferred from the first instruction defining it,
+/// and its location is the span of the first statement assigning to it, or
+/// of the HAS statement declaring it for LEVEL arguments and IMAGE attributes.
+///
+/// # Arguments
+///
+/// * `ast` - The instructions of the program.
+///
+/// * `statements` - The statements the instructions were parsed from.
+///
+/// * `identifiers` - A HashMap that associates each token identifier with its name.
+pub fn symbols(
+    ast: &[Instruction],
+    statements: &[Statement],
+    identifiers: &HashMap<usize, String>
+) -> Vec<Symbol> {
+    let mut definitions = Vec::new();
+    collect(ast, &mut definitions);
+
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for (id, kind) in definitions {
+        // EMPTY, LEVEL and IMAGE are keywords
+        if id <= 2 || !seen.insert(id) {
+            continue;
+        }
+        out.push(Symbol {
+            identifier: id,
+            name: identifiers.get(&id).cloned().unwrap_or_default(),
+            kind,
+            location: location(id, kind, statements)
+        });
+    }
+    out
+}
+
+/// Appends every definition in the instructions to `out`, in program order.
+fn collect(instructions: &[Instruction], out: &mut Vec<(usize, SymbolKind)>) {
+    for instruction in instructions {
+        match instruction {
+            Instruction::Simple(simple) => collect_simple(simple, out),
+            Instruction::Complex(complex) => collect_simple(&complex.instruction, out),
+            Instruction::Tele(tele) => {
+                out.push((tele.identifier, SymbolKind::Tele));
+                collect(&tele.instructions, out);
+            },
+            Instruction::Level(level) => {
+                out.push((level.identifier, SymbolKind::Level));
+                collect_level(level, out);
+            },
+            Instruction::Image(image) => {
+                out.push((image.identifier, SymbolKind::Image));
+                for attribute in &image.attributes {
+                    out.push((*attribute, SymbolKind::Attribute));
+                }
+                collect_level(&image.constructor, out);
+            },
+            _ => ()
+        }
+    }
+}
+
+/// Appends the arguments and body definitions of a LEVEL to `out`.
+fn collect_level(level: &Level, out: &mut Vec<(usize, SymbolKind)>) {
+    for argument in &level.arguments {
+        out.push((*argument, SymbolKind::Argument));
+    }
+    collect(&level.instructions, out);
+}
+
+/// Appends the definition made by a simple instruction to `out`, if any.
+fn collect_simple(simple: &Simple, out: &mut Vec<(usize, SymbolKind)>) {
+    match simple {
+        Simple::InitYou(id, _) => out.push((*id, SymbolKind::You)),
+        Simple::InitYou2(id, _) => out.push((*id, SymbolKind::You2)),
+        Simple::InitGroup(id, _) => out.push((*id, SymbolKind::Group)),
+        Simple::MimicReference(id, _, _) => out.push((*id, SymbolKind::Reference)),
+        Simple::IsEmpty(id) => out.push((*id, SymbolKind::Empty)),
+        Simple::IsLiteral(id, _) => out.push((*id, SymbolKind::Group)),
+        Simple::IsNumber(id, _, _) => out.push((*id, SymbolKind::You)),
+        Simple::IsValue(id, _, _) => out.push((*id, SymbolKind::Value)),
+        _ => ()
+    }
+}
+
+/// Returns the span of the first statement assigning to the identifier,
+/// falling back to the first statement mentioning it. Arguments and attributes
+/// are located at the HAS statement declaring them, if any.
+fn location(id: usize, kind: SymbolKind, statements: &[Statement]) -> Option<(usize, usize)> {
+    let noun = Noun::Identifier(id);
+    let is_target = |statement: &Statement| {
+        statement.action_target == Some(Target::Noun(noun))
+        || statement.action_targets.as_ref().is_some_and(|targets| targets.contains(&noun))
+    };
+    // e.g. `f has x` once `f is level`
+    let declares = |statement: &Statement| {
+        statement.action_type == Verb::Has && is_target(statement) && statements.iter().any(|other| {
+            other.subject == statement.subject 
+            && other.action_type == Verb::Is 
+            && matches!(other.action_target, Some(Target::Noun(Noun::Level | Noun::Image)))
+        })
+    };
+    let declaration = match kind {
+        SymbolKind::Argument | SymbolKind::Attribute => statements.iter().find(|statement| declares(statement)),
+        _ => None
+    };
+    declaration
+        .or_else(|| statements.iter().find(|statement| statement.subject == noun && statement.action_type == Verb::Is))
+        .or_else(|| statements.iter().find(|statement| statement.subject == noun || is_target(statement)))
+        .map(|statement| statement.span)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::symbols::{symbols, Symbol, SymbolKind};
+    use crate::{ast, parse_statements};
+
+    fn symbols_of(source: &str) -> Vec<Symbol> {
+        let (statements, identifiers) = parse_statements(source.as_bytes()).unwrap();
+        let instructions = ast::parse(&statements, &identifiers).unwrap();
+        symbols(&instructions, &statements, &identifiers)
+    }
+
+    #[test]
+    fn symbol_kinds() {
+        let found = symbols_of("
+            baba is you keke is group
+            me is you2 it mimic baba
+            loop is tele loop is done
+            f is level and has x x is you f is done
+            baba is group
+        ");
+        let kinds = found.iter()
+            .map(|symbol| (symbol.name.as_str(), symbol.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, vec![
+            ("baba", SymbolKind::You),
+            ("keke", SymbolKind::Group),
+            ("me", SymbolKind::You2),
+            ("it", SymbolKind::Reference),
+            ("loop", SymbolKind::Tele),
+            ("f", SymbolKind::Level),
+            ("x", SymbolKind::Argument),
+        ]);
+    }
+
+    #[test]
+    fn symbol_locations() {
+        let source = "baba is you\nkeke mimic baba";
+        let found = symbols_of(source);
+        let locations = found.iter()
+            .map(|symbol| symbol.location.map(|(start, end)| &source[start..end]))
+            .collect::<Vec<_>>();
+        assert_eq!(locations, vec![Some("baba is you"), Some("keke mimic baba")]);
+        // Arguments are located where they are declared, not where they are assigned to
+        let source = "f is level\nf has x\nx is you\nf is done";
+        let found = symbols_of(source);
+        let x = found.iter().find(|symbol| symbol.name == "x").unwrap();
+        assert_eq!(x.kind, SymbolKind::Argument);
+        assert_eq!(x.location.map(|(start, end)| &source[start..end]), Some("f has x"));
+    }
+}
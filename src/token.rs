@@ -2,27 +2,41 @@ use std::collections::HashMap;
 
 // Valid tokens
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Noun {
     All,
     Empty,
     Level,
     Image,
-    Identifier(usize)
+    Identifier(usize),
+    // String literal, identified by its quoted spelling
+    Literal(usize),
+    // Decimal number literal, wrapped to the width of YOU objects once executed
+    Number(u32)
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Verb {
     Eat,
+    Equal,
     Fear,
+    Feed,
+    Feel,
     Follow,
     Has,
     Is,
     Make,
     Mimic,
+    Peek,
     Play,
+    Spell,
+    Track,
+    Write,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Property {
     // Primitives
     You,
@@ -36,6 +50,7 @@ pub enum Property {
     // I/O
     Text,
     Word,
+    Line,
     // Program
     Win,
     Defeat,
@@ -45,11 +60,14 @@ pub enum Property {
     Turn,
     Fall,
     More,
+    Less,
     Up,
     Down,
     Left,
     Right,
     Chill,
+    Both,
+    Reset,
     // GROUP
     Shift,
     Sink,
@@ -59,23 +77,29 @@ pub enum Property {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Prefix {
     Idle,
     Lonely,
     Often,
-    Seldom
+    Seldom,
+    Powered
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Conditional {
     On,
     Near,
     Facing,
-    Without
+    Without,
+    Above,
+    Over
 }
 
 /// Every valid Baba token is a subset of Token.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Token {
     Noun(Noun),
     Verb(Verb),
@@ -83,10 +107,12 @@ pub enum Token {
     Prefix(Prefix),
     Not,
     And,
+    Or,
     Conditional(Conditional)
 }
 
-/// Parses a char slice into the associated token. Returns None if the slice is empty.
+/// Parses a char slice into the associated token. Returns None if the slice is empty
+/// or isn't valid UTF-8.
 /// If the token is a newly seen identifier, associates the identifier with an integer
 /// in the HashMap provided.
 /// 
@@ -100,7 +126,15 @@ pub fn parse<'a>(buffer: &'a [u8], identifiers: &mut HashMap<usize, String>) ->
         None
     }
     else {
-        let raw = std::str::from_utf8(buffer).unwrap();
+        let raw = std::str::from_utf8(buffer).ok()?;
+        if raw.starts_with('"') {
+            // String literals are case sensitive
+            return Some(Token::Noun(Noun::Literal(intern(raw, identifiers))));
+        }
+        if raw.bytes().all(|byte| byte.is_ascii_digit()) {
+            // Numbers that don't fit in 32 bits aren't tokens (the lexer reports them)
+            return Some(Token::Noun(Noun::Number(raw.parse().ok()?)));
+        }
         let id: &str = &raw.to_ascii_lowercase(); // Language is case independent
         let token = match id {
             // Noun keywords
@@ -110,13 +144,20 @@ pub fn parse<'a>(buffer: &'a [u8], identifiers: &mut HashMap<usize, String>) ->
             "image" => Token::Noun(Noun::Image),
             // Verb keywords
             "eat" => Token::Verb(Verb::Eat),
+            "equal" => Token::Verb(Verb::Equal),
             "fear" => Token::Verb(Verb::Fear),
+            "feed" => Token::Verb(Verb::Feed),
+            "feel" => Token::Verb(Verb::Feel),
             "follow" => Token::Verb(Verb::Follow),
             "has" => Token::Verb(Verb::Has),
             "is" => Token::Verb(Verb::Is),
             "make" => Token::Verb(Verb::Make),
             "mimic" => Token::Verb(Verb::Mimic),
+            "peek" => Token::Verb(Verb::Peek),
             "play" => Token::Verb(Verb::Play),
+            "spell" => Token::Verb(Verb::Spell),
+            "track" => Token::Verb(Verb::Track),
+            "write" => Token::Verb(Verb::Write),
             // Property keywords
             // - Initializers
             "you" => Token::Property(Property::You),
@@ -128,6 +169,7 @@ pub fn parse<'a>(buffer: &'a [u8], identifiers: &mut HashMap<usize, String>) ->
             // - I/O
             "text" => Token::Property(Property::Text),
             "word" => Token::Property(Property::Word),
+            "line" => Token::Property(Property::Line),
             // - Program
             "win" => Token::Property(Property::Win),
             "defeat" => Token::Property(Property::Defeat),
@@ -139,11 +181,14 @@ pub fn parse<'a>(buffer: &'a [u8], identifiers: &mut HashMap<usize, String>) ->
             "turn" => Token::Property(Property::Turn),
             "fall" => Token::Property(Property::Fall),
             "more" => Token::Property(Property::More),
+            "less" => Token::Property(Property::Less),
             "right" => Token::Property(Property::Right),
             "up" => Token::Property(Property::Up),
             "left" => Token::Property(Property::Left),
             "down" => Token::Property(Property::Down),
             "chill" => Token::Property(Property::Chill),
+            "both" => Token::Property(Property::Both),
+            "reset" => Token::Property(Property::Reset),
             // - Group
             "shift" => Token::Property(Property::Shift),
             "sink" => Token::Property(Property::Sink),
@@ -155,8 +200,11 @@ pub fn parse<'a>(buffer: &'a [u8], identifiers: &mut HashMap<usize, String>) ->
             "lonely" => Token::Prefix(Prefix::Lonely),
             "often" => Token::Prefix(Prefix::Often),
             "seldom" => Token::Prefix(Prefix::Seldom),
+            "powered" => Token::Prefix(Prefix::Powered),
             // "And"
             "and" => Token::And,
+            // "Or"
+            "or" => Token::Or,
             // "Not"
             "not" => Token::Not,
             // "Conditional" keywords
@@ -164,37 +212,120 @@ pub fn parse<'a>(buffer: &'a [u8], identifiers: &mut HashMap<usize, String>) ->
             "near" => Token::Conditional(Conditional::Near),
             "on" => Token::Conditional(Conditional::On),
             "without" => Token::Conditional(Conditional::Without),
+            "above" => Token::Conditional(Conditional::Above),
+            "over" => Token::Conditional(Conditional::Over),
             // Everything else (identifiers)
-            _ => {
-                let mut unique = true;
-                let mut existing_id = 0;
-                for (value, identifier) in identifiers.iter() {
-                    if &id == &identifier {
-                        existing_id = *value;
-                        unique = false;
-                        break;
-                    }
-                }
-                if unique {
-                    let new_id = identifiers.len();
-                    // For new strings, the unique identifier is just the length 
-                    // of the set, i.e. each identifier is one grer than the previous.
-                    identifiers.insert(new_id, id.to_string());
-                    Token::Noun(Noun::Identifier(new_id))
-                }
-                else {
-                    Token::Noun(Noun::Identifier(existing_id))
-                }
-            }
+            _ => Token::Noun(Noun::Identifier(intern(id, identifiers)))
         };
         Some(token)
     }
 }
 
+/// Returns the unique ID associated with a name, associating a new one 
+/// in the HashMap provided if the name hasn't been seen yet.
+fn intern(name: &str, identifiers: &mut HashMap<usize, String>) -> usize {
+    let mut unique = true;
+    let mut existing_id = 0;
+    for (value, identifier) in identifiers.iter() {
+        if identifier.as_str() == name {
+            existing_id = *value;
+            unique = false;
+            break;
+        }
+    }
+    if unique {
+        let new_id = identifiers.len();
+        // For new strings, the unique identifier is just the length 
+        // of the set, i.e. each identifier is one grer than the previous.
+        identifiers.insert(new_id, name.to_string());
+        new_id
+    }
+    else {
+        existing_id
+    }
+}
+
 /// Token parsing tests
+/// Returns the canonical spelling of a token, such that parsing the spelling
+/// yields the same token again.
+/// 
+/// # Arguments
+/// 
+/// * `token` - The token to spell.
+/// 
+/// * `identifiers` - A HashMap that associates each unique token identifer to an usize.
+pub fn spelling(token: &Token, identifiers: &HashMap<usize, String>) -> String {
+    match token {
+        Token::Noun(Noun::All) => String::from("all"),
+        Token::Noun(Noun::Empty) => String::from("empty"),
+        Token::Noun(Noun::Level) => String::from("level"),
+        Token::Noun(Noun::Image) => String::from("image"),
+        Token::Verb(Verb::Eat) => String::from("eat"),
+        Token::Verb(Verb::Equal) => String::from("equal"),
+        Token::Verb(Verb::Fear) => String::from("fear"),
+        Token::Verb(Verb::Feed) => String::from("feed"),
+        Token::Verb(Verb::Feel) => String::from("feel"),
+        Token::Verb(Verb::Follow) => String::from("follow"),
+        Token::Verb(Verb::Has) => String::from("has"),
+        Token::Verb(Verb::Is) => String::from("is"),
+        Token::Verb(Verb::Make) => String::from("make"),
+        Token::Verb(Verb::Mimic) => String::from("mimic"),
+        Token::Verb(Verb::Peek) => String::from("peek"),
+        Token::Verb(Verb::Play) => String::from("play"),
+        Token::Verb(Verb::Spell) => String::from("spell"),
+        Token::Verb(Verb::Track) => String::from("track"),
+        Token::Verb(Verb::Write) => String::from("write"),
+        Token::Property(Property::You) => String::from("you"),
+        Token::Property(Property::You2) => String::from("you2"),
+        Token::Property(Property::Group) => String::from("group"),
+        Token::Property(Property::Tele) => String::from("tele"),
+        Token::Property(Property::Float) => String::from("float"),
+        Token::Property(Property::Text) => String::from("text"),
+        Token::Property(Property::Word) => String::from("word"),
+        Token::Property(Property::Line) => String::from("line"),
+        Token::Property(Property::Win) => String::from("win"),
+        Token::Property(Property::Defeat) => String::from("defeat"),
+        Token::Property(Property::Sleep) => String::from("sleep"),
+        Token::Property(Property::Done) => String::from("done"),
+        Token::Property(Property::Move) => String::from("move"),
+        Token::Property(Property::Turn) => String::from("turn"),
+        Token::Property(Property::Fall) => String::from("fall"),
+        Token::Property(Property::More) => String::from("more"),
+        Token::Property(Property::Less) => String::from("less"),
+        Token::Property(Property::Right) => String::from("right"),
+        Token::Property(Property::Up) => String::from("up"),
+        Token::Property(Property::Left) => String::from("left"),
+        Token::Property(Property::Down) => String::from("down"),
+        Token::Property(Property::Chill) => String::from("chill"),
+        Token::Property(Property::Both) => String::from("both"),
+        Token::Property(Property::Reset) => String::from("reset"),
+        Token::Property(Property::Shift) => String::from("shift"),
+        Token::Property(Property::Sink) => String::from("sink"),
+        Token::Property(Property::Swap) => String::from("swap"),
+        Token::Property(Property::Power) => String::from("power"),
+        Token::Prefix(Prefix::Idle) => String::from("idle"),
+        Token::Prefix(Prefix::Lonely) => String::from("lonely"),
+        Token::Prefix(Prefix::Often) => String::from("often"),
+        Token::Prefix(Prefix::Seldom) => String::from("seldom"),
+        Token::Prefix(Prefix::Powered) => String::from("powered"),
+        Token::And => String::from("and"),
+        Token::Or => String::from("or"),
+        Token::Not => String::from("not"),
+        Token::Conditional(Conditional::Facing) => String::from("facing"),
+        Token::Conditional(Conditional::Near) => String::from("near"),
+        Token::Conditional(Conditional::On) => String::from("on"),
+        Token::Conditional(Conditional::Without) => String::from("without"),
+        Token::Conditional(Conditional::Above) => String::from("above"),
+        Token::Conditional(Conditional::Over) => String::from("over"),
+        Token::Noun(Noun::Identifier(id)) => identifiers[id].clone(),
+        Token::Noun(Noun::Literal(id)) => identifiers[id].clone(),
+        Token::Noun(Noun::Number(number)) => number.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::token::{parse, Token, Noun, Verb, Property, Prefix, Conditional};
+    use crate::token::{parse, spelling, Token, Noun, Verb, Property, Prefix, Conditional};
     use std::collections::HashMap;
     #[test]
     fn parse_keywords_all() {
@@ -315,9 +446,9 @@ mod tests {
                 Token::Noun(Noun::Identifier(2)),
                 Token::Noun(Noun::Identifier(3)),
                 Token::Noun(Noun::Identifier(4)),
+                Token::Noun(Noun::Number(0)),
                 Token::Noun(Noun::Identifier(5)),
-                Token::Noun(Noun::Identifier(6)),
-                Token::Noun(Noun::Identifier(7))
+                Token::Noun(Noun::Identifier(6))
             ]
         )
     }
@@ -384,14 +515,52 @@ mod tests {
                 Token::Noun(Noun::Identifier(2)),
                 Token::Noun(Noun::Identifier(0)),
                 Token::And,
+                Token::Noun(Noun::Number(4)),
                 Token::Noun(Noun::Identifier(3)),
-                Token::Noun(Noun::Identifier(4)),
                 Token::Verb(Verb::Is),
                 Token::Noun(Noun::Identifier(1)),
                 Token::Noun(Noun::Identifier(0)),
                 Token::Noun(Noun::Empty),
-                Token::Noun(Noun::Identifier(5))
+                Token::Noun(Noun::Identifier(4))
             ]
         )
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn parse_number() {
+        let mut identifiers = HashMap::new();
+        assert_eq!(parse(b"65", &mut identifiers), Some(Token::Noun(Noun::Number(65))));
+        assert_eq!(parse(b"300", &mut identifiers), Some(Token::Noun(Noun::Number(300))));
+        assert_eq!(parse(b"4294967295", &mut identifiers), Some(Token::Noun(Noun::Number(u32::MAX))));
+        assert_eq!(parse(b"4294967296", &mut identifiers), None);
+        assert_eq!(parse(b"0065", &mut identifiers), Some(Token::Noun(Noun::Number(65))));
+        assert!(identifiers.is_empty());
+    }
+
+    #[test]
+    fn parse_literal() {
+        let mut identifiers = HashMap::new();
+        let baba = parse(b"baba", &mut identifiers).unwrap();
+        let literal = parse(b"\"Baba\"", &mut identifiers).unwrap();
+        let other = parse(b"\"baba\"", &mut identifiers).unwrap();
+        assert_eq!(baba, Token::Noun(Noun::Identifier(0)));
+        assert_eq!(literal, Token::Noun(Noun::Literal(1)));
+        assert_eq!(other, Token::Noun(Noun::Literal(2)));
+        assert_eq!(parse(b"\"Baba\"", &mut identifiers).unwrap(), literal);
+        assert_eq!(identifiers[&1], "\"Baba\"");
+    }
+
+    #[test]
+    fn spelling_round_trip() {
+        let string = "all empty level image eat equal fear feed feel follow has is make mimic peek play spell track write 
+        you you2 group tele float text word line win defeat sleep done move turn fall more less 
+        right up left down chill both reset shift sink swap power idle lonely often seldom powered and or not 
+        facing near on without above over baba keke \"Baba\\n\" 65";
+
+        let mut identifiers = HashMap::new();
+        for word in string.split_ascii_whitespace() {
+            let token = parse(word.as_bytes(), &mut identifiers).unwrap();
+            assert_eq!(spelling(&token, &identifiers), word);
+        }
+    }
+}